@@ -0,0 +1,227 @@
+/*
+    LocalOrderbook benchmark, tick keyed BTreeMap vs the previous Vec implementation
+
+    Replays a stream of Bybit `orderbook.<depth>.<symbol>` messages through both books.
+    Point ORDERBOOK_RECORDING at a JSONL file of raw WebSocket messages (one message per
+    line, the first `snapshot` seeds the book) to replay a real session, e.g.
+
+        (echo '{"op":"subscribe","args":["orderbook.500.BTCUSDT"]}'; cat) \
+            | websocat wss://stream.bybit.com/v5/public/linear > btcusdt.jsonl
+
+        ORDERBOOK_RECORDING=btcusdt.jsonl ORDERBOOK_TICK=0.1 cargo bench --bench local_orderbook
+
+    Without a recording a seeded random walk in the same wire format is generated for
+    200, 500 and 1000 level books.
+*/
+
+use std::env;
+use std::fs;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rust_qts::decimal::{Price, Qty};
+use rust_qts::exchange::bybit::response::{
+    BybitWebSocketOrderbookData,
+    BybitWebSocketOrderbookResponse,
+    LocalOrderbook,
+};
+
+/// f64 level the Vec book stored
+struct VecLevel {
+    price: f64,
+    size: f64,
+}
+
+/// The Vec based book as it was before the BTreeMap rewrite, kept here as the baseline
+struct VecOrderbook {
+    bids: Vec<VecLevel>,  // Sorted by price descending
+    asks: Vec<VecLevel>,  // Sorted by price ascending
+}
+
+impl VecOrderbook {
+    fn new() -> Self {
+        Self { bids: Vec::new(), asks: Vec::new() }
+    }
+
+    fn apply_snapshot(&mut self, snapshot: &BybitWebSocketOrderbookData) {
+        self.bids.clear();
+        self.asks.clear();
+        for (price, size) in &snapshot.b {
+            if size.is_positive() {
+                self.bids.push(VecLevel { price: price.to_f64(), size: size.to_f64() });
+            }
+        }
+        for (price, size) in &snapshot.a {
+            if size.is_positive() {
+                self.asks.push(VecLevel { price: price.to_f64(), size: size.to_f64() });
+            }
+        }
+        self.bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
+        self.asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
+    }
+
+    fn apply_delta(&mut self, delta: &BybitWebSocketOrderbookData) {
+        for (price, size) in &delta.b {
+            self.update_bid_level(price.to_f64(), size.to_f64());
+        }
+        for (price, size) in &delta.a {
+            self.update_ask_level(price.to_f64(), size.to_f64());
+        }
+    }
+
+    fn update_bid_level(&mut self, price: f64, size: f64) {
+        if size == 0.0 {
+            self.bids.retain(|level| level.price != price);
+        } else if let Some(pos) = self.bids.iter().position(|level| level.price == price) {
+            self.bids[pos].size = size;
+        } else {
+            self.bids.push(VecLevel { price, size });
+            self.bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
+        }
+    }
+
+    fn update_ask_level(&mut self, price: f64, size: f64) {
+        if size == 0.0 {
+            self.asks.retain(|level| level.price != price);
+        } else if let Some(pos) = self.asks.iter().position(|level| level.price == price) {
+            self.asks[pos].size = size;
+        } else {
+            self.asks.push(VecLevel { price, size });
+            self.asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
+        }
+    }
+
+    fn spread(&self) -> Option<f64> {
+        match (self.bids.first(), self.asks.first()) {
+            (Some(bid), Some(ask)) => Some(ask.price - bid.price),
+            _ => None,
+        }
+    }
+}
+
+/// A snapshot followed by the deltas to replay on top of it
+struct Session {
+    name: String,
+    tick_size: Price,
+    snapshot: BybitWebSocketOrderbookData,
+    deltas: Vec<BybitWebSocketOrderbookData>,
+}
+
+/// Load a recorded session, skipping subscription acks and anything before the first snapshot
+fn load_recording(path: &str, tick_size: Price) -> Session {
+    let content = fs::read_to_string(path).expect("failed to read ORDERBOOK_RECORDING");
+    let mut snapshot = None;
+    let mut deltas = Vec::new();
+
+    for line in content.lines() {
+        let Ok(message) = serde_json::from_str::<BybitWebSocketOrderbookResponse>(line) else {
+            continue;
+        };
+        match (message.data_type.as_str(), &snapshot) {
+            ("snapshot", None) => snapshot = Some(message.data),
+            ("delta", Some(_)) => deltas.push(message.data),
+            _ => {}
+        }
+    }
+
+    Session {
+        name: format!("recorded/{}", path),
+        tick_size,
+        snapshot: snapshot.expect("recording has no snapshot message"),
+        deltas,
+    }
+}
+
+/// Level with a size in whole lots of 0.001
+fn level(price_ticks: i64, tick_size: Price, lots: i64) -> (Price, Qty) {
+    (Price::from_ticks(price_ticks, tick_size), Qty::from_raw(lots * Qty::lot_step(3).raw()))
+}
+
+/// Random walk book with Bybit-like deltas: a handful of levels per message,
+/// mostly near the top, roughly a third of them removals
+fn synthetic_session(depth: i64, num_deltas: usize) -> Session {
+    let tick_size: Price = "0.1".parse().unwrap();
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut mid: i64 = 1_000_000; // 100000.0 in 0.1 ticks
+
+    let snapshot = BybitWebSocketOrderbookData {
+        s: "BTCUSDT".to_string(),
+        b: (1..=depth).map(|i| level(mid - i, tick_size, rng.gen_range(1..5000))).collect(),
+        a: (1..=depth).map(|i| level(mid + i, tick_size, rng.gen_range(1..5000))).collect(),
+        u: 1,
+        seq: 1,
+    };
+
+    let mut deltas = Vec::with_capacity(num_deltas);
+    for n in 0..num_deltas {
+        mid += rng.gen_range(-2..=2);
+        let side = |sign: i64, rng: &mut StdRng| -> Vec<(Price, Qty)> {
+            (0..rng.gen_range(0..10))
+                .map(|_| {
+                    // Skew updates towards the top of the book
+                    let distance = (rng.gen_range(0.0f64..1.0).powi(3) * depth as f64) as i64 + 1;
+                    let size = if rng.gen_bool(0.3) { 0 } else { rng.gen_range(1..5000) };
+                    level(mid + sign * distance, tick_size, size)
+                })
+                .collect()
+        };
+        let b = side(-1, &mut rng);
+        let a = side(1, &mut rng);
+        deltas.push(BybitWebSocketOrderbookData {
+            s: "BTCUSDT".to_string(),
+            b,
+            a,
+            u: n as u64 + 2,
+            seq: n as u64 + 2,
+        });
+    }
+
+    Session { name: format!("synthetic/{}", depth), tick_size, snapshot, deltas }
+}
+
+fn sessions() -> Vec<Session> {
+    if let Ok(path) = env::var("ORDERBOOK_RECORDING") {
+        let tick = env::var("ORDERBOOK_TICK").unwrap_or_else(|_| "0.1".to_string());
+        let tick_size = tick.parse().expect("ORDERBOOK_TICK is not a decimal");
+        vec![load_recording(&path, tick_size)]
+    } else {
+        [200, 500, 1000].into_iter().map(|depth| synthetic_session(depth, 10_000)).collect()
+    }
+}
+
+fn bench_local_orderbook(c: &mut Criterion) {
+    let mut group = c.benchmark_group("local_orderbook");
+
+    for session in sessions() {
+        group.bench_with_input(BenchmarkId::new("vec", &session.name), &session, |bench, session| {
+            bench.iter(|| {
+                let mut book = VecOrderbook::new();
+                book.apply_snapshot(&session.snapshot);
+                for delta in &session.deltas {
+                    book.apply_delta(delta);
+                    black_box(book.spread());
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("btree", &session.name), &session, |bench, session| {
+            bench.iter(|| {
+                let mut book = LocalOrderbook::new(session.snapshot.s.clone(), session.tick_size).unwrap();
+                let snapshot = &session.snapshot;
+                book.apply_snapshot_levels(&snapshot.b, &snapshot.a, snapshot.u, snapshot.seq).unwrap();
+                for delta in &session.deltas {
+                    book.apply_delta_levels(&delta.b, &delta.a, delta.u, delta.seq).unwrap();
+                    black_box(book.spread());
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_local_orderbook);
+criterion_main!(benches);
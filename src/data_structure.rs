@@ -1,14 +1,109 @@
 use serde::{Deserialize, Serialize};
-use crate::enums::{InstrumentType, Exchanges};
+use crate::enums::{InstrumentType, Exchanges, OrderType, Side, TimeInForce};
+use crate::decimal::{Price, Qty, Rounding};
 
 
+/*
+    Normalized exchange data, returned by every ExchangeRest implementation
+*/
+
+#[derive(Debug, Clone)]
 pub struct QTSOrderBook{
     pub symbol: String,
-    pub asks: Vec<(String,String)>,
-    pub bids: Vec<(String,String)>,
+    pub asks: Vec<(Price,Qty)>,
+    pub bids: Vec<(Price,Qty)>,
     pub time:i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct QTSTicker{
+    pub symbol: String,
+    pub last_price: Price,
+    pub mark_price: Option<Price>,      // not every venue returns these on the ticker endpoint
+    pub bid_price: Option<Price>,
+    pub ask_price: Option<Price>,
+    pub volume_24h: Qty,
+    pub time: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSPosition{
+    pub symbol: String,
+    pub exchange: Exchanges,
+    pub size: Qty,                      // signed, negative when short
+    pub entry_price: Price,
+    pub mark_price: Price,
+    pub unrealized_pnl: f64,            // in the quote currency, like the margin figures
+    pub time: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSBalance{
+    pub exchange: Exchanges,
+    pub coin: String,
+    pub wallet_balance: f64,
+    pub available_balance: f64,
+    pub unrealized_pnl: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSOpenOrder{
+    pub symbol: String,
+    pub exchange: Exchanges,
+    pub order_id: String,
+    pub client_order_id: String,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Qty,
+    pub filled_qty: Qty,
+    pub status: String,
+    pub reduce_only: bool,
+    pub time: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSOrderRequest{
+    pub side: Side,
+    pub order_type: OrderType,
+    pub price: Option<Price>,           // None for market orders
+    pub qty: Qty,
+    pub time_in_force: TimeInForce,
+    pub client_order_id: String,
+    pub reduce_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSFill{
+    pub symbol: String,
+    pub exchange: Exchanges,
+    pub order_id: String,
+    pub client_order_id: String,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Qty,
+    pub filled_qty: Qty,                // order's cumulative fill, this one included
+    pub fee: f64,                       // in the quote currency, negative for a rebate
+    pub is_maker: bool,
+    pub time: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSMargin{
+    pub exchange: Exchanges,
+    pub margin_balance: f64,            // equity the margin is measured against, in the quote currency
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+    pub margin_ratio: f64,              // maintenance margin / margin balance, liquidation at 1
+    pub time: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QTSOrderAck{
+    pub exchange: Exchanges,
+    pub order_id: String,
+    pub client_order_id: String,
+}
+
 #[derive(Deserialize)]
 pub struct APIKey {
     pub api_name: String,
@@ -21,8 +116,23 @@ pub struct Symbol{
     pub symbol: String,
     pub exchange: Exchanges,
     pub instrument_type: InstrumentType,
-    pub min_tick: f64,
+    pub min_tick: Price,
     pub qty_decimal: u32,
 }
 
+impl Symbol {
+    /// Snap a bid down onto the tick grid, so a post-only bid never ends up more aggressive
+    pub fn round_bid(&self, price: Price) -> Price {
+        price.round_to_tick(self.min_tick, Rounding::Down)
+    }
+
+    /// Snap an ask up onto the tick grid
+    pub fn round_ask(&self, price: Price) -> Price {
+        price.round_to_tick(self.min_tick, Rounding::Up)
+    }
 
+    /// Truncate a size to the lot step, never rounding up past what was asked for
+    pub fn round_qty(&self, qty: Qty) -> Qty {
+        qty.round_to_lot(self.qty_decimal, Rounding::Down)
+    }
+}
@@ -0,0 +1,303 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/*
+    Fixed-point Price and Qty
+
+    Venues send prices and sizes as decimal strings ("65000.1", "0.001") and expect the same
+    back on order entry. Both types hold an integer count of 1e-8 units, so parsing and
+    printing round-trips exactly and a price rounded to Symbol.min_tick stays on the tick,
+    which is what keeps post-only quotes from being rejected or priced through.
+
+    Serialized as the exchange string format, deserialized from either a string or a JSON/YAML
+    number (config files write min_tick as 0.1).
+*/
+
+/// Number of decimal places both types carry
+pub const DECIMALS: u32 = 8;
+const SCALE: i64 = 10i64.pow(DECIMALS);
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum DecimalError {
+    #[error("invalid decimal '{0}'")]
+    Invalid(String),
+    #[error("'{0}' has more than 8 decimal places")]
+    TooPrecise(String),
+    #[error("'{0}' is out of range")]
+    OutOfRange(String),
+}
+
+/// Direction used when snapping to a tick or lot step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,       // towards negative infinity, bids
+    Up,         // towards positive infinity, asks
+    Nearest,    // half rounds up
+}
+
+/// Parse an exchange decimal string into 1e-8 units
+fn parse_scaled(s: &str) -> Result<i64, DecimalError> {
+    let invalid = || DecimalError::Invalid(s.to_string());
+    let out_of_range = || DecimalError::OutOfRange(s.to_string());
+
+    let trimmed = s.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(invalid());
+    }
+    if !int_part.bytes().all(|b| b.is_ascii_digit()) || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    // Trailing zeros beyond 8 places are fine ("0.100000000")
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.len() > DECIMALS as usize {
+        return Err(DecimalError::TooPrecise(s.to_string()));
+    }
+
+    let int_value: i64 = if int_part.is_empty() { 0 } else { int_part.parse().map_err(|_| out_of_range())? };
+    let frac_value: i64 = if frac_part.is_empty() {
+        0
+    } else {
+        frac_part.parse::<i64>().map_err(|_| invalid())? * 10i64.pow(DECIMALS - frac_part.len() as u32)
+    };
+    let value = int_value.checked_mul(SCALE)
+        .and_then(|v| v.checked_add(frac_value))
+        .ok_or_else(out_of_range)?;
+
+    Ok(if negative { -value } else { value })
+}
+
+/// Print 1e-8 units in the exchange string format, without trailing zeros
+fn format_scaled(value: i64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let sign = if value < 0 { "-" } else { "" };
+    let abs = value.unsigned_abs();
+    let int_part = abs / SCALE as u64;
+    let frac_part = abs % SCALE as u64;
+
+    if frac_part == 0 {
+        write!(f, "{}{}", sign, int_part)
+    } else {
+        let frac = format!("{:08}", frac_part);
+        write!(f, "{}{}.{}", sign, int_part, frac.trim_end_matches('0'))
+    }
+}
+
+/// Snap 1e-8 units onto a multiple of `step`
+fn round_scaled(value: i64, step: i64, mode: Rounding) -> i64 {
+    assert!(step > 0, "rounding step must be positive, got {}", step);
+    let floor = value.div_euclid(step) * step;
+    let remainder = value.rem_euclid(step);
+    match mode {
+        Rounding::Down => floor,
+        Rounding::Up if remainder == 0 => floor,
+        Rounding::Up => floor + step,
+        Rounding::Nearest if remainder * 2 >= step => floor + step,
+        Rounding::Nearest => floor,
+    }
+}
+
+/// Shared implementation of the two fixed-point types
+macro_rules! fixed_point {
+    ($name:ident, $what:literal) => {
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(i64);
+
+        impl $name {
+            pub const ZERO: $name = $name(0);
+
+            /// Build from a raw count of 1e-8 units
+            pub const fn from_raw(raw: i64) -> Self {
+                $name(raw)
+            }
+
+            /// Raw count of 1e-8 units
+            pub const fn raw(self) -> i64 {
+                self.0
+            }
+
+            /// Convert from a float, rounding to the nearest 1e-8
+            /// Only meant for values computed in floating point (fair values, hedge ratios),
+            /// anything coming from an exchange should be parsed from its string
+            pub fn from_f64(value: f64) -> Result<Self, DecimalError> {
+                let scaled = (value * SCALE as f64).round();
+                if !scaled.is_finite() || scaled.abs() >= i64::MAX as f64 {
+                    return Err(DecimalError::OutOfRange(value.to_string()));
+                }
+                Ok($name(scaled as i64))
+            }
+
+            /// Lossy conversion for analytics and display
+            pub fn to_f64(self) -> f64 {
+                self.0 as f64 / SCALE as f64
+            }
+
+            pub fn is_zero(self) -> bool {
+                self.0 == 0
+            }
+
+            pub fn is_positive(self) -> bool {
+                self.0 > 0
+            }
+
+            pub fn is_negative(self) -> bool {
+                self.0 < 0
+            }
+
+            pub fn abs(self) -> Self {
+                $name(self.0.abs())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = DecimalError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_scaled(s).map($name)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                format_scaled(self.0, f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), self)
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $name(-self.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: $name) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: $name) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl std::iter::Sum for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> $name {
+                iter.fold($name::ZERO, |acc, x| acc + x)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct FixedPointVisitor;
+
+                impl Visitor<'_> for FixedPointVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        write!(f, "a {} as a decimal string or number", $what)
+                    }
+
+                    fn visit_str<E: de::Error>(self, v: &str) -> Result<$name, E> {
+                        v.parse().map_err(E::custom)
+                    }
+
+                    fn visit_i64<E: de::Error>(self, v: i64) -> Result<$name, E> {
+                        v.checked_mul(SCALE).map($name)
+                            .ok_or_else(|| E::custom(DecimalError::OutOfRange(v.to_string())))
+                    }
+
+                    fn visit_u64<E: de::Error>(self, v: u64) -> Result<$name, E> {
+                        i64::try_from(v).ok().and_then(|v| v.checked_mul(SCALE)).map($name)
+                            .ok_or_else(|| E::custom(DecimalError::OutOfRange(v.to_string())))
+                    }
+
+                    fn visit_f64<E: de::Error>(self, v: f64) -> Result<$name, E> {
+                        // Go through the shortest round-trip string, so 0.1 becomes exactly 0.1
+                        v.to_string().parse().map_err(E::custom)
+                    }
+                }
+
+                deserializer.deserialize_any(FixedPointVisitor)
+            }
+        }
+    };
+}
+
+fixed_point!(Price, "price");
+fixed_point!(Qty, "quantity");
+
+impl Price {
+    /// Snap onto the tick grid (`Symbol.min_tick`)
+    pub fn round_to_tick(self, tick: Price, mode: Rounding) -> Price {
+        Price(round_scaled(self.0, tick.0, mode))
+    }
+
+    /// Whether the price sits exactly on the tick grid
+    pub fn is_on_tick(self, tick: Price) -> bool {
+        tick.0 > 0 && self.0 % tick.0 == 0
+    }
+
+    /// Number of ticks from zero, rounded to the nearest tick
+    pub fn to_ticks(self, tick: Price) -> i64 {
+        round_scaled(self.0, tick.0, Rounding::Nearest) / tick.0
+    }
+
+    /// Price of a tick index
+    pub fn from_ticks(ticks: i64, tick: Price) -> Price {
+        Price(ticks * tick.0)
+    }
+
+    /// Quote value of `qty` at this price, as a float since it is only used for risk and PnL
+    pub fn notional(self, qty: Qty) -> f64 {
+        self.to_f64() * qty.to_f64()
+    }
+}
+
+impl Qty {
+    /// Smallest order size step for a symbol with `qty_decimal` decimals
+    pub fn lot_step(qty_decimal: u32) -> Qty {
+        Qty(10i64.pow(DECIMALS.saturating_sub(qty_decimal)))
+    }
+
+    /// Snap onto the lot step (`Symbol.qty_decimal`)
+    pub fn round_to_lot(self, qty_decimal: u32, mode: Rounding) -> Qty {
+        Qty(round_scaled(self.0, Qty::lot_step(qty_decimal).0, mode))
+    }
+}
@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum StrategyTypes {
     #[serde(rename = "SPREAD")]
@@ -13,7 +14,40 @@ pub enum StrategyTypes {
 }
 
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Written as 0, 1 or 2 in the config
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "u32", into = "u32")]
+pub enum HedgeMode {
+    NoHedge,        // 0, track the residual, never trade
+    Active,         // 1, IOC through the touch
+    Passive,        // 2, resting limit behind the touch
+}
+
+impl TryFrom<u32> for HedgeMode {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(HedgeMode::NoHedge),
+            1 => Ok(HedgeMode::Active),
+            2 => Ok(HedgeMode::Passive),
+            _ => Err(format!("hedge_mode must be 0, 1 or 2, got {}", value)),
+        }
+    }
+}
+
+impl From<HedgeMode> for u32 {
+    fn from(mode: HedgeMode) -> Self {
+        match mode {
+            HedgeMode::NoHedge => 0,
+            HedgeMode::Active => 1,
+            HedgeMode::Passive => 2,
+        }
+    }
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum Exchanges {
     #[serde(rename = "bybit")]
     Bybit,
@@ -44,3 +78,49 @@ pub enum InstrumentType {
     #[serde(rename = "OPTIONS")]
     Options
 }
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    #[serde(rename = "Buy")]
+    Buy,
+    #[serde(rename = "Sell")]
+    Sell
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OrderType {
+    #[serde(rename = "Market")]
+    Market,
+    #[serde(rename = "Limit")]
+    Limit
+}
+
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    #[serde(rename = "GTC")]
+    GTC,
+    #[serde(rename = "IOC")]
+    IOC,
+    #[serde(rename = "FOK")]
+    FOK,
+    #[serde(rename = "PostOnly")]
+    PostOnly
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub enum Environment {
+    #[default]
+    #[serde(rename = "mainnet")]
+    Mainnet,
+    #[serde(rename = "testnet")]
+    Testnet,
+    #[serde(rename = "demo")]
+    Demo,
+    #[serde(rename = "custom")]
+    Custom(String)     // base URL, e.g. a local mock server
+}
@@ -0,0 +1,178 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use url::Url;
+
+use crate::data_structure::{QTSBalance, QTSMargin, QTSOpenOrder, QTSOrderAck, QTSOrderBook, QTSOrderRequest, QTSPosition, QTSTicker, Symbol};
+use crate::enums::{Environment, Exchanges};
+use crate::exchange::binance::binance_rest::BinanceRestClient;
+use crate::exchange::bybit::bybit_rest::BybitRestClient;
+use crate::exchange::error::{ExchangeError, ExchangeResult};
+
+/*
+    Common REST interface across venues
+
+    Each exchange client keeps its own venue specific methods (and raw response structs),
+    and implements this trait on top of them so the strategy layer only ever deals with
+    the normalized QTS types in data_structure.rs and the Symbol from the config.
+*/
+
+#[async_trait]
+pub trait ExchangeRest: Send + Sync {
+    /// Venue this client talks to
+    fn exchange(&self) -> Exchanges;
+
+    // Public Data Endpoints
+    async fn get_orderbook(&self, symbol: &Symbol) -> ExchangeResult<QTSOrderBook>;
+    async fn get_ticker(&self, symbol: &Symbol) -> ExchangeResult<QTSTicker>;
+
+    // Private Data Endpoints
+    async fn get_positions(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSPosition>>;
+    async fn get_balances(&self) -> ExchangeResult<Vec<QTSBalance>>;
+    async fn get_open_orders(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSOpenOrder>>;
+    async fn get_margin(&self) -> ExchangeResult<QTSMargin>;
+
+    // Order Entry Endpoints, orders are addressed by our client order id
+    async fn place_order(&self, symbol: &Symbol, order: &QTSOrderRequest) -> ExchangeResult<QTSOrderAck>;
+    async fn cancel_order(&self, symbol: &Symbol, client_order_id: &str) -> ExchangeResult<QTSOrderAck>;
+    async fn cancel_all_orders(&self, symbol: &Symbol) -> ExchangeResult<()>;
+
+    /// Place several orders, one result per order in request order. Venues without a batch
+    /// endpoint send them one at a time, all at once
+    async fn place_orders(&self, symbol: &Symbol, orders: &[QTSOrderRequest]) -> Vec<ExchangeResult<QTSOrderAck>> {
+        join_all(orders.iter().map(|order| self.place_order(symbol, order))).await
+    }
+
+    /// Cancel several orders, one result per order in request order
+    async fn cancel_orders(&self, symbol: &Symbol, client_order_ids: &[String]) -> Vec<ExchangeResult<QTSOrderAck>> {
+        join_all(client_order_ids.iter().map(|client_order_id| self.cancel_order(symbol, client_order_id))).await
+    }
+}
+
+
+/// Parse an amount the venue sends as a string, empty means nothing (e.g. rates on a classic Bybit account)
+pub(crate) fn parse_amount(field: &str, value: &str) -> ExchangeResult<f64> {
+    if value.is_empty() {
+        return Ok(0.0);
+    }
+    value.parse().map_err(|_| ExchangeError::Decode(format!("Invalid {}: {}", field, value)))
+}
+
+/// Maintenance margin over margin balance, any maintenance margin without a balance left is a breach
+pub(crate) fn margin_ratio(maintenance_margin: f64, margin_balance: f64) -> f64 {
+    if margin_balance > 0.0 {
+        maintenance_margin / margin_balance
+    } else if maintenance_margin > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    }
+}
+
+
+/// Build the REST client for a venue, e.g. from `EEConfig.quote_instrument.exchange`
+pub fn new_rest_client(exchange: &Exchanges, api_key: String, api_secret: String) -> ExchangeResult<Box<dyn ExchangeRest>> {
+    RestClientBuilder::new(api_key, api_secret).build(exchange)
+}
+
+
+pub const DEFAULT_RECV_WINDOW_MS: u64 = 5000;
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/*
+    REST client builder
+
+    Same settings for every venue, the environment is resolved to that venue's host:
+
+        let client = RestClientBuilder::new(api_key, api_secret)
+            .environment(Environment::Testnet)
+            .recv_window_ms(10_000)
+            .timeout(Duration::from_secs(3))
+            .build(&Exchanges::Bybit)?;
+
+    Environment::Custom points a client at any base URL, e.g. a local mock server.
+*/
+
+#[derive(Debug, Clone)]
+pub struct RestClientBuilder {
+    api_key: String,
+    api_secret: String,
+    environment: Environment,
+    recv_window_ms: u64,
+    timeout: Duration,            // whole request, connect included
+    connect_timeout: Duration,
+}
+
+impl RestClientBuilder {
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        RestClientBuilder {
+            api_key,
+            api_secret,
+            environment: Environment::Mainnet,
+            recv_window_ms: DEFAULT_RECV_WINDOW_MS,
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+        }
+    }
+
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// How long after its timestamp a signed request stays valid on the venue
+    pub fn recv_window_ms(mut self, recv_window_ms: u64) -> Self {
+        self.recv_window_ms = recv_window_ms;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    fn http_client(&self) -> ExchangeResult<Client> {
+        // Both venues reject a recvWindow above 60s
+        if self.recv_window_ms == 0 || self.recv_window_ms > 60_000 {
+            return Err(ExchangeError::InvalidRequest(format!("recv_window_ms must be between 1 and 60000, got {}", self.recv_window_ms)));
+        }
+        if let Environment::Custom(url) = &self.environment
+            && Url::parse(url).is_err() {
+            return Err(ExchangeError::InvalidRequest(format!("Invalid custom base URL: {}", url)));
+        }
+
+        Client::builder()
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .build()
+            .map_err(|e| ExchangeError::InvalidRequest(format!("Failed to build HTTP client: {}", e)))
+    }
+
+    pub fn build_bybit(self) -> ExchangeResult<BybitRestClient> {
+        let http_client = self.http_client()?;
+        let base_url = BybitRestClient::get_base_url(&self.environment);
+        Ok(BybitRestClient::from_parts(self.api_key, self.api_secret, base_url, self.recv_window_ms, http_client))
+    }
+
+    pub fn build_binance(self) -> ExchangeResult<BinanceRestClient> {
+        let http_client = self.http_client()?;
+        let base_url = BinanceRestClient::get_base_url(&self.environment);
+        Ok(BinanceRestClient::from_parts(self.api_key, self.api_secret, base_url, self.recv_window_ms, http_client))
+    }
+
+    /// Build the client for a venue behind the common interface
+    pub fn build(self, exchange: &Exchanges) -> ExchangeResult<Box<dyn ExchangeRest>> {
+        match exchange {
+            Exchanges::Bybit => Ok(Box::new(self.build_bybit()?)),
+            Exchanges::Binance => Ok(Box::new(self.build_binance()?)),
+            _ => Err(ExchangeError::InvalidRequest(format!("No REST client implemented for {:?}", exchange))),
+        }
+    }
+}
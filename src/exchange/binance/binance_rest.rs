@@ -1,11 +1,10 @@
 
 
-use std::error::Error;
-use reqwest::{Client, Response, header::{HeaderMap, HeaderValue}};
-use serde::{Deserialize, Serialize};
+use reqwest::{Client, Method, Response, StatusCode, header::{HeaderMap, HeaderValue}};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 use hex;
+use async_trait::async_trait;
 
 // Import response structs
 use crate::exchange::binance::response::{
@@ -14,31 +13,83 @@ use crate::exchange::binance::response::{
     BinanceTickersResponse, 
     BinanceTickerItem,
     BinancePositionResponse, 
-    BinancePositionItem
+    BinancePositionItem,
+    BinanceOpenOrdersResponse,
+    BinanceOpenOrderItem,
+    BinanceBalanceResponse,
+    BinanceBalanceItem,
+    BinanceNewOrderRequest,
+    BinanceOrderResponse,
+    BinanceCancelAllResponse,
+    BinanceErrorResponse
 };
+use crate::exchange::error::{ExchangeError, ExchangeResult};
+use crate::exchange::base_rest::{margin_ratio, parse_amount, ExchangeRest, DEFAULT_RECV_WINDOW_MS};
+use crate::data_structure::{QTSBalance, QTSMargin, QTSOpenOrder, QTSOrderAck, QTSOrderBook, QTSOrderRequest, QTSPosition, QTSTicker, Symbol};
+use crate::decimal::{Price, Qty};
+use crate::enums::{Environment, Exchanges, OrderType, Side};
 
 
 pub struct BinanceRestClient {
     api_key: String,
     api_secret: String,
     base_url: String,
+    recv_window: String,
     http_client: Client
 }
 
 
 impl BinanceRestClient {
     
-    // Binance Rest client Constructor
+    // Binance Rest client Constructor, mainnet with default settings
+    // Use RestClientBuilder for testnet, demo, a custom URL, recv_window or timeouts
     pub fn new(api_key:String, api_secret:String) -> Self{
         BinanceRestClient { 
-            api_key: api_key,
-            api_secret: api_secret,
-            base_url: "https://fapi.binance.com".to_string(), 
+            api_key,
+            api_secret,
+            base_url: Self::get_base_url(&Environment::Mainnet), 
+            recv_window: DEFAULT_RECV_WINDOW_MS.to_string(),
             http_client:  Client::new()
         }
     }
 
-    fn generate_binance_signature(&self, query_string: &str) -> Result<String, Box<dyn Error>> {
+    // Binance Rest client Constructor against the futures testnet
+    pub fn new_testnet(api_key:String, api_secret:String) -> Self{
+        BinanceRestClient { 
+            api_key,
+            api_secret,
+            base_url: Self::get_base_url(&Environment::Testnet), 
+            recv_window: DEFAULT_RECV_WINDOW_MS.to_string(),
+            http_client:  Client::new()
+        }
+    }
+
+    // Constructor used by RestClientBuilder
+    pub(crate) fn from_parts(api_key: String, api_secret: String, base_url: String, recv_window_ms: u64, http_client: Client) -> Self {
+        BinanceRestClient {
+            api_key,
+            api_secret,
+            base_url,
+            recv_window: recv_window_ms.to_string(),
+            http_client
+        }
+    }
+
+    // USD-M futures REST host for each environment
+    pub fn get_base_url(environment: &Environment) -> String {
+        match environment {
+            Environment::Mainnet => "https://fapi.binance.com".to_string(),
+            Environment::Testnet => "https://testnet.binancefuture.com".to_string(),
+            Environment::Demo => "https://demo-fapi.binance.com".to_string(),
+            Environment::Custom(url) => url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn generate_binance_signature(&self, query_string: &str) -> ExchangeResult<String> {
         /*
             Generate HMAC SHA256 signature for Binance API
             Based on: https://binance-docs.github.io/apidocs/futures/en/#signed-endpoint-examples-for-post-fapi-v1-order-hmac-keys
@@ -52,7 +103,7 @@ impl BinanceRestClient {
         Ok(signature)
     }
 
-    pub fn get_binance_auth_headers(&self) -> Result<HeaderMap, Box<dyn Error>> {
+    pub fn get_binance_auth_headers(&self) -> ExchangeResult<HeaderMap> {
         /*
             Generate authentication headers for Binance API
         */
@@ -64,12 +115,53 @@ impl BinanceRestClient {
         Ok(headers)
     }
 
+    async fn read_response(response: Response) -> ExchangeResult<String> {
+        /*
+            Binance signals errors with a 4xx/5xx status and a {"code", "msg"} body,
+            successful bodies carry no status field so they are returned as text
+            https://binance-docs.github.io/apidocs/futures/en/#error-codes
+        */
+        let status = response.status();
+        let retry_after_ms = response.headers().get("Retry-After")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(|seconds| seconds * 1000);
+        let body = response.text().await?;
+
+        if status.is_success() {
+            return Ok(body);
+        }
+
+        let exchange = Exchanges::Binance;
+        match serde_json::from_str::<BinanceErrorResponse>(&body) {
+            Ok(error) => {
+                let (code, msg) = (error.code, error.msg);
+                Err(match code {
+                    -1021 => ExchangeError::Timestamp { exchange, code, msg },
+                    // invalid signature, bad key format, invalid key/IP/permissions
+                    -1022 | -2014 | -2015 => ExchangeError::Auth { exchange, code, msg },
+                    // too many requests, too many orders
+                    -1003 | -1015 => ExchangeError::RateLimited { exchange, code, msg, retry_after_ms },
+                    // 429 warns, 418 means the IP is already banned
+                    _ if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::IM_A_TEAPOT => {
+                        ExchangeError::RateLimited { exchange, code, msg, retry_after_ms }
+                    }
+                    _ => ExchangeError::Rejected { exchange, code, msg },
+                })
+            }
+            Err(_) if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::IM_A_TEAPOT => {
+                Err(ExchangeError::RateLimited { exchange, code: status.as_u16() as i64, msg: body, retry_after_ms })
+            }
+            Err(_) => Err(ExchangeError::Http { status: status.as_u16(), body }),
+        }
+    }
+
 
 
     
 
     // Public Data Endpoints
-    pub async fn get_orderbook(&self, symbol: &str, limit: Option<u32>) -> Result<BinanceOrderbookResponse, Box<dyn Error>> {
+    pub async fn get_orderbook(&self, symbol: &str, limit: Option<u32>) -> ExchangeResult<BinanceOrderbookResponse> {
         /*
             Get orderbook data for a symbol
             https://binance-docs.github.io/apidocs/futures/en/#order-book
@@ -84,18 +176,19 @@ impl BinanceRestClient {
             if valid_limits.contains(&limit) {
                 url.push_str(&format!("&limit={}", limit));
             } else {
-                return Err("Invalid limit. Must be one of: 5, 10, 20, 50, 100, 500, 1000".into());
+                return Err(ExchangeError::InvalidRequest("Invalid limit. Must be one of: 5, 10, 20, 50, 100, 500, 1000".to_string()));
             }
         }
         
         // Query API Endpoint (no authentication required for public data)
         let response = self.http_client.get(&url).send().await?;
-        let api_response = response.json::<BinanceOrderbookResponse>().await?;
+        let response_text = Self::read_response(response).await?;
+        let api_response: BinanceOrderbookResponse = serde_json::from_str(&response_text)?;
         
         Ok(api_response)
     }
 
-    pub async fn get_tickers(&self, symbol: Option<&str>) -> Result<BinanceTickersResponse, Box<dyn Error>> {
+    pub async fn get_tickers(&self, symbol: Option<&str>) -> ExchangeResult<BinanceTickersResponse> {
         /*
             Get 24hr ticker price change statistics
             https://binance-docs.github.io/apidocs/futures/en/#24hr-ticker-price-change-statistics
@@ -112,7 +205,7 @@ impl BinanceRestClient {
         let response = self.http_client.get(&url).send().await?;
         
         // Handle the response - Binance returns either a single object or array
-        let response_text = response.text().await?;
+        let response_text = Self::read_response(response).await?;
         
         // Try to parse as array first (multiple tickers)
         if let Ok(tickers_array) = serde_json::from_str::<Vec<BinanceTickerResponse>>(&response_text) {
@@ -163,17 +256,17 @@ impl BinanceRestClient {
     }
 
     // Private Data Endpoints
-    pub async fn get_positions(&self, symbol: Option<&str>) -> Result<BinancePositionResponse, Box<dyn Error>> {
+    pub async fn get_positions(&self, symbol: Option<&str>) -> ExchangeResult<BinancePositionResponse> {
         /*
             Get current position information
             https://binance-docs.github.io/apidocs/futures/en/#position-information-v3-user_data
         */
         
-        let mut url = format!("{}/fapi/v3/positionRisk", self.base_url);
+        let url = format!("{}/fapi/v3/positionRisk", self.base_url);
         
         // Generate timestamp and recvWindow
         let timestamp = chrono::Utc::now().timestamp_millis();
-        let recv_window = "5000";
+        let recv_window = self.recv_window.as_str();
         
         // Build query parameters
         let mut query_params = vec![
@@ -214,8 +307,7 @@ impl BinanceRestClient {
 
         
         // Parse response - Binance returns an array directly, not wrapped in a result object
-        let response_text = response.text().await?;
-        println!("Raw API response: {}", response_text);
+        let response_text = Self::read_response(response).await?;
         let positions: Vec<BinancePositionItem> = serde_json::from_str(&response_text)?;
         
         Ok(BinancePositionResponse {
@@ -223,4 +315,345 @@ impl BinanceRestClient {
         })
     }
 
-}
\ No newline at end of file
+    pub async fn get_open_orders(&self, symbol: Option<&str>) -> ExchangeResult<BinanceOpenOrdersResponse> {
+        /*
+            Get all open orders on a symbol, or on every symbol when none is provided
+            https://binance-docs.github.io/apidocs/futures/en/#current-all-open-orders-user_data
+        */
+        
+        let url = format!("{}/fapi/v1/openOrders", self.base_url);
+        
+        // Generate timestamp and recvWindow
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let recv_window = self.recv_window.as_str();
+        
+        // Build query parameters
+        let mut query_params = vec![
+            ("timestamp".to_string(), timestamp.to_string()),
+            ("recvWindow".to_string(), recv_window.to_string()),
+        ];
+        
+        // Add optional symbol parameter if provided
+        if let Some(symbol) = symbol {
+            query_params.push(("symbol".to_string(), symbol.to_string()));
+        }
+        
+        // Create query string for signature
+        let query_string = query_params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+        
+        // Generate signature
+        let signature = self.generate_binance_signature(&query_string)?;
+        
+        // Build final URL with signature
+        let final_url = format!("{}?{}&signature={}", url, query_string, signature);
+        
+        // Create headers
+        let headers = self.get_binance_auth_headers()?;
+        
+        // Make the request
+        let response = self
+            .http_client
+            .get(&final_url)
+            .headers(headers)
+            .send()
+            .await?;
+        
+        // Parse response - Binance returns an array directly
+        let response_text = Self::read_response(response).await?;
+        let orders: Vec<BinanceOpenOrderItem> = serde_json::from_str(&response_text)?;
+        
+        Ok(BinanceOpenOrdersResponse {
+            list: orders
+        })
+    }
+
+    pub async fn get_account_info(&self) -> ExchangeResult<BinanceBalanceResponse> {
+        /*
+            Binance calls this the futures account balance, but internally we will
+            handle this as account info (same as Bybit wallet balance)
+            https://binance-docs.github.io/apidocs/futures/en/#futures-account-balance-v3-user_data
+        */
+        
+        let url = format!("{}/fapi/v3/balance", self.base_url);
+        
+        // Generate timestamp and recvWindow
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let recv_window = self.recv_window.as_str();
+        
+        let query_string = format!("timestamp={}&recvWindow={}", timestamp, recv_window);
+        
+        // Generate signature
+        let signature = self.generate_binance_signature(&query_string)?;
+        
+        // Build final URL with signature
+        let final_url = format!("{}?{}&signature={}", url, query_string, signature);
+        
+        // Create headers
+        let headers = self.get_binance_auth_headers()?;
+        
+        // Make the request
+        let response = self
+            .http_client
+            .get(&final_url)
+            .headers(headers)
+            .send()
+            .await?;
+        
+        // Parse response - Binance returns an array directly
+        let response_text = Self::read_response(response).await?;
+        let balances: Vec<BinanceBalanceItem> = serde_json::from_str(&response_text)?;
+        
+        Ok(BinanceBalanceResponse {
+            list: balances
+        })
+    }
+
+    // Order Entry Endpoints
+    async fn send_signed(&self, method: Method, endpoint: &str, mut params: Vec<(String, String)>) -> ExchangeResult<String> {
+        /*
+            Order entry is signed the same way as the reads, every parameter goes
+            in the query string and the body stays empty
+         */
+
+        let url = format!("{}{}", self.base_url, endpoint);
+
+        params.push(("timestamp".to_string(), chrono::Utc::now().timestamp_millis().to_string()));
+        params.push(("recvWindow".to_string(), self.recv_window.clone()));
+
+        let query_string = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let signature = self.generate_binance_signature(&query_string)?;
+        let final_url = format!("{}?{}&signature={}", url, query_string, signature);
+        let headers = self.get_binance_auth_headers()?;
+
+        let response = self
+            .http_client
+            .request(method, &final_url)
+            .headers(headers)
+            .send()
+            .await?;
+
+        Self::read_response(response).await
+    }
+
+    fn order_lookup(symbol: &str, order_id: Option<u64>, orig_client_order_id: Option<&str>) -> ExchangeResult<Vec<(String, String)>> {
+        let mut params = vec![("symbol".to_string(), symbol.to_string())];
+        match (order_id, orig_client_order_id) {
+            (Some(order_id), _) => params.push(("orderId".to_string(), order_id.to_string())),
+            (None, Some(client_order_id)) => params.push(("origClientOrderId".to_string(), client_order_id.to_string())),
+            (None, None) => return Err(ExchangeError::InvalidRequest("Either orderId or origClientOrderId must be provided".to_string())),
+        }
+        Ok(params)
+    }
+
+    pub async fn place_order(&self, request: &BinanceNewOrderRequest) -> ExchangeResult<BinanceOrderResponse> {
+        /*
+            https://binance-docs.github.io/apidocs/futures/en/#new-order-trade
+         */
+        if request.price.is_none() && request.orderType == OrderType::Limit {
+            return Err(ExchangeError::InvalidRequest("Price must be provided for Limit orders".to_string()));
+        }
+
+        let response_text = self.send_signed(Method::POST, "/fapi/v1/order", request.params()).await?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    pub async fn modify_order(&self, symbol: &str, order_id: Option<u64>, orig_client_order_id: Option<&str>, side: Side, quantity: Qty, price: Price) -> ExchangeResult<BinanceOrderResponse> {
+        /*
+            Only limit orders can be modified, and side, quantity and price are all required
+            https://binance-docs.github.io/apidocs/futures/en/#modify-order-trade
+         */
+        let mut params = Self::order_lookup(symbol, order_id, orig_client_order_id)?;
+        params.push(("side".to_string(), match side { Side::Buy => "BUY", Side::Sell => "SELL" }.to_string()));
+        params.push(("quantity".to_string(), quantity.to_string()));
+        params.push(("price".to_string(), price.to_string()));
+
+        let response_text = self.send_signed(Method::PUT, "/fapi/v1/order", params).await?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    pub async fn cancel_order(&self, symbol: &str, order_id: Option<u64>, orig_client_order_id: Option<&str>) -> ExchangeResult<BinanceOrderResponse> {
+        /*
+            https://binance-docs.github.io/apidocs/futures/en/#cancel-order-trade
+         */
+        let params = Self::order_lookup(symbol, order_id, orig_client_order_id)?;
+
+        let response_text = self.send_signed(Method::DELETE, "/fapi/v1/order", params).await?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    pub async fn cancel_all_orders(&self, symbol: &str) -> ExchangeResult<BinanceCancelAllResponse> {
+        /*
+            https://binance-docs.github.io/apidocs/futures/en/#cancel-all-open-orders-trade
+         */
+        let params = vec![("symbol".to_string(), symbol.to_string())];
+
+        let response_text = self.send_signed(Method::DELETE, "/fapi/v1/allOpenOrders", params).await?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+}
+
+
+#[async_trait]
+impl ExchangeRest for BinanceRestClient {
+
+    fn exchange(&self) -> Exchanges {
+        Exchanges::Binance
+    }
+
+    async fn get_orderbook(&self, symbol: &Symbol) -> ExchangeResult<QTSOrderBook> {
+        let response = BinanceRestClient::get_orderbook(self, &symbol.symbol, None).await?;
+
+        Ok(QTSOrderBook {
+            symbol: symbol.symbol.clone(),
+            asks: response.asks,
+            bids: response.bids,
+            time: response.T as i64,
+        })
+    }
+
+    async fn get_ticker(&self, symbol: &Symbol) -> ExchangeResult<QTSTicker> {
+        let response = self.get_tickers(Some(&symbol.symbol)).await?;
+
+        let ticker = response.list.into_iter().next()
+            .ok_or_else(|| ExchangeError::Decode(format!("No ticker returned for {}", symbol.symbol)))?;
+
+        // The 24hr ticker carries no mark or top of book prices
+        Ok(QTSTicker {
+            symbol: ticker.symbol,
+            last_price: ticker.lastPrice.parse()?,
+            mark_price: None,
+            bid_price: None,
+            ask_price: None,
+            volume_24h: ticker.volume.parse()?,
+            time: ticker.closeTime as i64,
+        })
+    }
+
+    async fn get_positions(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSPosition>> {
+        let response = BinanceRestClient::get_positions(self, Some(&symbol.symbol)).await?;
+
+        // positionAmt is already signed
+        response.list.into_iter().map(|position| Ok(QTSPosition {
+            exchange: Exchanges::Binance,
+            size: position.positionAmt.parse()?,
+            entry_price: position.entryPrice.parse()?,
+            mark_price: position.markPrice.parse()?,
+            time: position.updateTime as i64,
+            unrealized_pnl: parse_amount("unRealizedProfit", &position.unRealizedProfit)?,
+            symbol: position.symbol,
+        })).collect()
+    }
+
+    async fn get_balances(&self) -> ExchangeResult<Vec<QTSBalance>> {
+        let response = self.get_account_info().await?;
+
+        response.list.into_iter().map(|balance| Ok(QTSBalance {
+            exchange: Exchanges::Binance,
+            wallet_balance: parse_amount("balance", &balance.balance)?,
+            available_balance: parse_amount("availableBalance", &balance.availableBalance)?,
+            unrealized_pnl: parse_amount("crossUnPnl", &balance.crossUnPnl)?,
+            coin: balance.asset,
+        })).collect()
+    }
+
+    async fn get_open_orders(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSOpenOrder>> {
+        let response = BinanceRestClient::get_open_orders(self, Some(&symbol.symbol)).await?;
+
+        response.list.into_iter().map(|order| Ok(QTSOpenOrder {
+            exchange: Exchanges::Binance,
+            order_id: order.orderId.to_string(),
+            side: if order.side == "SELL" { Side::Sell } else { Side::Buy },
+            price: order.price.parse()?,
+            qty: order.origQty.parse()?,
+            filled_qty: order.executedQty.parse()?,
+            time: order.time as i64,
+            symbol: order.symbol,
+            client_order_id: order.clientOrderId,
+            status: order.status,
+            reduce_only: order.reduceOnly,
+        })).collect()
+    }
+
+    async fn get_margin(&self) -> ExchangeResult<QTSMargin> {
+        let positions = BinanceRestClient::get_positions(self, None).await?;
+        let balances = self.get_account_info().await?;
+
+        // Margin requirements come with the positions, the balance is the cross wallet of their
+        // margin assets plus its unrealized PnL
+        let mut margin_assets: Vec<&str> = positions.list.iter().map(|position| position.marginAsset.as_str()).collect();
+        if margin_assets.is_empty() {
+            margin_assets.push("USDT");
+        }
+
+        let mut initial_margin = 0.0;
+        let mut maintenance_margin = 0.0;
+        let mut time = 0;
+        for position in &positions.list {
+            initial_margin += parse_amount("initialMargin", &position.initialMargin)?;
+            maintenance_margin += parse_amount("maintMargin", &position.maintMargin)?;
+            time = time.max(position.updateTime as i64);
+        }
+
+        let mut margin_balance = 0.0;
+        for balance in balances.list.iter().filter(|balance| margin_assets.contains(&balance.asset.as_str())) {
+            margin_balance += parse_amount("crossWalletBalance", &balance.crossWalletBalance)?;
+            margin_balance += parse_amount("crossUnPnl", &balance.crossUnPnl)?;
+            time = time.max(balance.updateTime as i64);
+        }
+
+        Ok(QTSMargin {
+            exchange: Exchanges::Binance,
+            margin_balance,
+            initial_margin,
+            maintenance_margin,
+            margin_ratio: margin_ratio(maintenance_margin, margin_balance),
+            time,
+        })
+    }
+
+    async fn place_order(&self, symbol: &Symbol, order: &QTSOrderRequest) -> ExchangeResult<QTSOrderAck> {
+        let request = BinanceNewOrderRequest {
+            symbol: symbol.symbol.clone(),
+            side: order.side,
+            orderType: order.order_type,
+            quantity: order.qty,
+            price: order.price,
+            // Market orders take no timeInForce
+            timeInForce: if order.order_type == OrderType::Limit { Some(order.time_in_force) } else { None },
+            newClientOrderId: Some(order.client_order_id.clone()),
+            reduceOnly: if order.reduce_only { Some(true) } else { None },
+        };
+        let response = BinanceRestClient::place_order(self, &request).await?;
+
+        Ok(QTSOrderAck {
+            exchange: Exchanges::Binance,
+            order_id: response.orderId.to_string(),
+            client_order_id: response.clientOrderId,
+        })
+    }
+
+    async fn cancel_order(&self, symbol: &Symbol, client_order_id: &str) -> ExchangeResult<QTSOrderAck> {
+        let response = BinanceRestClient::cancel_order(self, &symbol.symbol, None, Some(client_order_id)).await?;
+
+        Ok(QTSOrderAck {
+            exchange: Exchanges::Binance,
+            order_id: response.orderId.to_string(),
+            client_order_id: response.clientOrderId,
+        })
+    }
+
+    async fn cancel_all_orders(&self, symbol: &Symbol) -> ExchangeResult<()> {
+        BinanceRestClient::cancel_all_orders(self, &symbol.symbol).await?;
+        Ok(())
+    }
+}
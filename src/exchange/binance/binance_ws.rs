@@ -0,0 +1,349 @@
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use futures_util::{SinkExt, StreamExt};
+use serde_json;
+use url::Url;
+use std::error::Error;
+
+use crate::decimal::Price;
+use crate::exchange::binance::binance_rest::BinanceRestClient;
+use crate::exchange::binance::response::{
+    BinanceWebSocketAggTrade,
+    BinanceWebSocketDepthUpdate,
+    BinanceWebSocketSubscription,
+    BinanceWebSocketSubscriptionResponse,
+    BinanceLocalOrderbook,
+    BinanceDepthUpdateStatus,
+};
+
+/// Get the USD-M futures market stream URL
+///
+/// # Arguments
+/// * `is_testnet` - Whether to use testnet or mainnet URLs
+///
+/// # Returns
+/// The WebSocket URL as a String
+pub fn get_url(is_testnet: bool) -> String {
+    if is_testnet {
+        "wss://stream.binancefuture.com/ws".to_string()
+    } else {
+        "wss://fstream.binance.com/ws".to_string()
+    }
+}
+
+/// WebSocket connection configuration
+#[derive(Debug, Clone)]
+pub struct BinanceWebSocketConfig {
+    /// Whether to use testnet
+    pub is_testnet: bool,
+    /// Custom stream URL (e.g. a local mock server), takes precedence over `is_testnet`
+    pub base_url: Option<String>,
+}
+
+impl Default for BinanceWebSocketConfig {
+    fn default() -> Self {
+        Self {
+            is_testnet: true, // Default to testnet for safety
+            base_url: None,
+        }
+    }
+}
+
+/// WebSocket message types that can be received
+#[derive(Debug, Clone)]
+pub enum BinanceWebSocketMessage {
+    /// Diff depth update
+    DepthUpdate(BinanceWebSocketDepthUpdate),
+    /// Aggregate trade
+    AggTrade(BinanceWebSocketAggTrade),
+    /// Subscription confirmation
+    Subscription(BinanceWebSocketSubscriptionResponse),
+    /// Ping message
+    Ping,
+    /// Pong message
+    Pong,
+    /// Error message
+    Error(String),
+}
+
+/// Main WebSocket client for Binance USD-M futures market streams
+///
+/// There is no reconnect, the message channel closes with the connection and it is up to the
+/// caller to connect again or fall back to REST
+pub struct BinanceWebSocketClient {
+    /// Configuration for the WebSocket connection
+    config: BinanceWebSocketConfig,
+    /// Sender channel for sending messages to the WebSocket
+    sender: Option<mpsc::UnboundedSender<BinanceWebSocketSubscription>>,
+    /// Id of the next subscribe/unsubscribe request
+    next_request_id: u64,
+}
+
+impl BinanceWebSocketClient {
+    /// Create a new WebSocket client with the given configuration
+    ///
+    /// # Arguments
+    /// * `config` - WebSocket configuration
+    ///
+    /// # Returns
+    /// A new BinanceWebSocketClient instance
+    pub fn new(config: BinanceWebSocketConfig) -> Self {
+        Self {
+            config,
+            sender: None,
+            next_request_id: 1,
+        }
+    }
+
+    /// Connect to the Binance market stream
+    ///
+    /// This method establishes the WebSocket connection and sets up the message channels.
+    /// Messages are queued on the returned receiver until they are read, which is what
+    /// buffers depth diffs while a REST snapshot is in flight.
+    ///
+    /// # Returns
+    /// * `Result<(mpsc::UnboundedSender<BinanceWebSocketSubscription>, mpsc::UnboundedReceiver<BinanceWebSocketMessage>), Box<dyn Error>>`
+    ///   - Sender channel for sending subscription requests
+    ///   - Receiver channel for receiving WebSocket messages
+    ///   - Error if connection fails
+    pub async fn connect(&mut self) -> Result<
+        (
+            mpsc::UnboundedSender<BinanceWebSocketSubscription>,
+            mpsc::UnboundedReceiver<BinanceWebSocketMessage>,
+        ),
+        Box<dyn Error>,
+    > {
+        // Get the WebSocket URL based on configuration
+        let url = match &self.config.base_url {
+            Some(base_url) => base_url.clone(),
+            None => get_url(self.config.is_testnet),
+        };
+        let url = Url::parse(&url)?;
+
+        // Create channels for communication
+        let (tx, mut rx) = mpsc::unbounded_channel::<BinanceWebSocketSubscription>();
+        let (message_tx, message_rx) = mpsc::unbounded_channel::<BinanceWebSocketMessage>();
+
+        // Store the channel for later use
+        self.sender = Some(tx.clone());
+
+        // Connect to the WebSocket
+        let (ws_stream, _) = connect_async(url).await?;
+        let (mut write, mut read) = ws_stream.split();
+
+        // Spawn a task to handle the WebSocket connection
+        let message_tx_clone = message_tx.clone();
+        tokio::spawn(async move {
+            // Handle incoming messages from the WebSocket
+            while let Some(msg) = read.next().await {
+                match msg {
+                    Ok(Message::Text(text)) => {
+                        // Try to parse the message as JSON
+                        if let Ok(parsed) = serde_json::from_str::<BinanceWebSocketDepthUpdate>(&text) {
+                            let _ = message_tx_clone.send(BinanceWebSocketMessage::DepthUpdate(parsed));
+                        } else if let Ok(parsed) = serde_json::from_str::<BinanceWebSocketAggTrade>(&text) {
+                            let _ = message_tx_clone.send(BinanceWebSocketMessage::AggTrade(parsed));
+                        } else if let Ok(parsed) = serde_json::from_str::<BinanceWebSocketSubscriptionResponse>(&text) {
+                            let _ = message_tx_clone.send(BinanceWebSocketMessage::Subscription(parsed));
+                        } else {
+                            let _ = message_tx_clone.send(BinanceWebSocketMessage::Error(format!("Unknown message: {}", text)));
+                        }
+                    }
+                    Ok(Message::Ping(_)) => {
+                        // tungstenite queues the pong reply for us
+                        let _ = message_tx_clone.send(BinanceWebSocketMessage::Ping);
+                    }
+                    Ok(Message::Pong(_)) => {
+                        let _ = message_tx_clone.send(BinanceWebSocketMessage::Pong);
+                    }
+                    Ok(Message::Close(_)) => {
+                        println!("WebSocket connection closed");
+                        break;
+                    }
+                    Err(e) => {
+                        let _ = message_tx_clone.send(BinanceWebSocketMessage::Error(format!("WebSocket error: {}", e)));
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        // Spawn a task to handle outgoing messages
+        tokio::spawn(async move {
+            while let Some(subscription) = rx.recv().await {
+                if let Ok(json) = serde_json::to_string(&subscription)
+                    && let Err(e) = write.send(Message::Text(json)).await {
+                    eprintln!("Failed to send subscription: {}", e);
+                    break;
+                }
+            }
+        });
+
+        Ok((tx, message_rx))
+    }
+
+    /// Send a SUBSCRIBE/UNSUBSCRIBE request for a single stream
+    fn send_request(&mut self, method: &str, stream: String) -> Result<(), Box<dyn Error>> {
+        if let Some(sender) = &self.sender {
+            let subscription = BinanceWebSocketSubscription {
+                method: method.to_string(),
+                params: vec![stream],
+                id: self.next_request_id,
+            };
+            self.next_request_id += 1;
+            sender.send(subscription)?;
+            Ok(())
+        } else {
+            Err("WebSocket not connected".into())
+        }
+    }
+
+    /// Subscribe to the 100ms diff depth stream for a symbol
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "BTCUSDT")
+    ///
+    /// # Returns
+    /// * `Result<(), Box<dyn Error>>` - Error if subscription fails
+    pub async fn subscribe_depth(&mut self, symbol: &str) -> Result<(), Box<dyn Error>> {
+        // Stream names are lowercase
+        let stream = format!("{}@depth@100ms", symbol.to_lowercase());
+        self.send_request("SUBSCRIBE", stream)
+    }
+
+    /// Subscribe to the aggregate trade stream for a symbol
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "BTCUSDT")
+    ///
+    /// # Returns
+    /// * `Result<(), Box<dyn Error>>` - Error if subscription fails
+    pub async fn subscribe_agg_trades(&mut self, symbol: &str) -> Result<(), Box<dyn Error>> {
+        let stream = format!("{}@aggTrade", symbol.to_lowercase());
+        self.send_request("SUBSCRIBE", stream)
+    }
+
+    /// Unsubscribe from the diff depth stream for a symbol
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "BTCUSDT")
+    ///
+    /// # Returns
+    /// * `Result<(), Box<dyn Error>>` - Error if unsubscription fails
+    pub async fn unsubscribe_depth(&mut self, symbol: &str) -> Result<(), Box<dyn Error>> {
+        let stream = format!("{}@depth@100ms", symbol.to_lowercase());
+        self.send_request("UNSUBSCRIBE", stream)
+    }
+}
+
+/// Helper function to run a synced local orderbook from the depth stream
+///
+/// Subscribes to `<symbol>@depth@100ms`, buffers diffs until the first one arrives,
+/// takes the REST snapshot through `BinanceRestClient::get_orderbook`, then keeps the
+/// book in sync. Whenever `pu` continuity breaks the book is re-snapshotted.
+///
+/// # Arguments
+/// * `symbol` - Trading symbol to subscribe to (e.g., "BTCUSDT")
+/// * `limit` - Snapshot depth (5, 10, 20, 50, 100, 500, 1000), None for the venue default
+/// * `min_tick` - Price tick size of the symbol (e.g., 0.1 for BTCUSDT)
+/// * `is_testnet` - Whether to use testnet or mainnet
+///
+/// # Returns
+/// * `Result<(), Box<dyn Error>>` - Error if the example fails
+pub async fn run_orderbook_example(
+    symbol: &str,
+    limit: Option<u32>,
+    min_tick: Price,
+    is_testnet: bool,
+) -> Result<(), Box<dyn Error>> {
+    println!("🚀 Starting Binance WebSocket Orderbook Example");
+    println!("Symbol: {}, Limit: {:?}, Tick: {}, Testnet: {}", symbol, limit, min_tick, is_testnet);
+
+    // Snapshot must come from the same environment as the stream
+    let rest_client = if is_testnet {
+        BinanceRestClient::new_testnet(String::new(), String::new())
+    } else {
+        BinanceRestClient::new(String::new(), String::new())
+    };
+
+    // Create and connect the WebSocket client
+    let config = BinanceWebSocketConfig {
+        is_testnet,
+        ..Default::default()
+    };
+    let mut client = BinanceWebSocketClient::new(config);
+    let (_sender, mut receiver) = client.connect().await?;
+
+    println!("✅ Connected to Binance WebSocket");
+
+    client.subscribe_depth(symbol).await?;
+    println!("📡 Subscribed to {}@depth@100ms", symbol.to_lowercase());
+
+    // Create local orderbook to track state
+    let mut local_orderbook = BinanceLocalOrderbook::new(symbol.to_string(), min_tick)?;
+
+    // Listen for messages
+    let mut message_count = 0;
+    while let Some(message) = receiver.recv().await {
+        match message {
+            BinanceWebSocketMessage::DepthUpdate(update) => {
+                message_count += 1;
+                println!("\n📊 Depth Update #{}", message_count);
+                println!("   Symbol: {}", update.s);
+                println!("   U: {}, u: {}, pu: {}", update.U, update.u, update.pu);
+
+                match local_orderbook.apply_update(update) {
+                    BinanceDepthUpdateStatus::Applied => {
+                        local_orderbook.book.print_summary();
+                        println!("   ---");
+                    }
+                    BinanceDepthUpdateStatus::Dropped => {
+                        println!("⏭️  Dropped update older than snapshot");
+                    }
+                    BinanceDepthUpdateStatus::OutOfSync => {
+                        println!("⚠️  Update sequence broken, waiting for a new snapshot");
+                    }
+                    BinanceDepthUpdateStatus::Buffered => {
+                        println!("📥 Buffered ({} pending)", local_orderbook.buffered());
+                    }
+                }
+
+                // Take the snapshot once at least one diff is buffered, anything that
+                // arrives while the request is in flight queues up on the channel
+                while local_orderbook.needs_snapshot() && local_orderbook.buffered() > 0 {
+                    println!("🔄 Fetching snapshot...");
+                    let snapshot = rest_client.get_orderbook(symbol, limit).await?;
+                    println!("   lastUpdateId: {}", snapshot.lastUpdateId);
+
+                    if local_orderbook.apply_snapshot(&snapshot) == BinanceDepthUpdateStatus::OutOfSync {
+                        println!("⚠️  Snapshot does not line up with buffered updates, retrying");
+                        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                    } else {
+                        local_orderbook.book.print_summary();
+                    }
+                }
+            }
+            // Not subscribed here
+            BinanceWebSocketMessage::AggTrade(_) => {}
+            BinanceWebSocketMessage::Subscription(sub) => {
+                if sub.result.is_none() {
+                    println!("✅ Subscription request {} acknowledged", sub.id);
+                } else {
+                    println!("❌ Subscription request {} returned: {:?}", sub.id, sub.result);
+                }
+            }
+            BinanceWebSocketMessage::Ping => {
+                println!("🏓 Received ping");
+            }
+            BinanceWebSocketMessage::Pong => {
+                println!("🏓 Received pong");
+            }
+            BinanceWebSocketMessage::Error(err) => {
+                println!("❌ Error: {}", err);
+            }
+        }
+    }
+
+    Ok(())
+}
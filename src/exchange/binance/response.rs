@@ -1,4 +1,24 @@
+// Field names mirror the venue JSON, so they stay camelCase
+#![allow(non_snake_case)]
+
 use serde::{Deserialize, Serialize};
+use crate::decimal::{Price, Qty};
+use crate::enums::{OrderType, Side, TimeInForce};
+use crate::exchange::bybit::response::{LocalOrderbook, OrderbookSyncError};
+
+/*
+Binance Error Response
+https://binance-docs.github.io/apidocs/futures/en/#error-codes
+*/
+
+#[derive(Deserialize, Debug)]
+pub struct BinanceErrorResponse {
+    /*
+    Body of every non-2xx response
+     */
+    pub code: i64,
+    pub msg: String,
+}
 
 /*
 Binance Order Book Response
@@ -13,8 +33,8 @@ pub struct BinanceOrderbookResponse {
     pub lastUpdateId: u64,
     pub E: u64,  // Message output time
     pub T: u64,     // Transaction time
-    pub bids: Vec<[String; 2]>,    // Bids array - [price, qty]
-    pub asks: Vec<[String; 2]>,    // Asks array - [price, qty]
+    pub bids: Vec<(Price, Qty)>,    // Bids array - [price, qty]
+    pub asks: Vec<(Price, Qty)>,    // Asks array - [price, qty]
 }
 
 /*
@@ -161,3 +181,348 @@ pub struct BinanceRateLimit {
     pub limit: u32,
     pub count: u32,
 }
+
+/*
+Binance Current All Open Orders (USER_DATA)
+https://binance-docs.github.io/apidocs/futures/en/#current-all-open-orders-user_data
+*/
+
+#[derive(Deserialize, Debug)]
+pub struct BinanceOpenOrdersResponse {
+    /*
+    Open orders response
+    https://binance-docs.github.io/apidocs/futures/en/#current-all-open-orders-user_data
+     */
+    pub list: Vec<BinanceOpenOrderItem>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BinanceOpenOrderItem {
+    /*
+    Individual open order item
+    https://binance-docs.github.io/apidocs/futures/en/#current-all-open-orders-user_data
+     */
+    pub avgPrice: String,
+    pub clientOrderId: String,
+    pub cumQuote: String,
+    pub executedQty: String,
+    pub orderId: u64,
+    pub origQty: String,
+    pub origType: String,
+    pub price: String,
+    pub reduceOnly: bool,
+    pub side: String,                       // BUY or SELL
+    pub positionSide: String,               // BOTH, LONG, SHORT
+    pub status: String,                     // NEW, PARTIALLY_FILLED, ...
+    pub stopPrice: String,
+    pub closePosition: bool,
+    pub symbol: String,
+    pub time: u64,                          // order time
+    pub timeInForce: String,
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub updateTime: u64,
+    pub workingType: String,
+    pub priceProtect: bool
+}
+
+/*
+Binance Futures Account Balance V3 (USER_DATA)
+https://binance-docs.github.io/apidocs/futures/en/#futures-account-balance-v3-user_data
+*/
+
+#[derive(Deserialize, Debug)]
+pub struct BinanceBalanceResponse {
+    /*
+    Account balance response
+    https://binance-docs.github.io/apidocs/futures/en/#futures-account-balance-v3-user_data
+     */
+    pub list: Vec<BinanceBalanceItem>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BinanceBalanceItem {
+    /*
+    Individual asset balance
+    https://binance-docs.github.io/apidocs/futures/en/#futures-account-balance-v3-user_data
+     */
+    pub accountAlias: String,               // unique account code
+    pub asset: String,                      // asset name
+    pub balance: String,                    // wallet balance
+    pub crossWalletBalance: String,         // crossed wallet balance
+    pub crossUnPnl: String,                 // unrealized profit of crossed positions
+    pub availableBalance: String,           // available balance
+    pub maxWithdrawAmount: String,          // maximum amount for transfer out
+    pub marginAvailable: bool,              // whether the asset can be used as margin in Multi-Assets mode
+    pub updateTime: u64
+}
+
+/*
+Binance Order Entry
+https://binance-docs.github.io/apidocs/futures/en/#new-order-trade
+*/
+
+#[derive(Debug, Clone)]
+pub struct BinanceNewOrderRequest {
+    /*
+    Sent as signed query parameters, not JSON
+    https://binance-docs.github.io/apidocs/futures/en/#new-order-trade
+     */
+    pub symbol: String,
+    pub side: Side,
+    pub orderType: OrderType,
+    pub quantity: Qty,
+    pub price: Option<Price>,               // required for Limit orders
+    pub timeInForce: Option<TimeInForce>,   // PostOnly is sent as GTX
+    pub newClientOrderId: Option<String>,   // our client order id
+    pub reduceOnly: Option<bool>,
+}
+
+impl BinanceNewOrderRequest {
+    /// Query parameters in the order they are signed
+    pub fn params(&self) -> Vec<(String, String)> {
+        let mut params = vec![
+            ("symbol".to_string(), self.symbol.clone()),
+            ("side".to_string(), match self.side { Side::Buy => "BUY", Side::Sell => "SELL" }.to_string()),
+            ("type".to_string(), match self.orderType { OrderType::Limit => "LIMIT", OrderType::Market => "MARKET" }.to_string()),
+            ("quantity".to_string(), self.quantity.to_string()),
+        ];
+        if let Some(price) = self.price {
+            params.push(("price".to_string(), price.to_string()));
+        }
+        if let Some(time_in_force) = self.timeInForce {
+            let time_in_force = match time_in_force {
+                TimeInForce::GTC => "GTC",
+                TimeInForce::IOC => "IOC",
+                TimeInForce::FOK => "FOK",
+                TimeInForce::PostOnly => "GTX",
+            };
+            params.push(("timeInForce".to_string(), time_in_force.to_string()));
+        }
+        if let Some(client_order_id) = &self.newClientOrderId {
+            params.push(("newClientOrderId".to_string(), client_order_id.clone()));
+        }
+        if let Some(reduce_only) = self.reduceOnly {
+            params.push(("reduceOnly".to_string(), reduce_only.to_string()));
+        }
+        params
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BinanceOrderResponse {
+    /*
+    Shared by new, modify and cancel order
+    https://binance-docs.github.io/apidocs/futures/en/#new-order-trade
+     */
+    pub orderId: u64,
+    pub clientOrderId: String,
+    pub symbol: String,
+    pub status: String,                     // NEW, PARTIALLY_FILLED, FILLED, CANCELED, EXPIRED
+    pub side: String,                       // BUY or SELL
+    pub price: String,
+    pub avgPrice: String,
+    pub origQty: String,
+    pub executedQty: String,
+    pub cumQuote: String,
+    pub timeInForce: String,
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub reduceOnly: bool,
+    pub updateTime: u64
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BinanceCancelAllResponse {
+    /*
+    https://binance-docs.github.io/apidocs/futures/en/#cancel-all-open-orders-trade
+     */
+    pub code: i64,
+    pub msg: String,
+}
+
+/*
+Binance WebSocket Market Streams
+https://binance-docs.github.io/apidocs/futures/en/#websocket-market-streams
+*/
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BinanceWebSocketSubscription {
+    /*
+    Live subscribe/unsubscribe request
+    https://binance-docs.github.io/apidocs/futures/en/#live-subscribing-unsubscribing-to-streams
+     */
+    pub method: String,     // SUBSCRIBE or UNSUBSCRIBE
+    pub params: Vec<String>,  // stream names e.g. btcusdt@depth@100ms
+    pub id: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BinanceWebSocketSubscriptionResponse {
+    /*
+    Reply to a subscribe/unsubscribe request, result is null on success
+    https://binance-docs.github.io/apidocs/futures/en/#live-subscribing-unsubscribing-to-streams
+     */
+    pub result: Option<serde_json::Value>,
+    pub id: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BinanceWebSocketDepthUpdate {
+    /*
+    Diff book depth stream event
+    https://binance-docs.github.io/apidocs/futures/en/#diff-book-depth-streams
+     */
+    pub e: String,  // Event type, depthUpdate
+    pub E: u64,     // Event time
+    pub T: u64,     // Transaction time
+    pub s: String,  // Symbol
+    pub U: u64,     // First update ID in event
+    pub u: u64,     // Final update ID in event
+    pub pu: u64,    // Final update ID in last stream event
+    pub b: Vec<(Price, Qty)>,  // Bids to be updated - [price, qty]
+    pub a: Vec<(Price, Qty)>,  // Asks to be updated - [price, qty]
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BinanceWebSocketAggTrade {
+    /*
+    Aggregate trade stream event, taker orders filled at one price
+    https://binance-docs.github.io/apidocs/futures/en/#aggregate-trade-streams
+     */
+    pub e: String,  // Event type, aggTrade
+    pub E: u64,     // Event time
+    pub s: String,  // Symbol
+    pub a: u64,     // Aggregate trade ID
+    pub p: Price,   // Price
+    pub q: Qty,     // Quantity
+    pub T: u64,     // Trade time
+    pub m: bool,    // Whether the buyer is the maker
+}
+
+/*
+Binance Orderbook Management
+https://binance-docs.github.io/apidocs/futures/en/#how-to-manage-a-local-order-book-correctly
+*/
+
+/// Result of feeding a depth update into the local orderbook
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinanceDepthUpdateStatus {
+    /// No snapshot yet, the update was buffered
+    Buffered,
+    /// The update was applied to the book
+    Applied,
+    /// The update is older than the snapshot and was dropped
+    Dropped,
+    /// Continuity broken, the book was reset and needs a new snapshot
+    OutOfSync,
+}
+
+/// Local orderbook kept in sync with the diff depth stream
+///
+/// Follows Binance's procedure: buffer diffs, take a REST snapshot, drop
+/// diffs with `u` < `lastUpdateId`, the first applied diff must straddle
+/// `lastUpdateId`, and every following diff's `pu` must equal the previous `u`.
+#[derive(Debug, Clone)]
+pub struct BinanceLocalOrderbook {
+    pub book: LocalOrderbook,
+    buffer: Vec<BinanceWebSocketDepthUpdate>,
+    snapshot_update_id: Option<u64>,  // lastUpdateId of the snapshot in use
+    last_final_update_id: Option<u64>,  // u of the last applied diff
+}
+
+impl BinanceLocalOrderbook {
+    /// Create a new empty orderbook waiting for a snapshot, the tick size must be positive
+    pub fn new(symbol: String, tick_size: Price) -> Result<Self, OrderbookSyncError> {
+        Ok(Self {
+            book: LocalOrderbook::new(symbol, tick_size)?,
+            buffer: Vec::new(),
+            snapshot_update_id: None,
+            last_final_update_id: None,
+        })
+    }
+
+    /// Whether the book is waiting on a REST snapshot
+    pub fn needs_snapshot(&self) -> bool {
+        self.snapshot_update_id.is_none()
+    }
+
+    /// Number of diffs waiting for a snapshot
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Load a REST snapshot and replay the buffered diffs on top of it
+    pub fn apply_snapshot(&mut self, snapshot: &BinanceOrderbookResponse) -> BinanceDepthUpdateStatus {
+        // Off the tick grid, keep the buffered diffs for the next snapshot
+        if self.book.apply_snapshot_levels(&snapshot.bids, &snapshot.asks, snapshot.lastUpdateId, snapshot.lastUpdateId).is_err() {
+            self.snapshot_update_id = None;
+            self.last_final_update_id = None;
+            return BinanceDepthUpdateStatus::OutOfSync;
+        }
+        self.snapshot_update_id = Some(snapshot.lastUpdateId);
+        self.last_final_update_id = None;
+
+        let buffered = std::mem::take(&mut self.buffer);
+        let mut status = BinanceDepthUpdateStatus::Applied;
+        for update in buffered {
+            status = self.apply_update(update);
+            if status == BinanceDepthUpdateStatus::OutOfSync {
+                break;
+            }
+        }
+        status
+    }
+
+    /// Feed a diff from the stream
+    pub fn apply_update(&mut self, update: BinanceWebSocketDepthUpdate) -> BinanceDepthUpdateStatus {
+        let snapshot_update_id = match self.snapshot_update_id {
+            Some(id) => id,
+            None => {
+                self.buffer.push(update);
+                return BinanceDepthUpdateStatus::Buffered;
+            }
+        };
+
+        match self.last_final_update_id {
+            None => {
+                // Drop anything the snapshot already contains
+                if update.u < snapshot_update_id {
+                    return BinanceDepthUpdateStatus::Dropped;
+                }
+                // The first diff must straddle the snapshot
+                if update.U > snapshot_update_id {
+                    self.reset(update);
+                    return BinanceDepthUpdateStatus::OutOfSync;
+                }
+            }
+            Some(last_u) => {
+                if update.pu != last_u {
+                    self.reset(update);
+                    return BinanceDepthUpdateStatus::OutOfSync;
+                }
+            }
+        }
+
+        let applied = self.book.apply_delta_levels(&update.b, &update.a, update.u, update.pu);
+        self.last_final_update_id = Some(update.u);
+
+        // A level off the tick grid can't be keyed, and a crossed book means a diff went
+        // missing somewhere, either way start over
+        if applied.is_err() || self.book.check_crossed().is_err() {
+            self.snapshot_update_id = None;
+            self.last_final_update_id = None;
+            self.buffer.clear();
+            return BinanceDepthUpdateStatus::OutOfSync;
+        }
+        BinanceDepthUpdateStatus::Applied
+    }
+
+    /// Throw away the book and start buffering again from this update
+    fn reset(&mut self, update: BinanceWebSocketDepthUpdate) {
+        self.snapshot_update_id = None;
+        self.last_final_update_id = None;
+        self.buffer.clear();
+        self.buffer.push(update);
+    }
+}
@@ -6,38 +6,94 @@ use crate::exchange::bybit::response::{
     BybitAccInfoResponse,
     BybitWalletBalanceResponse,
     BybitPositionResponse,
-    BybitOpenOrdersResponse
+    BybitOpenOrdersResponse,
+    BybitPlaceOrderRequest,
+    BybitAmendOrderRequest,
+    BybitCancelOrderRequest,
+    BybitCancelAllRequest,
+    BybitOrderResponse,
+    BybitCancelAllResponse,
+    BybitDcpRequest,
+    BybitDcpResponse,
+    BybitBatchRequest,
+    BybitBatchPlaceItem,
+    BybitBatchAmendItem,
+    BybitBatchCancelItem,
+    BybitBatchOrderResponse,
+    BybitRetStatus
 };
-
-use std::error::Error;
-use reqwest::{Client, Response, header::{HeaderMap, HeaderValue}};
-use serde::{Deserialize, Serialize};
+use crate::exchange::base_rest::{parse_amount, ExchangeRest, DEFAULT_RECV_WINDOW_MS};
+use crate::data_structure::{QTSBalance, QTSMargin, QTSOpenOrder, QTSOrderAck, QTSOrderBook, QTSOrderRequest, QTSPosition, QTSTicker, Symbol};
+use crate::enums::{Environment, Exchanges, InstrumentType, OrderType, Side};
+use crate::decimal::Qty;
+use crate::exchange::error::{ExchangeError, ExchangeResult};
+
+use reqwest::{Client, Response, StatusCode, header::{HeaderMap, HeaderValue}};
+use serde::{Serialize, de::DeserializeOwned};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 use hex;
+use async_trait::async_trait;
+use futures::future::join_all;
+
+// Range Bybit accepts for the disconnect-cancel-all window, in seconds
+pub const BYBIT_DCP_MIN_WINDOW: u32 = 3;
+pub const BYBIT_DCP_MAX_WINDOW: u32 = 300;
+// Largest page /v5/order/realtime returns, it defaults to 20
+const BYBIT_OPEN_ORDERS_PAGE_LIMIT: i32 = 50;
+// Pages read before giving up on a cursor that never runs out
+const BYBIT_OPEN_ORDERS_MAX_PAGES: usize = 20;
 
 
 pub struct BybitRestClient {
     api_key: String,
     api_secret: String,
     base_url: String,
+    recv_window: String,
     http_client: Client
 }
 
 
 impl BybitRestClient {
     
-    // Bybit Rest client Constructor
+    // Bybit Rest client Constructor, mainnet with default settings
+    // Use RestClientBuilder for testnet, demo, a custom URL, recv_window or timeouts
     pub fn new(api_key:String, api_secret:String) -> Self{
         BybitRestClient { 
-            api_key: api_key,
-            api_secret: api_secret,
-            base_url: "https://api.bybit.com".to_string(), 
+            api_key,
+            api_secret,
+            base_url: Self::get_base_url(&Environment::Mainnet), 
+            recv_window: DEFAULT_RECV_WINDOW_MS.to_string(),
             http_client:  Client::new()
         }
     }
 
-    fn generate_bybit_signature2<T:Serialize>(&self, payload: &T,timestamp: i64,recv_window: &str) -> Result<String, Box<dyn Error>>{
+    // Constructor used by RestClientBuilder
+    pub(crate) fn from_parts(api_key: String, api_secret: String, base_url: String, recv_window_ms: u64, http_client: Client) -> Self {
+        BybitRestClient {
+            api_key,
+            api_secret,
+            base_url,
+            recv_window: recv_window_ms.to_string(),
+            http_client
+        }
+    }
+
+    // REST host for each environment
+    pub fn get_base_url(environment: &Environment) -> String {
+        match environment {
+            Environment::Mainnet => "https://api.bybit.com".to_string(),
+            Environment::Testnet => "https://api-testnet.bybit.com".to_string(),
+            Environment::Demo => "https://api-demo.bybit.com".to_string(),
+            Environment::Custom(url) => url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn generate_bybit_signature2<T:Serialize>(&self, payload: &T,timestamp: i64,recv_window: &str) -> ExchangeResult<String>{
 
 
         let payload_json = serde_json::to_string(payload)?;
@@ -57,7 +113,7 @@ impl BybitRestClient {
         Ok(signature)
     }
 
-    fn generate_bybit_signature(&self, query_string: &str) -> Result<String, Box<dyn Error>>{
+    fn generate_bybit_signature(&self, query_string: &str) -> ExchangeResult<String>{
 
         // Create HMAC-SHA256 instance with the API secret
         let mut mac = Hmac::<Sha256>::new_from_slice(self.api_secret.as_bytes())?;
@@ -68,12 +124,12 @@ impl BybitRestClient {
     }
 
 
-    pub fn get_bybit_auth_headers(&self, signature: &str, timestamp: i64, recv_window: &str) -> Result<HeaderMap, Box<dyn Error>> {
+    pub fn get_bybit_auth_headers(&self, signature: &str, timestamp: i64, recv_window: &str) -> ExchangeResult<HeaderMap> {
 
         // Construct headers
         let mut headers = HeaderMap::new();
         headers.insert("Content-Type", HeaderValue::from_static("application/json"));
-        headers.insert("X-BAPI-SIGN", HeaderValue::from_str(&signature)?);
+        headers.insert("X-BAPI-SIGN", HeaderValue::from_str(signature)?);
         headers.insert("X-BAPI-API-KEY", HeaderValue::from_str(&self.api_key)?);
         headers.insert("X-BAPI-TIMESTAMP", HeaderValue::from_str(&timestamp.to_string())?);
         headers.insert("X-BAPI-RECV-WINDOW", HeaderValue::from_str(recv_window)?);
@@ -81,10 +137,59 @@ impl BybitRestClient {
         Ok(headers)
     }
 
+    async fn read_response<R: DeserializeOwned>(response: Response) -> ExchangeResult<R> {
+        /*
+            Every v5 response carries retCode/retMsg and a rejected request still comes back
+            as HTTP 200, so retCode is checked before parsing the typed body
+            https://bybit-exchange.github.io/docs/v5/error
+         */
+        let status = response.status();
+        let retry_after_ms = Self::rate_limit_reset_ms(response.headers());
+        let body = response.text().await?;
+
+        // The gateway answers 403 without a JSON body once the IP goes over its limit
+        if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ExchangeError::RateLimited {
+                exchange: Exchanges::Bybit,
+                code: status.as_u16() as i64,
+                msg: body,
+                retry_after_ms,
+            });
+        }
+
+        match serde_json::from_str::<BybitRetStatus>(&body) {
+            Ok(ret) if ret.retCode != 0 => return Err(Self::classify_ret_code(ret.retCode, ret.retMsg, retry_after_ms)),
+            Ok(_) if status.is_success() => {}
+            // The request went through, only the body is not what a v5 response looks like
+            Err(e) if status.is_success() => return Err(ExchangeError::Decode(format!("{}: {}", e, body))),
+            _ => return Err(ExchangeError::Http { status: status.as_u16(), body }),
+        }
+
+        Ok(serde_json::from_str::<R>(&body)?)
+    }
+
+    fn classify_ret_code(code: i64, msg: String, retry_after_ms: Option<u64>) -> ExchangeError {
+        let exchange = Exchanges::Bybit;
+        match code {
+            10002 => ExchangeError::Timestamp { exchange, code, msg },
+            // invalid key, invalid signature, permission denied, auth failed, key/IP mismatch, key expired
+            10003 | 10004 | 10005 | 10007 | 10010 | 33004 => ExchangeError::Auth { exchange, code, msg },
+            // too many visits, IP rate limit, frequency protection
+            10006 | 10018 | 10429 => ExchangeError::RateLimited { exchange, code, msg, retry_after_ms },
+            _ => ExchangeError::Rejected { exchange, code, msg },
+        }
+    }
+
+    fn rate_limit_reset_ms(headers: &HeaderMap) -> Option<u64> {
+        // Epoch ms at which the endpoint limit resets
+        let reset = headers.get("X-Bapi-Limit-Reset-Timestamp")?.to_str().ok()?.parse::<i64>().ok()?;
+        Some((reset - chrono::Utc::now().timestamp_millis()).max(0) as u64)
+    }
+
 
 
     // Private Data Endpoints
-    pub async fn get_account_type(&self) -> Result<BybitAccInfoResponse,Box<dyn Error>>{
+    pub async fn get_account_type(&self) -> ExchangeResult<BybitAccInfoResponse>{
         /*
             Bybits API calls the Account info, but internally we will handle this as 
             account type
@@ -93,21 +198,21 @@ impl BybitRestClient {
         let url = format!("{}/v5/account/info", self.base_url);
 
         let timestamp = chrono::Utc::now().timestamp_millis();
-        let recv_window = "5000";
+        let recv_window = self.recv_window.as_str();
         let query_string: String =  format!("{}{}{}", timestamp, self.api_key, recv_window);
 
 
         let signature = self.generate_bybit_signature(&query_string)?;
-        let mut headers = self.get_bybit_auth_headers(&signature, timestamp, recv_window)?;
+        let headers = self.get_bybit_auth_headers(&signature, timestamp, recv_window)?;
         
         let response = self.http_client.get(&url).headers(headers).send().await?;
-        let api_response: BybitAccInfoResponse = response.json::<BybitAccInfoResponse>().await?;
+        let api_response: BybitAccInfoResponse = Self::read_response(response).await?;
 
         Ok(api_response)
     }
 
 
-    pub async fn get_account_info(&self, account_type: Option<&str>, coin: Option<&str>) -> Result<BybitWalletBalanceResponse,Box<dyn Error>>{
+    pub async fn get_account_info(&self, account_type: Option<&str>, coin: Option<&str>) -> ExchangeResult<BybitWalletBalanceResponse>{
         /*
             Bybits API calls wallet balance, but internally we will handle this as 
             account info
@@ -127,13 +232,13 @@ impl BybitRestClient {
         
         //println!("url String {}",url);
         let timestamp = chrono::Utc::now().timestamp_millis();
-        let recv_window = "5000";
+        let recv_window = self.recv_window.as_str();
 
         let signature_string = format!("{}{}{}{}", timestamp, self.api_key, recv_window, query_params);
         //println!("signature_string: {}", signature_string);
 
         let signature = self.generate_bybit_signature(&signature_string)?;
-        let mut headers = self.get_bybit_auth_headers(&signature, timestamp, recv_window)?;
+        let headers = self.get_bybit_auth_headers(&signature, timestamp, recv_window)?;
 
         let response = self.http_client.get(&url).headers(headers).send().await?;
 
@@ -150,15 +255,15 @@ impl BybitRestClient {
         //let api_response: BybitWalletBalanceResponse = serde_json::from_str(&response_text)?;
         
 
-        let api_response: BybitWalletBalanceResponse = response.json::<BybitWalletBalanceResponse>().await?;
+        let api_response: BybitWalletBalanceResponse = Self::read_response(response).await?;
 
         Ok(api_response)
     }
 
 
-    pub async fn get_positions(&self, category: &str, symbol: Option<&str>, base_coin: Option<&str>, settle_coin: Option<&str>, limit: Option<i32>, cursor: Option<&str>) -> Result<BybitPositionResponse, Box<dyn Error>> {
+    pub async fn get_positions(&self, category: &str, symbol: Option<&str>, base_coin: Option<&str>, settle_coin: Option<&str>, limit: Option<i32>, cursor: Option<&str>) -> ExchangeResult<BybitPositionResponse> {
         if symbol.is_none() && settle_coin.is_none() {
-            return Err("Either symbol or settle_coin must be provided".into());
+            return Err(ExchangeError::InvalidRequest("Either symbol or settle_coin must be provided".to_string()));
         }
         
         let endpoint = "/v5/position/list";
@@ -184,7 +289,7 @@ impl BybitRestClient {
         
         // Generate timestamp and signature
         let timestamp = chrono::Utc::now().timestamp_millis();
-        let recv_window = "5000";
+        let recv_window = self.recv_window.as_str();
         
         // Create query string for signature
         let query_string = params
@@ -225,11 +330,14 @@ impl BybitRestClient {
         
 
         
-        let api_response: BybitPositionResponse = response.json::<BybitPositionResponse>().await?;
+        let api_response: BybitPositionResponse = Self::read_response(response).await?;
         
         Ok(api_response)
     }
 
+    // One page of open orders, at most `limit` of them (20 when None, 50 at most)
+    // Pass the page's nextPageCursor as `cursor` for the next one, an empty cursor means it was the last
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_open_orders(
         &self, 
         category: &str, 
@@ -242,30 +350,30 @@ impl BybitRestClient {
         order_filter: Option<&str>, 
         limit: Option<i32>, 
         cursor: Option<&str>
-    ) -> Result<BybitOpenOrdersResponse, Box<dyn Error>> {
+    ) -> ExchangeResult<BybitOpenOrdersResponse> {
         
         // Validate required parameters based on category
         match category {
             "linear" => {
                 if symbol.is_none() && base_coin.is_none() && settle_coin.is_none() {
-                    return Err("For linear category, either symbol, baseCoin, or settleCoin must be provided".into());
+                    return Err(ExchangeError::InvalidRequest("For linear category, either symbol, baseCoin, or settleCoin must be provided".to_string()));
                 }
             },
             "inverse" => {
                 if symbol.is_none() && base_coin.is_none() && settle_coin.is_none() {
-                    return Err("For inverse category, either symbol, baseCoin, or settleCoin must be provided".into());
+                    return Err(ExchangeError::InvalidRequest("For inverse category, either symbol, baseCoin, or settleCoin must be provided".to_string()));
                 }
             },
             "spot" => {
                 if symbol.is_none() && base_coin.is_none() {
-                    return Err("For spot category, either symbol or baseCoin must be provided".into());
+                    return Err(ExchangeError::InvalidRequest("For spot category, either symbol or baseCoin must be provided".to_string()));
                 }
             },
             "option" => {
                 // Option category doesn't require any specific parameters
             },
             _ => {
-                return Err("Invalid category. Must be one of: linear, inverse, spot, option".into());
+                return Err(ExchangeError::InvalidRequest("Invalid category. Must be one of: linear, inverse, spot, option".to_string()));
             }
         }
         
@@ -304,7 +412,7 @@ impl BybitRestClient {
         
         // Generate timestamp and signature
         let timestamp = chrono::Utc::now().timestamp_millis();
-        let recv_window = "5000";
+        let recv_window = self.recv_window.as_str();
         
         // Create query string for signature
         let query_string = params
@@ -330,12 +438,173 @@ impl BybitRestClient {
             .send()
             .await?;
         
-        let api_response: BybitOpenOrdersResponse = response.json::<BybitOpenOrdersResponse>().await?;
+        let api_response: BybitOpenOrdersResponse = Self::read_response(response).await?;
         
         Ok(api_response)
     }
+    // Order Entry Endpoints
+    async fn post_signed<T: Serialize, R: DeserializeOwned>(&self, endpoint: &str, payload: &T) -> ExchangeResult<R> {
+        /*
+            POST requests are signed over the JSON body, so the body we send must be
+            byte for byte the string that went into generate_bybit_signature2
+         */
+
+        let url = format!("{}{}", self.base_url, endpoint);
+
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let recv_window = self.recv_window.as_str();
+
+        let signature = self.generate_bybit_signature2(payload, timestamp, recv_window)?;
+        let headers = self.get_bybit_auth_headers(&signature, timestamp, recv_window)?;
+        let body = serde_json::to_string(payload)?;
+
+        let response = self
+            .http_client
+            .post(&url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await?;
+
+        let api_response: R = Self::read_response(response).await?;
+
+        Ok(api_response)
+    }
+
+    pub async fn place_order(&self, request: &BybitPlaceOrderRequest) -> ExchangeResult<BybitOrderResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/create-order
+         */
+        if request.price.is_none() && request.orderType == OrderType::Limit {
+            return Err(ExchangeError::InvalidRequest("Price must be provided for Limit orders".to_string()));
+        }
+
+        self.post_signed("/v5/order/create", request).await
+    }
+
+    pub async fn amend_order(&self, request: &BybitAmendOrderRequest) -> ExchangeResult<BybitOrderResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/amend-order
+         */
+        if request.orderId.is_none() && request.orderLinkId.is_none() {
+            return Err(ExchangeError::InvalidRequest("Either orderId or orderLinkId must be provided".to_string()));
+        }
+
+        self.post_signed("/v5/order/amend", request).await
+    }
+
+    pub async fn cancel_order(&self, request: &BybitCancelOrderRequest) -> ExchangeResult<BybitOrderResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/cancel-order
+         */
+        if request.orderId.is_none() && request.orderLinkId.is_none() {
+            return Err(ExchangeError::InvalidRequest("Either orderId or orderLinkId must be provided".to_string()));
+        }
+
+        self.post_signed("/v5/order/cancel", request).await
+    }
+
+    pub async fn cancel_all_orders(&self, request: &BybitCancelAllRequest) -> ExchangeResult<BybitCancelAllResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/cancel-all
+         */
+        self.post_signed("/v5/order/cancel-all", request).await
+    }
+
+    pub async fn set_dcp(&self, request: &BybitDcpRequest) -> ExchangeResult<BybitDcpResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/dcp
+            Turns disconnect-cancel-all on for the product, it only triggers once a private
+            stream subscribed to the product's dcp topic (dcp.future, dcp.spot or dcp.option)
+            has been gone for timeWindow seconds
+         */
+        if !(BYBIT_DCP_MIN_WINDOW..=BYBIT_DCP_MAX_WINDOW).contains(&request.timeWindow) {
+            return Err(ExchangeError::InvalidRequest(format!(
+                "DCP timeWindow must be between {} and {} seconds, got {}",
+                BYBIT_DCP_MIN_WINDOW, BYBIT_DCP_MAX_WINDOW, request.timeWindow
+            )));
+        }
+
+        self.post_signed("/v5/order/disconnected-cancel-all", request).await
+    }
+
+    // Batch Order Entry Endpoints
+    fn max_batch_size(category: &str) -> usize {
+        /*
+            Spot accepts up to 10 orders per batch, linear/inverse/option up to 20
+         */
+        if category == "spot" { 10 } else { 20 }
+    }
+
+    fn validate_batch_size<T>(request: &BybitBatchRequest<T>) -> ExchangeResult<()> {
+        let max_size = Self::max_batch_size(&request.category);
+
+        if request.request.is_empty() {
+            return Err(ExchangeError::InvalidRequest("Batch request must contain at least one order".to_string()));
+        }
+        if request.request.len() > max_size {
+            return Err(ExchangeError::InvalidRequest(format!("Batch request for {} is limited to {} orders, got {}", request.category, max_size, request.request.len())));
+        }
+        Ok(())
+    }
+
+    pub async fn place_batch_orders(&self, request: &BybitBatchRequest<BybitBatchPlaceItem>) -> ExchangeResult<BybitBatchOrderResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/batch-place
+            Individual orders can fail while the request succeeds, check outcomes()
+         */
+        Self::validate_batch_size(request)?;
+        if request.request.iter().any(|order| order.price.is_none() && order.orderType == OrderType::Limit) {
+            return Err(ExchangeError::InvalidRequest("Price must be provided for Limit orders".to_string()));
+        }
+
+        self.post_signed("/v5/order/create-batch", request).await
+    }
+
+    pub async fn amend_batch_orders(&self, request: &BybitBatchRequest<BybitBatchAmendItem>) -> ExchangeResult<BybitBatchOrderResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/batch-amend
+         */
+        Self::validate_batch_size(request)?;
+        if request.request.iter().any(|order| order.orderId.is_none() && order.orderLinkId.is_none()) {
+            return Err(ExchangeError::InvalidRequest("Either orderId or orderLinkId must be provided for every order".to_string()));
+        }
+
+        self.post_signed("/v5/order/amend-batch", request).await
+    }
+
+    pub async fn cancel_batch_orders(&self, request: &BybitBatchRequest<BybitBatchCancelItem>) -> ExchangeResult<BybitBatchOrderResponse> {
+        /*
+            https://bybit-exchange.github.io/docs/v5/order/batch-cancel
+         */
+        Self::validate_batch_size(request)?;
+        if request.request.iter().any(|order| order.orderId.is_none() && order.orderLinkId.is_none()) {
+            return Err(ExchangeError::InvalidRequest("Either orderId or orderLinkId must be provided for every order".to_string()));
+        }
+
+        self.post_signed("/v5/order/cancel-batch", request).await
+    }
+
+    // One result per order of a batch, a failed request fails every order in it
+    fn batch_acks(response: ExchangeResult<BybitBatchOrderResponse>, count: usize) -> Vec<ExchangeResult<QTSOrderAck>> {
+        let outcomes = match response {
+            Ok(response) => response.outcomes(),
+            Err(e) => return vec![Err(e); count],
+        };
+        let mut outcomes = outcomes.into_iter();
+        (0..count).map(|_| match outcomes.next() {
+            Some(outcome) if outcome.is_success() => Ok(QTSOrderAck {
+                exchange: Exchanges::Bybit,
+                order_id: outcome.orderId,
+                client_order_id: outcome.orderLinkId,
+            }),
+            Some(outcome) => Err(Self::classify_ret_code(outcome.code as i64, outcome.msg, None)),
+            None => Err(ExchangeError::Decode("Batch response is missing an order".to_string())),
+        }).collect()
+    }
+
     // Public Data Endpoints
-    pub async fn get_orderbook(&self, category: &str, symbol: &str) -> Result<BybitOrderbookResponse,Box<dyn Error>>{
+    pub async fn get_orderbook(&self, category: &str, symbol: &str) -> ExchangeResult<BybitOrderbookResponse>{
 
         let url= format!(
         "{}/v5/market/orderbook?category={}&symbol={}",
@@ -345,14 +614,14 @@ impl BybitRestClient {
 
         // Query API Endpoint
         let response = self.http_client.get(&url).send().await?;
-        let api_response = response.json::<BybitOrderbookResponse>().await?;
+        let api_response = Self::read_response(response).await?;
 
         
         Ok(api_response)
 
     }
 
-    pub async fn get_tickers(&self, category: &str, symbol: Option<&str>, base_coin: Option<&str>, exp_date: Option<&str>) -> Result<BybitTickersResponse, Box<dyn Error>> {
+    pub async fn get_tickers(&self, category: &str, symbol: Option<&str>, base_coin: Option<&str>, exp_date: Option<&str>) -> ExchangeResult<BybitTickersResponse> {
         let mut url = format!(
             "{}/v5/market/tickers?category={}",
             self.base_url, category
@@ -371,12 +640,242 @@ impl BybitRestClient {
 
         // Query API Endpoint
         let response = self.http_client.get(&url).send().await?;
-        let api_response = response.json::<BybitTickersResponse>().await?;
+        let api_response = Self::read_response(response).await?;
 
         Ok(api_response)
     }
 
 
+    // Map our instrument type onto the Bybit v5 category parameter
+    pub fn get_category(instrument_type: &InstrumentType) -> &'static str {
+        match instrument_type {
+            InstrumentType::Spot => "spot",
+            InstrumentType::Perp | InstrumentType::Linear | InstrumentType::Futures => "linear",
+            InstrumentType::Inverse => "inverse",
+            InstrumentType::Options => "option",
+        }
+    }
 
 }
 
+
+#[async_trait]
+impl ExchangeRest for BybitRestClient {
+
+    fn exchange(&self) -> Exchanges {
+        Exchanges::Bybit
+    }
+
+    async fn get_orderbook(&self, symbol: &Symbol) -> ExchangeResult<QTSOrderBook> {
+        let category = Self::get_category(&symbol.instrument_type);
+        let response = BybitRestClient::get_orderbook(self, category, &symbol.symbol).await?;
+
+        Ok(QTSOrderBook {
+            symbol: response.result.s,
+            asks: response.result.a,
+            bids: response.result.b,
+            time: response.result.ts,
+        })
+    }
+
+    async fn get_ticker(&self, symbol: &Symbol) -> ExchangeResult<QTSTicker> {
+        let category = Self::get_category(&symbol.instrument_type);
+        let response = self.get_tickers(category, Some(&symbol.symbol), None, None).await?;
+
+        let time = response.time;
+        let ticker = response.result.list.into_iter().next()
+            .ok_or_else(|| ExchangeError::Decode(format!("No ticker returned for {}", symbol.symbol)))?;
+
+        // Spot tickers have no mark price, and an empty book has no top of book
+        Ok(QTSTicker {
+            symbol: ticker.symbol,
+            last_price: ticker.lastPrice.parse()?,
+            mark_price: ticker.markPrice.parse().ok(),
+            bid_price: ticker.bid1Price.parse().ok(),
+            ask_price: ticker.ask1Price.parse().ok(),
+            volume_24h: ticker.volume24h.parse()?,
+            time,
+        })
+    }
+
+    async fn get_positions(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSPosition>> {
+        let category = Self::get_category(&symbol.instrument_type);
+        let response = BybitRestClient::get_positions(self, category, Some(&symbol.symbol), None, None, None, None).await?;
+
+        let time = response.time;
+        response.result.list.into_iter().map(|position| {
+            // Bybit reports an unsigned size with a side, sells become negative
+            let size: Qty = position.size.parse()?;
+            Ok(QTSPosition {
+                exchange: Exchanges::Bybit,
+                size: if position.side == "Sell" { -size } else { size },
+                entry_price: position.avgPrice.parse()?,
+                mark_price: position.markPrice.parse()?,
+                unrealized_pnl: parse_amount("unrealisedPnl", &position.unrealisedPnl)?,
+                symbol: position.symbol,
+                time,
+            })
+        }).collect()
+    }
+
+    async fn get_balances(&self) -> ExchangeResult<Vec<QTSBalance>> {
+        let response = self.get_account_info(None, None).await?;
+
+        // availableToWithdraw is empty on unified accounts
+        response.result.list.into_iter()
+            .flat_map(|account| account.coin)
+            .map(|coin| Ok(QTSBalance {
+                exchange: Exchanges::Bybit,
+                wallet_balance: parse_amount("walletBalance", &coin.walletBalance)?,
+                available_balance: parse_amount("availableToWithdraw", &coin.availableToWithdraw)?,
+                unrealized_pnl: parse_amount("unrealisedPnl", &coin.unrealisedPnl)?,
+                coin: coin.coin,
+            })).collect()
+    }
+
+    async fn get_open_orders(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSOpenOrder>> {
+        let category = Self::get_category(&symbol.instrument_type);
+
+        // Paged, a full ladder plus hedges can run past a single page. A short list would read
+        // as orders gone, so a cursor that doesn't move on is an error rather than the end
+        let mut orders = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..BYBIT_OPEN_ORDERS_MAX_PAGES {
+            let response = BybitRestClient::get_open_orders(
+                self, category, Some(&symbol.symbol), None, None, None, None, None, None,
+                Some(BYBIT_OPEN_ORDERS_PAGE_LIMIT), cursor.as_deref(),
+            ).await?;
+
+            for order in response.result.list {
+                orders.push(QTSOpenOrder {
+                    exchange: Exchanges::Bybit,
+                    side: if order.side == "Sell" { Side::Sell } else { Side::Buy },
+                    price: order.price.parse()?,
+                    qty: order.qty.parse()?,
+                    filled_qty: order.cumExecQty.parse()?,
+                    time: order.createdTime.parse().unwrap_or(0),
+                    symbol: order.symbol,
+                    order_id: order.orderId,
+                    client_order_id: order.orderLinkId,
+                    status: order.orderStatus,
+                    reduce_only: order.reduceOnly,
+                });
+            }
+            if response.result.nextPageCursor.is_empty() {
+                return Ok(orders);
+            }
+            if cursor.as_deref() == Some(response.result.nextPageCursor.as_str()) {
+                return Err(ExchangeError::Decode(format!("Open orders cursor {} repeated", response.result.nextPageCursor)));
+            }
+            cursor = Some(response.result.nextPageCursor);
+        }
+        Err(ExchangeError::Decode(format!("Open orders still paging after {} pages", BYBIT_OPEN_ORDERS_MAX_PAGES)))
+    }
+
+    async fn get_margin(&self) -> ExchangeResult<QTSMargin> {
+        let response = self.get_account_info(None, None).await?;
+
+        let time = response.time;
+        let account = response.result.list.into_iter().next()
+            .ok_or_else(|| ExchangeError::Decode("No account returned by wallet balance".to_string()))?;
+
+        // accountMMRate is already maintenance margin over margin balance
+        Ok(QTSMargin {
+            exchange: Exchanges::Bybit,
+            margin_balance: parse_amount("totalMarginBalance", &account.totalMarginBalance)?,
+            initial_margin: parse_amount("totalInitialMargin", &account.totalInitialMargin)?,
+            maintenance_margin: parse_amount("totalMaintenanceMargin", &account.totalMaintenanceMargin)?,
+            margin_ratio: parse_amount("accountMMRate", &account.accountMMRate)?,
+            time,
+        })
+    }
+
+    async fn place_order(&self, symbol: &Symbol, order: &QTSOrderRequest) -> ExchangeResult<QTSOrderAck> {
+        let request = BybitPlaceOrderRequest {
+            category: Self::get_category(&symbol.instrument_type).to_string(),
+            symbol: symbol.symbol.clone(),
+            side: order.side,
+            orderType: order.order_type,
+            qty: order.qty,
+            price: order.price,
+            timeInForce: Some(order.time_in_force),
+            orderLinkId: Some(order.client_order_id.clone()),
+            reduceOnly: if order.reduce_only { Some(true) } else { None },
+            positionIdx: None,
+        };
+        let response = BybitRestClient::place_order(self, &request).await?;
+
+        Ok(QTSOrderAck {
+            exchange: Exchanges::Bybit,
+            order_id: response.result.orderId,
+            client_order_id: response.result.orderLinkId,
+        })
+    }
+
+    async fn cancel_order(&self, symbol: &Symbol, client_order_id: &str) -> ExchangeResult<QTSOrderAck> {
+        let request = BybitCancelOrderRequest {
+            category: Self::get_category(&symbol.instrument_type).to_string(),
+            symbol: symbol.symbol.clone(),
+            orderId: None,
+            orderLinkId: Some(client_order_id.to_string()),
+        };
+        let response = BybitRestClient::cancel_order(self, &request).await?;
+
+        Ok(QTSOrderAck {
+            exchange: Exchanges::Bybit,
+            order_id: response.result.orderId,
+            client_order_id: response.result.orderLinkId,
+        })
+    }
+
+    async fn cancel_all_orders(&self, symbol: &Symbol) -> ExchangeResult<()> {
+        let request = BybitCancelAllRequest {
+            category: Self::get_category(&symbol.instrument_type).to_string(),
+            symbol: Some(symbol.symbol.clone()),
+            baseCoin: None,
+            settleCoin: None,
+        };
+        BybitRestClient::cancel_all_orders(self, &request).await?;
+        Ok(())
+    }
+
+    async fn place_orders(&self, symbol: &Symbol, orders: &[QTSOrderRequest]) -> Vec<ExchangeResult<QTSOrderAck>> {
+        let category = Self::get_category(&symbol.instrument_type);
+        let requests: Vec<_> = orders.chunks(Self::max_batch_size(category)).map(|chunk| BybitBatchRequest {
+            category: category.to_string(),
+            request: chunk.iter().map(|order| BybitBatchPlaceItem {
+                symbol: symbol.symbol.clone(),
+                side: order.side,
+                orderType: order.order_type,
+                qty: order.qty,
+                price: order.price,
+                timeInForce: Some(order.time_in_force),
+                orderLinkId: Some(order.client_order_id.clone()),
+                reduceOnly: if order.reduce_only { Some(true) } else { None },
+                positionIdx: None,
+            }).collect(),
+        }).collect();
+
+        let responses = join_all(requests.iter().map(|request| self.place_batch_orders(request))).await;
+        requests.iter().zip(responses)
+            .flat_map(|(request, response)| Self::batch_acks(response, request.request.len()))
+            .collect()
+    }
+
+    async fn cancel_orders(&self, symbol: &Symbol, client_order_ids: &[String]) -> Vec<ExchangeResult<QTSOrderAck>> {
+        let category = Self::get_category(&symbol.instrument_type);
+        let requests: Vec<_> = client_order_ids.chunks(Self::max_batch_size(category)).map(|chunk| BybitBatchRequest {
+            category: category.to_string(),
+            request: chunk.iter().map(|client_order_id| BybitBatchCancelItem {
+                symbol: symbol.symbol.clone(),
+                orderId: None,
+                orderLinkId: Some(client_order_id.clone()),
+            }).collect(),
+        }).collect();
+
+        let responses = join_all(requests.iter().map(|request| self.cancel_batch_orders(request))).await;
+        requests.iter().zip(responses)
+            .flat_map(|(request, response)| Self::batch_acks(response, request.request.len()))
+            .collect()
+    }
+}
@@ -1,17 +1,27 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use futures_util::{SinkExt, StreamExt};
 use serde_json;
 use url::Url;
 use std::error::Error;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 
+use crate::decimal::Price;
 use crate::exchange::bybit::response::{
     BybitWebSocketOrderbookResponse,
+    BybitWebSocketTradeResponse,
     BybitWebSocketSubscription,
     BybitWebSocketSubscriptionResponse,
+    BybitWebSocketOrderResponse,
+    BybitWebSocketExecutionResponse,
+    BybitWebSocketPositionResponse,
+    BybitWebSocketWalletResponse,
     LocalOrderbook,
+    OrderbookSyncError,
 };
 
 /// WebSocket stream types for different Bybit endpoints
@@ -27,6 +37,8 @@ pub enum BybitStreamType {
     Spread,
     /// Options trading
     Option,
+    /// Authenticated account stream (orders, executions, positions, wallet)
+    Private,
 }
 
 impl BybitStreamType {
@@ -39,17 +51,82 @@ impl BybitStreamType {
     /// The WebSocket URL as a String
     pub fn get_url(&self, is_testnet: bool) -> String {
         let base_url = if is_testnet {
-            "wss://stream-testnet.bybit.com/v5/public"
+            "wss://stream-testnet.bybit.com/v5"
         } else {
-            "wss://stream.bybit.com/v5/public"
+            "wss://stream.bybit.com/v5"
         };
 
+        self.get_url_with_base(base_url)
+    }
+
+    /// Get the WebSocket URL for the stream type under a custom base URL
+    ///
+    /// # Arguments
+    /// * `base_url` - Everything before `/public/...` or `/private`, e.g. "ws://127.0.0.1:9000/v5"
+    ///
+    /// # Returns
+    /// The WebSocket URL as a String
+    pub fn get_url_with_base(&self, base_url: &str) -> String {
+        let base_url = base_url.trim_end_matches('/');
+
+        match self {
+            BybitStreamType::Spot => format!("{}/public/spot", base_url),
+            BybitStreamType::Linear => format!("{}/public/linear", base_url),
+            BybitStreamType::Inverse => format!("{}/public/inverse", base_url),
+            BybitStreamType::Spread => format!("{}/public/spread", base_url),
+            BybitStreamType::Option => format!("{}/public/option", base_url),
+            BybitStreamType::Private => format!("{}/private", base_url),
+        }
+    }
+}
+
+/// Topics available on the private stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BybitPrivateTopic {
+    /// Order status updates
+    Order,
+    /// Fills
+    Execution,
+    /// Position updates
+    Position,
+    /// Wallet and margin updates
+    Wallet,
+    /// Disconnect-cancel-all for one product, the venue cancels our orders on it once no
+    /// connection holds this topic
+    Dcp(BybitDcpProduct),
+}
+
+impl BybitPrivateTopic {
+    /// Topic name as used in the subscribe request (all categories)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BybitPrivateTopic::Order => "order",
+            BybitPrivateTopic::Execution => "execution",
+            BybitPrivateTopic::Position => "position",
+            BybitPrivateTopic::Wallet => "wallet",
+            BybitPrivateTopic::Dcp(BybitDcpProduct::Derivatives) => "dcp.future",
+            BybitPrivateTopic::Dcp(BybitDcpProduct::Spot) => "dcp.spot",
+            BybitPrivateTopic::Dcp(BybitDcpProduct::Options) => "dcp.option",
+        }
+    }
+}
+
+/// Products disconnect-cancel-all is set for, each one has its own window and dcp topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BybitDcpProduct {
+    /// Linear and inverse contracts
+    Derivatives,
+    Spot,
+    Options,
+}
+
+impl BybitDcpProduct {
+    /// Product name as used in the set_dcp request
+    pub fn as_str(&self) -> &'static str {
         match self {
-            BybitStreamType::Spot => format!("{}/spot", base_url),
-            BybitStreamType::Linear => format!("{}/linear", base_url),
-            BybitStreamType::Inverse => format!("{}/inverse", base_url),
-            BybitStreamType::Spread => format!("{}/spread", base_url),
-            BybitStreamType::Option => format!("{}/option", base_url),
+            BybitDcpProduct::Derivatives => "DERIVATIVES",
+            BybitDcpProduct::Spot => "SPOT",
+            BybitDcpProduct::Options => "OPTIONS",
         }
     }
 }
@@ -61,10 +138,16 @@ pub struct BybitWebSocketConfig {
     pub stream_type: BybitStreamType,
     /// Whether to use testnet
     pub is_testnet: bool,
+    /// Custom base URL (e.g. a local mock server), takes precedence over `is_testnet`
+    pub base_url: Option<String>,
     /// Maximum reconnection attempts
     pub max_reconnect_attempts: u32,
     /// Reconnection delay in milliseconds
     pub reconnect_delay_ms: u64,
+    /// How often to send an application level ping, 0 disables the heartbeat
+    pub heartbeat_interval_ms: u64,
+    /// Unanswered pings before the connection is treated as stale and reconnected
+    pub max_missed_pongs: u32,
 }
 
 impl Default for BybitWebSocketConfig {
@@ -72,8 +155,11 @@ impl Default for BybitWebSocketConfig {
         Self {
             stream_type: BybitStreamType::Linear,
             is_testnet: true, // Default to testnet for safety
+            base_url: None,
             max_reconnect_attempts: 5,
             reconnect_delay_ms: 1000,
+            heartbeat_interval_ms: 20_000, // Bybit drops connections idle for ~20s+
+            max_missed_pongs: 2,
         }
     }
 }
@@ -83,12 +169,34 @@ impl Default for BybitWebSocketConfig {
 pub enum BybitWebSocketMessage {
     /// Orderbook data
     Orderbook(BybitWebSocketOrderbookResponse),
+    /// Public trades
+    Trade(BybitWebSocketTradeResponse),
     /// Subscription confirmation
     Subscription(BybitWebSocketSubscriptionResponse),
+    /// Authentication result on the private stream
+    Auth(BybitWebSocketSubscriptionResponse),
+    /// Order status updates
+    Order(BybitWebSocketOrderResponse),
+    /// Fills
+    Execution(BybitWebSocketExecutionResponse),
+    /// Position updates
+    Position(BybitWebSocketPositionResponse),
+    /// Wallet updates
+    Wallet(BybitWebSocketWalletResponse),
+    /// Anything pushed on a dcp topic, only the subscription itself matters
+    Dcp,
+    /// Connection dropped, a reconnect will be attempted
+    Disconnected(String),
+    /// Connection re-established and subscriptions replayed, books need a fresh snapshot
+    Reconnected { attempt: u32 },
+    /// Reconnect attempts exhausted, no more messages will arrive
+    GaveUp,
     /// Ping message
     Ping,
     /// Pong message
     Pong,
+    /// Round trip time of our last ping
+    Heartbeat { rtt_ms: u64 },
     /// Error message
     Error(String),
 }
@@ -99,8 +207,260 @@ pub struct BybitWebSocketClient {
     config: BybitWebSocketConfig,
     /// Sender channel for sending messages to the WebSocket
     sender: Option<mpsc::UnboundedSender<BybitWebSocketSubscription>>,
-    /// Receiver channel for receiving messages from the WebSocket
-    receiver: Option<mpsc::UnboundedReceiver<BybitWebSocketMessage>>,
+    /// API credentials, only needed for the private stream
+    api_key: Option<String>,
+    api_secret: Option<String>,
+    /// Topics currently subscribed, replayed after a reconnect
+    subscriptions: Arc<Mutex<Vec<String>>>,
+}
+
+/// Upper bound for the reconnect backoff
+const MAX_RECONNECT_DELAY_MS: u64 = 30_000;
+
+type BybitWebSocketStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Why a connection stopped pumping messages
+enum ConnectionEnd {
+    /// Every handle to the client was dropped, stop for good
+    ClientClosed,
+    /// The venue side went away, try to reconnect
+    Lost(String),
+}
+
+/// Parse a text frame into a typed message
+///
+/// Topic messages are routed on their `topic`, everything else is an op reply
+fn parse_message(text: &str) -> BybitWebSocketMessage {
+    let value: serde_json::Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => return BybitWebSocketMessage::Error(format!("Invalid message: {} ({})", text, e)),
+    };
+
+    // Private topics are either "order" or "order.linear" style
+    let is_topic = |topic: &str, name: &str| topic == name || topic.starts_with(&format!("{}.", name));
+
+    let parsed = if let Some(topic) = value.get("topic").and_then(|t| t.as_str()) {
+        if topic.starts_with("orderbook.") {
+            serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Orderbook)
+        } else if topic.starts_with("publicTrade.") {
+            serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Trade)
+        } else if is_topic(topic, "order") {
+            serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Order)
+        } else if is_topic(topic, "execution") {
+            serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Execution)
+        } else if is_topic(topic, "position") {
+            serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Position)
+        } else if is_topic(topic, "wallet") {
+            serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Wallet)
+        } else if is_topic(topic, "dcp") {
+            return BybitWebSocketMessage::Dcp;
+        } else {
+            return BybitWebSocketMessage::Error(format!("Unknown topic: {}", text));
+        }
+    } else {
+        match value.get("op").and_then(|op| op.as_str()) {
+            Some("auth") => serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Auth),
+            // Public streams answer our ping with op "ping" and ret_msg "pong"
+            Some("ping") if value.get("ret_msg").and_then(|m| m.as_str()) == Some("pong") => return BybitWebSocketMessage::Pong,
+            Some("ping") => return BybitWebSocketMessage::Ping,
+            Some("pong") => return BybitWebSocketMessage::Pong,
+            Some(_) => serde_json::from_value(value.clone()).map(BybitWebSocketMessage::Subscription),
+            None => return BybitWebSocketMessage::Error(format!("Unknown message: {}", text)),
+        }
+    };
+
+    parsed.unwrap_or_else(|e| BybitWebSocketMessage::Error(format!("Failed to parse {}: {}", text, e)))
+}
+
+/// Build the private stream auth request
+///
+/// Signed like the REST client: hex HMAC-SHA256 of `GET/realtime{expires}` with the API secret
+fn build_auth_request(api_key: &str, api_secret: &str) -> Result<BybitWebSocketSubscription, Box<dyn Error + Send + Sync>> {
+    // Request is valid for 10 seconds
+    let expires = chrono::Utc::now().timestamp_millis() + 10_000;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(api_secret.as_bytes())?;
+    mac.update(format!("GET/realtime{}", expires).as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+
+    Ok(BybitWebSocketSubscription {
+        op: "auth".to_string(),
+        args: vec![api_key.to_string(), expires.to_string(), signature],
+    })
+}
+
+/// Open a connection, authenticating first when credentials are given
+async fn open_stream(
+    url: &Url,
+    credentials: Option<&(String, String)>,
+) -> Result<BybitWebSocketStream, Box<dyn Error + Send + Sync>> {
+    let (mut ws_stream, _) = connect_async(url.clone()).await?;
+
+    // The private stream must authenticate before anything else is sent
+    if let Some((api_key, api_secret)) = credentials {
+        let auth = build_auth_request(api_key, api_secret)?;
+        ws_stream.send(Message::Text(serde_json::to_string(&auth)?)).await?;
+    }
+
+    Ok(ws_stream)
+}
+
+/// Exponential backoff starting at `reconnect_delay_ms`, capped at MAX_RECONNECT_DELAY_MS
+fn reconnect_delay(reconnect_delay_ms: u64, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_millis(reconnect_delay_ms.saturating_mul(factor).min(MAX_RECONNECT_DELAY_MS))
+}
+
+/// Keep the active topic list in step with what we send
+fn track_subscription(subscriptions: &Mutex<Vec<String>>, request: &BybitWebSocketSubscription) {
+    let mut active = subscriptions.lock().unwrap();
+    match request.op.as_str() {
+        "subscribe" => {
+            for topic in &request.args {
+                if !active.contains(topic) {
+                    active.push(topic.clone());
+                }
+            }
+        }
+        "unsubscribe" => active.retain(|topic| !request.args.contains(topic)),
+        _ => {}
+    }
+}
+
+/// Forward traffic both ways on one connection until it ends
+///
+/// Also drives the heartbeat: a `{"op":"ping"}` every `heartbeat_interval_ms`, and the
+/// connection is reported lost once `max_missed_pongs` pings in a row go unanswered.
+async fn pump_messages(
+    ws_stream: BybitWebSocketStream,
+    config: &BybitWebSocketConfig,
+    outgoing: &mut mpsc::UnboundedReceiver<BybitWebSocketSubscription>,
+    subscriptions: &Mutex<Vec<String>>,
+    message_tx: &mpsc::UnboundedSender<BybitWebSocketMessage>,
+) -> ConnectionEnd {
+    let (mut write, mut read) = ws_stream.split();
+
+    // Heartbeat state
+    let heartbeat_enabled = config.heartbeat_interval_ms > 0;
+    let period = Duration::from_millis(config.heartbeat_interval_ms.max(1));
+    let mut heartbeat = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let mut ping_sent_at: Option<Instant> = None;  // set while a ping is unanswered
+    let mut missed_pongs = 0;
+    let mut next_req_id: u64 = 1;
+
+    loop {
+        tokio::select! {
+            // Handle incoming messages from the WebSocket
+            msg = read.next() => {
+                let message = match msg {
+                    Some(Ok(Message::Text(text))) => parse_message(&text),
+                    Some(Ok(Message::Ping(_))) => BybitWebSocketMessage::Ping,
+                    Some(Ok(Message::Pong(_))) => BybitWebSocketMessage::Pong,
+                    Some(Ok(Message::Close(_))) => {
+                        println!("WebSocket connection closed");
+                        return ConnectionEnd::Lost("Connection closed by server".to_string());
+                    }
+                    Some(Err(e)) => return ConnectionEnd::Lost(format!("WebSocket error: {}", e)),
+                    None => return ConnectionEnd::Lost("WebSocket stream ended".to_string()),
+                    Some(Ok(_)) => continue,
+                };
+                let pong = matches!(message, BybitWebSocketMessage::Pong);
+                if message_tx.send(message).is_err() {
+                    return ConnectionEnd::ClientClosed;
+                }
+                if pong && let Some(sent_at) = ping_sent_at.take() {
+                    missed_pongs = 0;
+                    let rtt_ms = sent_at.elapsed().as_millis() as u64;
+                    if message_tx.send(BybitWebSocketMessage::Heartbeat { rtt_ms }).is_err() {
+                        return ConnectionEnd::ClientClosed;
+                    }
+                }
+            }
+            // Send our own ping and check the previous one was answered
+            _ = heartbeat.tick(), if heartbeat_enabled => {
+                if ping_sent_at.is_some() {
+                    missed_pongs += 1;
+                    if missed_pongs >= config.max_missed_pongs {
+                        return ConnectionEnd::Lost(format!("Connection stale, {} pings unanswered", missed_pongs));
+                    }
+                }
+                let ping = serde_json::json!({"op": "ping", "req_id": next_req_id.to_string()});
+                next_req_id += 1;
+                if let Err(e) = write.send(Message::Text(ping.to_string())).await {
+                    return ConnectionEnd::Lost(format!("Failed to send ping: {}", e));
+                }
+                ping_sent_at = Some(Instant::now());
+            }
+            // Handle outgoing subscription requests
+            request = outgoing.recv() => {
+                let Some(subscription) = request else {
+                    return ConnectionEnd::ClientClosed;
+                };
+                // Track before sending so a request lost to a dead socket is still replayed
+                track_subscription(subscriptions, &subscription);
+                if let Ok(json) = serde_json::to_string(&subscription)
+                    && let Err(e) = write.send(Message::Text(json)).await {
+                    return ConnectionEnd::Lost(format!("Failed to send subscription: {}", e));
+                }
+            }
+        }
+    }
+}
+
+/// Own the connection for its whole life: pump messages, and when the venue drops
+/// us reconnect with backoff, re-authenticate and replay the active subscriptions
+async fn run_connection(
+    mut ws_stream: BybitWebSocketStream,
+    url: Url,
+    credentials: Option<(String, String)>,
+    config: BybitWebSocketConfig,
+    subscriptions: Arc<Mutex<Vec<String>>>,
+    mut outgoing: mpsc::UnboundedReceiver<BybitWebSocketSubscription>,
+    message_tx: mpsc::UnboundedSender<BybitWebSocketMessage>,
+) {
+    loop {
+        let reason = match pump_messages(ws_stream, &config, &mut outgoing, &subscriptions, &message_tx).await {
+            ConnectionEnd::ClientClosed => return,
+            ConnectionEnd::Lost(reason) => reason,
+        };
+        if message_tx.send(BybitWebSocketMessage::Disconnected(reason)).is_err() {
+            return;
+        }
+
+        // Reconnect with backoff
+        let mut attempt = 0;
+        ws_stream = loop {
+            if attempt >= config.max_reconnect_attempts {
+                let _ = message_tx.send(BybitWebSocketMessage::GaveUp);
+                return;
+            }
+            attempt += 1;
+            tokio::time::sleep(reconnect_delay(config.reconnect_delay_ms, attempt)).await;
+
+            match open_stream(&url, credentials.as_ref()).await {
+                Ok(stream) => break stream,
+                Err(e) => {
+                    let _ = message_tx.send(BybitWebSocketMessage::Error(format!("Reconnect attempt {} failed: {}", attempt, e)));
+                }
+            }
+        };
+
+        // Replay subscriptions, a failed send surfaces on the next read
+        let topics = subscriptions.lock().unwrap().clone();
+        if !topics.is_empty() {
+            let subscription = BybitWebSocketSubscription {
+                op: "subscribe".to_string(),
+                args: topics,
+            };
+            if let Ok(json) = serde_json::to_string(&subscription) {
+                let _ = ws_stream.send(Message::Text(json)).await;
+            }
+        }
+
+        if message_tx.send(BybitWebSocketMessage::Reconnected { attempt }).is_err() {
+            return;
+        }
+    }
 }
 
 impl BybitWebSocketClient {
@@ -115,7 +475,31 @@ impl BybitWebSocketClient {
         Self {
             config,
             sender: None,
-            receiver: None,
+            api_key: None,
+            api_secret: None,
+            subscriptions: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Create a client for the authenticated private stream
+    ///
+    /// # Arguments
+    /// * `config` - WebSocket configuration, the stream type is forced to Private
+    /// * `api_key` - Bybit API key
+    /// * `api_secret` - Bybit API secret
+    ///
+    /// # Returns
+    /// A new BybitWebSocketClient instance
+    pub fn new_private(config: BybitWebSocketConfig, api_key: String, api_secret: String) -> Self {
+        Self {
+            config: BybitWebSocketConfig {
+                stream_type: BybitStreamType::Private,
+                ..config
+            },
+            sender: None,
+            api_key: Some(api_key),
+            api_secret: Some(api_secret),
+            subscriptions: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -123,12 +507,14 @@ impl BybitWebSocketClient {
     /// 
     /// This method establishes the WebSocket connection and sets up the message channels.
     /// It returns a sender for sending subscription requests and a receiver for receiving messages.
+    /// If the connection later drops it is re-established in the background using
+    /// `max_reconnect_attempts` and `reconnect_delay_ms`, see `BybitWebSocketMessage::Reconnected`.
     /// 
     /// # Returns
     /// * `Result<(mpsc::UnboundedSender<BybitWebSocketSubscription>, mpsc::UnboundedReceiver<BybitWebSocketMessage>), Box<dyn Error>>`
     ///   - Sender channel for sending subscription requests
     ///   - Receiver channel for receiving WebSocket messages
-    ///   - Error if connection fails
+    ///   - Error if the initial connection fails
     pub async fn connect(&mut self) -> Result<
         (
             mpsc::UnboundedSender<BybitWebSocketSubscription>,
@@ -137,78 +523,50 @@ impl BybitWebSocketClient {
         Box<dyn Error>,
     > {
         // Get the WebSocket URL based on configuration
-        let url = self.config.stream_type.get_url(self.config.is_testnet);
+        let url = match &self.config.base_url {
+            Some(base_url) => self.config.stream_type.get_url_with_base(base_url),
+            None => self.config.stream_type.get_url(self.config.is_testnet),
+        };
         let url = Url::parse(&url)?;
 
+        let credentials = match self.config.stream_type {
+            BybitStreamType::Private => match (&self.api_key, &self.api_secret) {
+                (Some(api_key), Some(api_secret)) => Some((api_key.clone(), api_secret.clone())),
+                _ => return Err("API credentials are required for the private stream".into()),
+            },
+            _ => None,
+        };
+
         // Create channels for communication
-        let (tx, mut rx) = mpsc::unbounded_channel::<BybitWebSocketSubscription>();
+        let (tx, rx) = mpsc::unbounded_channel::<BybitWebSocketSubscription>();
         let (message_tx, message_rx) = mpsc::unbounded_channel::<BybitWebSocketMessage>();
 
         // Store the channels for later use
         self.sender = Some(tx.clone());
         // Don't store message_rx here since we need to return it
 
-        // Connect to the WebSocket
-        let (ws_stream, _) = connect_async(url).await?;
-        let (mut write, mut read) = ws_stream.split();
+        // Connect to the WebSocket, only this first attempt is reported to the caller
+        let ws_stream = open_stream(&url, credentials.as_ref()).await.map_err(|e| e as Box<dyn Error>)?;
 
-        // Spawn a task to handle the WebSocket connection
-        let message_tx_clone = message_tx.clone();
-        tokio::spawn(async move {
-            // Handle incoming messages from the WebSocket
-            while let Some(msg) = read.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        // Try to parse the message as JSON
-                        if let Ok(parsed) = serde_json::from_str::<BybitWebSocketOrderbookResponse>(&text) {
-                            let _ = message_tx_clone.send(BybitWebSocketMessage::Orderbook(parsed));
-                        } else if let Ok(parsed) = serde_json::from_str::<BybitWebSocketSubscriptionResponse>(&text) {
-                            let _ = message_tx_clone.send(BybitWebSocketMessage::Subscription(parsed));
-                        } else {
-                            // Handle ping/pong messages
-                            if text.contains("ping") {
-                                let _ = message_tx_clone.send(BybitWebSocketMessage::Ping);
-                            } else if text.contains("pong") {
-                                let _ = message_tx_clone.send(BybitWebSocketMessage::Pong);
-                            } else {
-                                let _ = message_tx_clone.send(BybitWebSocketMessage::Error(format!("Unknown message: {}", text)));
-                            }
-                        }
-                    }
-                    Ok(Message::Ping(_)) => {
-                        let _ = message_tx_clone.send(BybitWebSocketMessage::Ping);
-                    }
-                    Ok(Message::Pong(_)) => {
-                        let _ = message_tx_clone.send(BybitWebSocketMessage::Pong);
-                    }
-                    Ok(Message::Close(_)) => {
-                        println!("WebSocket connection closed");
-                        break;
-                    }
-                    Err(e) => {
-                        let _ = message_tx_clone.send(BybitWebSocketMessage::Error(format!("WebSocket error: {}", e)));
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-        });
-
-        // Spawn a task to handle outgoing messages
-        tokio::spawn(async move {
-            while let Some(subscription) = rx.recv().await {
-                if let Ok(json) = serde_json::to_string(&subscription) {
-                    if let Err(e) = write.send(Message::Text(json)).await {
-                        eprintln!("Failed to send subscription: {}", e);
-                        break;
-                    }
-                }
-            }
-        });
+        // Spawn a task to own the connection and reconnect when it drops
+        tokio::spawn(run_connection(
+            ws_stream,
+            url,
+            credentials,
+            self.config.clone(),
+            self.subscriptions.clone(),
+            rx,
+            message_tx,
+        ));
 
         Ok((tx, message_rx))
     }
 
+    /// Topics that will be replayed after a reconnect
+    pub fn active_subscriptions(&self) -> Vec<String> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
     /// Subscribe to orderbook data for a specific symbol and depth
     /// 
     /// # Arguments
@@ -260,6 +618,72 @@ impl BybitWebSocketClient {
             Err("WebSocket not connected".into())
         }
     }
+
+    /// Subscribe to public trades for a specific symbol
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "BTCUSDT")
+    ///
+    /// # Returns
+    /// * `Result<(), Box<dyn Error>>` - Error if subscription fails
+    pub async fn subscribe_trades(&self, symbol: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(sender) = &self.sender {
+            let subscription = BybitWebSocketSubscription {
+                op: "subscribe".to_string(),
+                args: vec![format!("publicTrade.{}", symbol)],
+            };
+            sender.send(subscription)?;
+            Ok(())
+        } else {
+            Err("WebSocket not connected".into())
+        }
+    }
+
+    /// Ask for a fresh orderbook snapshot
+    ///
+    /// Bybit has no snapshot request, so the topic is unsubscribed and subscribed
+    /// again, the venue then starts over with a snapshot message.
+    ///
+    /// # Arguments
+    /// * `symbol` - Trading symbol (e.g., "BTCUSDT")
+    /// * `depth` - Orderbook depth (1, 50, 200, 500, 1000)
+    ///
+    /// # Returns
+    /// * `Result<(), Box<dyn Error>>` - Error if the requests could not be queued
+    pub async fn resubscribe_orderbook(
+        &self,
+        symbol: &str,
+        depth: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        self.unsubscribe_orderbook(symbol, depth).await?;
+        self.subscribe_orderbook(symbol, depth).await
+    }
+
+    /// Subscribe to private topics, only valid on the private stream
+    ///
+    /// # Arguments
+    /// * `topics` - Private topics to subscribe to
+    ///
+    /// # Returns
+    /// * `Result<(), Box<dyn Error>>` - Error if subscription fails
+    pub async fn subscribe_private(
+        &self,
+        topics: &[BybitPrivateTopic],
+    ) -> Result<(), Box<dyn Error>> {
+        if !matches!(self.config.stream_type, BybitStreamType::Private) {
+            return Err("Private topics require a private stream client".into());
+        }
+        if let Some(sender) = &self.sender {
+            let subscription = BybitWebSocketSubscription {
+                op: "subscribe".to_string(),
+                args: topics.iter().map(|topic| topic.as_str().to_string()).collect(),
+            };
+            sender.send(subscription)?;
+            Ok(())
+        } else {
+            Err("WebSocket not connected".into())
+        }
+    }
 }
 
 /// Helper function to create a simple orderbook subscription example
@@ -270,6 +694,7 @@ impl BybitWebSocketClient {
 /// # Arguments
 /// * `symbol` - Trading symbol to subscribe to (e.g., "BTCUSDT")
 /// * `depth` - Orderbook depth (1, 50, 200, 500, 1000)
+/// * `min_tick` - Price tick size of the symbol (e.g., 0.1 for BTCUSDT)
 /// * `is_testnet` - Whether to use testnet or mainnet
 /// 
 /// # Returns
@@ -277,10 +702,11 @@ impl BybitWebSocketClient {
 pub async fn run_orderbook_example(
     symbol: &str,
     depth: u32,
+    min_tick: Price,
     is_testnet: bool,
 ) -> Result<(), Box<dyn Error>> {
     println!("🚀 Starting Bybit WebSocket Orderbook Example");
-    println!("Symbol: {}, Depth: {}, Testnet: {}", symbol, depth, is_testnet);
+    println!("Symbol: {}, Depth: {}, Tick: {}, Testnet: {}", symbol, depth, min_tick, is_testnet);
 
     // Create WebSocket configuration
     let config = BybitWebSocketConfig {
@@ -291,7 +717,7 @@ pub async fn run_orderbook_example(
 
     // Create and connect the WebSocket client
     let mut client = BybitWebSocketClient::new(config);
-    let (sender, mut receiver) = client.connect().await?;
+    let (_sender, mut receiver) = client.connect().await?;
 
     println!("✅ Connected to Bybit WebSocket");
 
@@ -300,7 +726,7 @@ pub async fn run_orderbook_example(
     println!("📡 Subscribed to orderbook.{}.{}", depth, symbol);
 
     // Create local orderbook to track state
-    let mut local_orderbook = LocalOrderbook::new(symbol.to_string());
+    let mut local_orderbook = LocalOrderbook::new(symbol.to_string(), min_tick)?;
     
     // Listen for messages
     let mut message_count = 0;
@@ -318,11 +744,28 @@ pub async fn run_orderbook_example(
                 match orderbook.data_type.as_str() {
                     "snapshot" => {
                         println!("🔄 Processing SNAPSHOT...");
-                        local_orderbook.apply_snapshot(&orderbook.data);
+                        if let Err(e) = local_orderbook.apply_snapshot(&orderbook.data) {
+                            println!("⚠️  Snapshot refused ({}), requesting another", e);
+                            client.resubscribe_orderbook(symbol, depth).await?;
+                            continue;
+                        }
                     }
                     "delta" => {
                         println!("📈 Processing DELTA...");
-                        local_orderbook.apply_delta(&orderbook.data);
+                        match local_orderbook.apply_delta(&orderbook.data) {
+                            Ok(()) => {}
+                            Err(OrderbookSyncError::StaleSequence { .. }) => {
+                                println!("⏭️  Skipped stale delta");
+                                continue;
+                            }
+                            // Nothing to do until the snapshot we asked for arrives
+                            Err(OrderbookSyncError::NoSnapshot) => continue,
+                            Err(e) => {
+                                println!("⚠️  Orderbook out of sync ({}), requesting snapshot", e);
+                                client.resubscribe_orderbook(symbol, depth).await?;
+                                continue;
+                            }
+                        }
                     }
                     _ => {
                         println!("❓ Unknown message type: {}", orderbook.data_type);
@@ -342,12 +785,36 @@ pub async fn run_orderbook_example(
                     println!("❌ Subscription failed: {:?}", sub.args);
                 }
             }
+            BybitWebSocketMessage::Disconnected(reason) => {
+                println!("🔌 Disconnected: {}", reason);
+            }
+            BybitWebSocketMessage::Reconnected { attempt } => {
+                // Bybit sends a fresh snapshot after the resubscribe, drop the stale book until then
+                println!("🔁 Reconnected after {} attempt(s), waiting for snapshot", attempt);
+                local_orderbook.reset();
+            }
+            BybitWebSocketMessage::GaveUp => {
+                return Err("Gave up reconnecting to Bybit WebSocket".into());
+            }
+            // Not subscribed here
+            BybitWebSocketMessage::Trade(_) => {}
+            BybitWebSocketMessage::Auth(_)
+            | BybitWebSocketMessage::Order(_)
+            | BybitWebSocketMessage::Execution(_)
+            | BybitWebSocketMessage::Position(_)
+            | BybitWebSocketMessage::Wallet(_)
+            | BybitWebSocketMessage::Dcp => {
+                // Private stream only
+            }
             BybitWebSocketMessage::Ping => {
                 println!("🏓 Received ping");
             }
             BybitWebSocketMessage::Pong => {
                 println!("🏓 Received pong");
             }
+            BybitWebSocketMessage::Heartbeat { rtt_ms } => {
+                println!("💓 Heartbeat rtt: {}ms", rtt_ms);
+            }
             BybitWebSocketMessage::Error(err) => {
                 println!("❌ Error: {}", err);
             }
@@ -362,3 +829,100 @@ pub async fn run_orderbook_example(
 
     Ok(())
 }
+
+/// Helper function to stream private account events
+///
+/// Authenticates against the private stream and prints order, execution,
+/// position and wallet events as they arrive.
+///
+/// # Arguments
+/// * `api_key` - Bybit API key
+/// * `api_secret` - Bybit API secret
+/// * `is_testnet` - Whether to use testnet or mainnet
+///
+/// # Returns
+/// * `Result<(), Box<dyn Error>>` - Error if the example fails
+pub async fn run_private_example(
+    api_key: String,
+    api_secret: String,
+    is_testnet: bool,
+) -> Result<(), Box<dyn Error>> {
+    println!("🚀 Starting Bybit Private WebSocket Example");
+
+    let config = BybitWebSocketConfig {
+        is_testnet,
+        ..Default::default()
+    };
+
+    let mut client = BybitWebSocketClient::new_private(config, api_key, api_secret);
+    let (_sender, mut receiver) = client.connect().await?;
+
+    println!("✅ Connected to Bybit private WebSocket");
+
+    client.subscribe_private(&[
+        BybitPrivateTopic::Order,
+        BybitPrivateTopic::Execution,
+        BybitPrivateTopic::Position,
+        BybitPrivateTopic::Wallet,
+    ]).await?;
+
+    while let Some(message) = receiver.recv().await {
+        match message {
+            BybitWebSocketMessage::Auth(auth) => {
+                if auth.success {
+                    println!("🔑 Authenticated");
+                } else {
+                    println!("❌ Authentication failed: {}", auth.ret_msg);
+                    break;
+                }
+            }
+            BybitWebSocketMessage::Subscription(sub) => {
+                println!("📋 Subscription Response: success={} {}", sub.success, sub.ret_msg);
+            }
+            BybitWebSocketMessage::Order(orders) => {
+                for order in orders.data {
+                    println!("📝 Order {} {} {} {} @ {} [{}]", order.symbol, order.orderLinkId, order.side, order.qty, order.price, order.orderStatus);
+                }
+            }
+            BybitWebSocketMessage::Execution(executions) => {
+                for execution in executions.data {
+                    println!("💥 Fill {} {} {} @ {} maker={}", execution.symbol, execution.side, execution.execQty, execution.execPrice, execution.isMaker);
+                }
+            }
+            BybitWebSocketMessage::Position(positions) => {
+                for position in positions.data {
+                    println!("📍 Position {} {} {} @ {}", position.symbol, position.side, position.size, position.entryPrice);
+                }
+            }
+            BybitWebSocketMessage::Wallet(wallets) => {
+                for wallet in wallets.data {
+                    println!("💰 Wallet {} equity={} IM={} MM={}", wallet.accountType, wallet.totalEquity, wallet.accountIMRate, wallet.accountMMRate);
+                }
+            }
+            BybitWebSocketMessage::Disconnected(reason) => {
+                println!("🔌 Disconnected: {}", reason);
+            }
+            BybitWebSocketMessage::Reconnected { attempt } => {
+                println!("🔁 Reconnected after {} attempt(s)", attempt);
+            }
+            BybitWebSocketMessage::GaveUp => {
+                return Err("Gave up reconnecting to Bybit private WebSocket".into());
+            }
+            BybitWebSocketMessage::Orderbook(_) | BybitWebSocketMessage::Trade(_) | BybitWebSocketMessage::Dcp => {}
+            BybitWebSocketMessage::Ping => {
+                println!("🏓 Received ping");
+            }
+            BybitWebSocketMessage::Pong => {
+                println!("🏓 Received pong");
+            }
+            BybitWebSocketMessage::Heartbeat { rtt_ms } => {
+                println!("💓 Heartbeat rtt: {}ms", rtt_ms);
+            }
+            BybitWebSocketMessage::Error(err) => {
+                println!("❌ Error: {}", err);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,29 @@
+// Field names mirror the venue JSON, so they stay camelCase
+#![allow(non_snake_case)]
+
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use crate::enums::{Exchanges, OrderType, Side, TimeInForce};
+use thiserror::Error;
+use std::collections::BTreeMap;
+use crate::data_structure::{QTSFill, Symbol};
+use crate::decimal::{DecimalError, Price, Qty};
+
+
+/*
+Bybit Response Status
+*/
 
+#[derive(Deserialize, Debug)]
+pub struct BybitRetStatus {
+    /*
+    Envelope shared by every v5 REST response, read before the typed body
+    https://bybit-exchange.github.io/docs/v5/error
+     */
+    pub retCode: i64,
+    pub retMsg: String,
+}
 
 /*
 Bybit Order Book
@@ -26,8 +48,8 @@ pub struct BybitOrderbookResult {
     https://bybit-exchange.github.io/docs/v5/market/orderbook
      */
     pub s: String,
-    pub a: Vec<(String,String)>,
-    pub b: Vec<(String,String)>,
+    pub a: Vec<(Price,Qty)>,
+    pub b: Vec<(Price,Qty)>,
     pub ts:i64,
     pub u: i64,
     pub seq:i64,
@@ -354,6 +376,279 @@ pub struct BybitOpenOrderItem {
     pub updatedTime: String
 }
 
+/*
+Bybit Order Entry
+*/
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitPlaceOrderRequest {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/create-order
+     */
+    pub category: String,
+    pub symbol: String,
+    pub side: Side,
+    pub orderType: OrderType,
+    pub qty: Qty,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,              // required for Limit orders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeInForce: Option<TimeInForce>,   // PostOnly for maker only quotes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderLinkId: Option<String>,        // our client order id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduceOnly: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub positionIdx: Option<i32>,           // 0=one-way, 1=hedge buy side, 2=hedge sell side
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitAmendOrderRequest {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/amend-order
+    Either orderId or orderLinkId is required
+     */
+    pub category: String,
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderLinkId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qty: Option<Qty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitCancelOrderRequest {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/cancel-order
+    Either orderId or orderLinkId is required
+     */
+    pub category: String,
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderLinkId: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitCancelAllRequest {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/cancel-all
+     */
+    pub category: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseCoin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settleCoin: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitOrderResponse {
+    /*
+    Shared by create, amend and cancel
+    https://bybit-exchange.github.io/docs/v5/order/create-order
+     */
+    pub retCode: i32,
+    pub retMsg: String,
+    pub result: BybitOrderResult,
+    pub retExtInfo: Value,
+    pub time: i64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitOrderResult {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/create-order
+     */
+    pub orderId: String,
+    pub orderLinkId: String
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitCancelAllResponse {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/cancel-all
+     */
+    pub retCode: i32,
+    pub retMsg: String,
+    pub result: BybitCancelAllResult,
+    pub retExtInfo: Value,
+    pub time: i64
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitCancelAllResult {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/cancel-all
+     */
+    pub list: Vec<BybitOrderResult>,
+    pub success: Option<String>             // "1" on success, only returned for spot/linear/inverse
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitDcpRequest {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/dcp
+     */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,            // OPTIONS (default), DERIVATIVES or SPOT
+    pub timeWindow: u32,                    // seconds, 3 to 300
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitDcpResponse {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/dcp
+     */
+    pub retCode: i32,
+    pub retMsg: String,
+}
+
+/*
+Bybit Batch Order Entry
+*/
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitBatchRequest<T> {
+    /*
+    Wrapper shared by create-batch, amend-batch and cancel-batch
+    https://bybit-exchange.github.io/docs/v5/order/batch-place
+     */
+    pub category: String,
+    pub request: Vec<T>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitBatchPlaceItem {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/batch-place
+     */
+    pub symbol: String,
+    pub side: Side,
+    pub orderType: OrderType,
+    pub qty: Qty,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeInForce: Option<TimeInForce>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderLinkId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduceOnly: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub positionIdx: Option<i32>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitBatchAmendItem {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/batch-amend
+     */
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderLinkId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qty: Option<Qty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BybitBatchCancelItem {
+    /*
+    https://bybit-exchange.github.io/docs/v5/order/batch-cancel
+     */
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orderLinkId: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitBatchOrderResponse {
+    /*
+    Shared by create-batch, amend-batch and cancel-batch. result.list and
+    retExtInfo.list are in the same order as the request list.
+    https://bybit-exchange.github.io/docs/v5/order/batch-place
+     */
+    pub retCode: i32,
+    pub retMsg: String,
+    pub result: BybitBatchOrderResult,
+    pub retExtInfo: BybitBatchExtInfo,
+    pub time: i64
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitBatchOrderResult {
+    pub list: Vec<BybitBatchOrderItem>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitBatchOrderItem {
+    pub category: String,
+    pub symbol: String,
+    pub orderId: String,
+    pub orderLinkId: String,
+    pub createAt: Option<String>            // only returned by create-batch
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BybitBatchExtInfo {
+    pub list: Vec<BybitBatchOrderStatus>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitBatchOrderStatus {
+    pub code: i32,                          // 0 means this order succeeded
+    pub msg: String
+}
+
+/// Outcome of a single order within a batch request
+#[derive(Debug, Clone)]
+pub struct BybitBatchOrderOutcome {
+    pub symbol: String,
+    pub orderId: String,
+    pub orderLinkId: String,
+    pub code: i32,
+    pub msg: String,
+}
+
+impl BybitBatchOrderOutcome {
+    pub fn is_success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+impl BybitBatchOrderResponse {
+    /// Pair every order with its own status code, in request order
+    pub fn outcomes(&self) -> Vec<BybitBatchOrderOutcome> {
+        self.result.list.iter()
+            .zip(self.retExtInfo.list.iter())
+            .map(|(item, status)| BybitBatchOrderOutcome {
+                symbol: item.symbol.clone(),
+                orderId: item.orderId.clone(),
+                orderLinkId: item.orderLinkId.clone(),
+                code: status.code,
+                msg: status.msg.clone(),
+            })
+            .collect()
+    }
+
+    /// Orders within the batch that the venue rejected
+    pub fn failures(&self) -> Vec<BybitBatchOrderOutcome> {
+        self.outcomes().into_iter().filter(|outcome| !outcome.is_success()).collect()
+    }
+}
+
 /*
 Bybit WebSocket Orderbook Response
 */
@@ -378,12 +673,35 @@ pub struct BybitWebSocketOrderbookData {
     Orderbook data structure for WebSocket responses
     */
     pub s: String,  // Symbol name
-    pub b: Vec<[String; 2]>,  // Bids array - [price, size]
-    pub a: Vec<[String; 2]>,  // Asks array - [price, size]
+    pub b: Vec<(Price, Qty)>,  // Bids array - [price, size]
+    pub a: Vec<(Price, Qty)>,  // Asks array - [price, size]
     pub u: u64,     // Update ID
     pub seq: u64,   // Cross sequence
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketTradeResponse {
+    /*
+    Public trades, oldest first, several can come in one message
+    https://bybit-exchange.github.io/docs/v5/ws/public/trade
+    */
+    pub topic: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub ts: u64,
+    pub data: Vec<BybitWebSocketTradeData>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketTradeData {
+    pub T: u64,     // Trade time
+    pub s: String,  // Symbol name
+    pub S: String,  // Taker side, Buy or Sell
+    pub v: Qty,     // Trade size
+    pub p: Price,   // Trade price
+    pub i: String,  // Trade ID
+}
+
 /*
 Bybit WebSocket Generic Response
 For handling different types of WebSocket messages
@@ -422,162 +740,376 @@ Bybit WebSocket Subscription Response
 #[derive(Deserialize, Debug, Clone)]
 pub struct BybitWebSocketSubscriptionResponse {
     /*
-    WebSocket subscription response structure
+    WebSocket subscription response structure, also used for the auth reply
+    Private stream replies do not echo args
     */
     pub success: bool,
     pub ret_msg: String,
     pub conn_id: String,
     pub op: String,
+    #[serde(default)]
     pub args: Vec<String>,
 }
 
+/*
+Bybit WebSocket Private Streams
+https://bybit-exchange.github.io/docs/v5/ws/private/order
+*/
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketPrivateResponse<T> {
+    /*
+    Envelope shared by every private topic
+    */
+    pub id: String,
+    pub topic: String,
+    pub creationTime: i64,
+    pub data: Vec<T>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketOrderData {
+    /*
+    https://bybit-exchange.github.io/docs/v5/ws/private/order
+    */
+    pub category: String,
+    pub symbol: String,
+    pub orderId: String,
+    pub orderLinkId: String,
+    pub side: String,
+    pub orderType: String,
+    pub orderStatus: String,    // New, PartiallyFilled, Filled, Cancelled, Rejected, ...
+    pub price: String,
+    pub qty: String,
+    pub timeInForce: String,
+    pub avgPrice: String,
+    pub leavesQty: String,
+    pub cumExecQty: String,
+    pub cumExecValue: String,
+    pub cumExecFee: String,
+    pub reduceOnly: bool,
+    pub rejectReason: String,
+    pub cancelType: String,
+    pub createdTime: String,
+    pub updatedTime: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketExecutionData {
+    /*
+    https://bybit-exchange.github.io/docs/v5/ws/private/execution
+    */
+    pub category: String,
+    pub symbol: String,
+    pub execId: String,
+    pub orderId: String,
+    pub orderLinkId: String,
+    pub side: String,
+    pub orderPrice: String,
+    pub orderQty: String,
+    pub leavesQty: String,
+    pub execPrice: String,
+    pub execQty: String,
+    pub execValue: String,
+    pub execFee: String,
+    pub feeRate: String,
+    pub execType: String,       // Trade, Funding, AdlTrade, BustTrade, ...
+    pub isMaker: bool,
+    pub execTime: String,
+    pub seq: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketPositionData {
+    /*
+    https://bybit-exchange.github.io/docs/v5/ws/private/position
+    */
+    pub category: String,
+    pub symbol: String,
+    pub side: String,           // Buy, Sell, or empty when flat
+    pub size: String,
+    pub positionIdx: i32,
+    pub entryPrice: String,
+    pub markPrice: String,
+    pub positionValue: String,
+    pub leverage: String,
+    pub unrealisedPnl: String,
+    pub curRealisedPnl: String,
+    pub cumRealisedPnl: String,
+    pub positionStatus: String,
+    pub updatedTime: String,
+    pub seq: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketWalletData {
+    /*
+    https://bybit-exchange.github.io/docs/v5/ws/private/wallet
+    */
+    pub accountType: String,
+    pub accountIMRate: String,
+    pub accountMMRate: String,
+    pub totalEquity: String,
+    pub totalWalletBalance: String,
+    pub totalMarginBalance: String,
+    pub totalAvailableBalance: String,
+    pub totalPerpUPL: String,
+    pub totalInitialMargin: String,
+    pub totalMaintenanceMargin: String,
+    pub coin: Vec<BybitWebSocketWalletCoin>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BybitWebSocketWalletCoin {
+    /*
+    https://bybit-exchange.github.io/docs/v5/ws/private/wallet
+    */
+    pub coin: String,
+    pub equity: String,
+    pub usdValue: String,
+    pub walletBalance: String,
+    pub unrealisedPnl: String,
+    pub cumRealisedPnl: String,
+    pub totalOrderIM: String,
+    pub totalPositionIM: String,
+    pub totalPositionMM: String,
+}
+
+pub type BybitWebSocketOrderResponse = BybitWebSocketPrivateResponse<BybitWebSocketOrderData>;
+pub type BybitWebSocketExecutionResponse = BybitWebSocketPrivateResponse<BybitWebSocketExecutionData>;
+pub type BybitWebSocketPositionResponse = BybitWebSocketPrivateResponse<BybitWebSocketPositionData>;
+pub type BybitWebSocketWalletResponse = BybitWebSocketPrivateResponse<BybitWebSocketWalletData>;
+
+impl BybitWebSocketOrderData {
+    /// Whether the order is finished, nothing more will fill on it
+    pub fn is_closed(&self) -> bool {
+        matches!(self.orderStatus.as_str(), "Filled" | "Cancelled" | "PartiallyFilledCanceled" | "Rejected" | "Deactivated")
+    }
+}
+
+impl BybitWebSocketExecutionData {
+    /// Normalize a trade execution, Bybit reports fees as positive costs
+    pub fn to_fill(&self) -> Result<QTSFill, DecimalError> {
+        let order_qty: Qty = self.orderQty.parse()?;
+        let leaves_qty: Qty = self.leavesQty.parse()?;
+        Ok(QTSFill {
+            symbol: self.symbol.clone(),
+            exchange: Exchanges::Bybit,
+            order_id: self.orderId.clone(),
+            client_order_id: self.orderLinkId.clone(),
+            side: if self.side == "Sell" { Side::Sell } else { Side::Buy },
+            price: self.execPrice.parse()?,
+            qty: self.execQty.parse()?,
+            filled_qty: order_qty - leaves_qty,
+            fee: self.execFee.parse().unwrap_or(0.0),
+            is_maker: self.isMaker,
+            time: self.execTime.parse().unwrap_or(0),
+        })
+    }
+}
+
 /*
 Bybit Orderbook Management
 */
 
 /// Represents a single price level in the orderbook
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct OrderbookLevel {
-    pub price: f64,
-    pub size: f64,
+    pub price: Price,
+    pub size: Qty,
+}
+
+/// Why a book could not be kept or an update trusted, the book needs a fresh snapshot unless noted
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum OrderbookSyncError {
+    /// Zero or negative tick size, there is no grid to key levels on
+    #[error("tick size {tick_size} must be positive")]
+    InvalidTickSize { tick_size: Price },
+    /// No snapshot has been applied yet
+    #[error("no snapshot applied yet")]
+    NoSnapshot,
+    /// One or more updates were missed
+    #[error("update id gap, expected {expected} got {received}")]
+    Gap { expected: u64, received: u64 },
+    /// Older or duplicate message, it was ignored and the book is still valid
+    #[error("stale sequence {received}, last applied {last}")]
+    StaleSequence { last: u64, received: u64 },
+    /// Best bid at or through best ask after applying the update
+    #[error("crossed book, best bid {best_bid} >= best ask {best_ask}")]
+    Crossed { best_bid: Price, best_ask: Price },
+    /// A level is off the tick grid, rounding it could merge two levels so nothing was applied
+    #[error("level price {price} is not on the {tick_size} tick")]
+    OffTick { price: Price, tick_size: Price },
 }
 
 /// Local orderbook state for managing snapshots and deltas
+///
+/// Levels are kept in ordered maps keyed by integer ticks (`price / tick_size`),
+/// so an update is a single O(log n) insert or remove instead of a scan and re-sort.
+/// Updates with a level off the tick grid are refused rather than rounded.
+/// Prices and sizes stay fixed-point end to end, nothing is parsed through f64.
 #[derive(Debug, Clone)]
 pub struct LocalOrderbook {
     pub symbol: String,
-    pub bids: Vec<OrderbookLevel>,  // Sorted by price descending (highest first)
-    pub asks: Vec<OrderbookLevel>,  // Sorted by price ascending (lowest first)
+    pub tick_size: Price,           // Symbol.min_tick
+    bids: BTreeMap<i64, Qty>,       // ticks -> size, best bid is the last key
+    asks: BTreeMap<i64, Qty>,       // ticks -> size, best ask is the first key
     pub last_update_id: u64,
     pub last_sequence: u64,
     pub last_timestamp: u64,
+    pub needs_snapshot: bool,       // set until a snapshot arrives, and again once the book can't be trusted
 }
 
 impl LocalOrderbook {
-    /// Create a new empty orderbook
-    pub fn new(symbol: String) -> Self {
-        Self {
+    /// Create a new empty orderbook, the tick size must be positive
+    pub fn new(symbol: String, tick_size: Price) -> Result<Self, OrderbookSyncError> {
+        if !tick_size.is_positive() {
+            return Err(OrderbookSyncError::InvalidTickSize { tick_size });
+        }
+        Ok(Self {
             symbol,
-            bids: Vec::new(),
-            asks: Vec::new(),
+            tick_size,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
             last_update_id: 0,
             last_sequence: 0,
             last_timestamp: 0,
-        }
+            needs_snapshot: true,
+        })
+    }
+
+    /// Create a new empty orderbook for a configured instrument
+    pub fn from_symbol(symbol: &Symbol) -> Result<Self, OrderbookSyncError> {
+        Self::new(symbol.symbol.clone(), symbol.min_tick)
+    }
+
+    /// Drop every level and wait for a new snapshot, e.g. after a reconnect
+    pub fn reset(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.needs_snapshot = true;
     }
 
     /// Apply a snapshot update (completely replace the orderbook)
-    pub fn apply_snapshot(&mut self, snapshot: &BybitWebSocketOrderbookData) {
-        println!("🔄 Applying snapshot for {}", self.symbol);
+    pub fn apply_snapshot(&mut self, snapshot: &BybitWebSocketOrderbookData) -> Result<(), OrderbookSyncError> {
+        self.apply_snapshot_levels(&snapshot.b, &snapshot.a, snapshot.u, snapshot.seq)
+    }
+
+    /// Apply a delta update (modify existing orderbook)
+    ///
+    /// Bybit's `u` must be exactly the previous `u` + 1 and `seq` must move forward.
+    /// On a gap, an off-tick level or a crossed book the book is flagged with `needs_snapshot` and further
+    /// deltas are refused until a snapshot is applied. Stale messages are skipped.
+    pub fn apply_delta(&mut self, delta: &BybitWebSocketOrderbookData) -> Result<(), OrderbookSyncError> {
+        if self.needs_snapshot {
+            return Err(OrderbookSyncError::NoSnapshot);
+        }
+        if delta.seq <= self.last_sequence {
+            return Err(OrderbookSyncError::StaleSequence { last: self.last_sequence, received: delta.seq });
+        }
+        if delta.u != self.last_update_id + 1 {
+            self.needs_snapshot = true;
+            return Err(OrderbookSyncError::Gap { expected: self.last_update_id + 1, received: delta.u });
+        }
         
+        self.apply_delta_levels(&delta.b, &delta.a, delta.u, delta.seq)?;
+        self.check_crossed()?;
+        Ok(())
+    }
+
+    /// Flag the book for a resnapshot if it is crossed
+    pub fn check_crossed(&mut self) -> Result<(), OrderbookSyncError> {
+        if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask())
+            && bid.price >= ask.price {
+            self.needs_snapshot = true;
+            return Err(OrderbookSyncError::Crossed { best_bid: bid.price, best_ask: ask.price });
+        }
+        Ok(())
+    }
+
+    /// Flag the book for a resnapshot if any level is off the tick grid
+    fn check_on_tick(&mut self, levels: &[(Price, Qty)]) -> Result<(), OrderbookSyncError> {
+        if let Some(&(price, _)) = levels.iter().find(|(price, _)| !price.is_on_tick(self.tick_size)) {
+            self.needs_snapshot = true;
+            return Err(OrderbookSyncError::OffTick { price, tick_size: self.tick_size });
+        }
+        Ok(())
+    }
+
+    /// Replace the whole book from raw [price, size] levels
+    /// Venue agnostic, so other exchanges can keep the same local book
+    pub fn apply_snapshot_levels(&mut self, bids: &[(Price, Qty)], asks: &[(Price, Qty)], update_id: u64, sequence: u64) -> Result<(), OrderbookSyncError> {
+        self.check_on_tick(bids)?;
+        self.check_on_tick(asks)?;
+
         // Clear existing data
         self.bids.clear();
         self.asks.clear();
         
-        // Parse and add bids (convert strings to f64)
-        for bid in &snapshot.b {
-            if let (Ok(price), Ok(size)) = (bid[0].parse::<f64>(), bid[1].parse::<f64>()) {
-                if size > 0.0 {  // Only add non-zero sizes
-                    self.bids.push(OrderbookLevel { price, size });
-                }
+        // Add levels, only non-zero sizes
+        for &(price, size) in bids {
+            if size.is_positive() {
+                self.bids.insert(price.to_ticks(self.tick_size), size);
             }
         }
-        
-        // Parse and add asks (convert strings to f64)
-        for ask in &snapshot.a {
-            if let (Ok(price), Ok(size)) = (ask[0].parse::<f64>(), ask[1].parse::<f64>()) {
-                if size > 0.0 {  // Only add non-zero sizes
-                    self.asks.push(OrderbookLevel { price, size });
-                }
+        for &(price, size) in asks {
+            if size.is_positive() {
+                self.asks.insert(price.to_ticks(self.tick_size), size);
             }
         }
         
-        // Sort bids by price descending (highest first)
-        self.bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
-        
-        // Sort asks by price ascending (lowest first)
-        self.asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
-        
         // Update metadata
-        self.last_update_id = snapshot.u;
-        self.last_sequence = snapshot.seq;
-        self.last_timestamp = snapshot.u; // Using update ID as timestamp
-        
-        println!("✅ Snapshot applied: {} bids, {} asks", self.bids.len(), self.asks.len());
+        self.last_update_id = update_id;
+        self.last_sequence = sequence;
+        self.last_timestamp = update_id; // Using update ID as timestamp
+        self.needs_snapshot = false;
+        Ok(())
     }
 
-    /// Apply a delta update (modify existing orderbook)
-    pub fn apply_delta(&mut self, delta: &BybitWebSocketOrderbookData) {
-        println!("📈 Applying delta for {}", self.symbol);
-        
-        // Apply bid updates
-        for bid in &delta.b {
-            if let (Ok(price), Ok(size)) = (bid[0].parse::<f64>(), bid[1].parse::<f64>()) {
-                self.update_bid_level(price, size);
-            }
+    /// Apply raw [price, size] level changes, a size of zero removes the level
+    pub fn apply_delta_levels(&mut self, bids: &[(Price, Qty)], asks: &[(Price, Qty)], update_id: u64, sequence: u64) -> Result<(), OrderbookSyncError> {
+        self.check_on_tick(bids)?;
+        self.check_on_tick(asks)?;
+
+        for &(price, size) in bids {
+            Self::update_level(&mut self.bids, price.to_ticks(self.tick_size), size);
         }
-        
-        // Apply ask updates
-        for ask in &delta.a {
-            if let (Ok(price), Ok(size)) = (ask[0].parse::<f64>(), ask[1].parse::<f64>()) {
-                self.update_ask_level(price, size);
-            }
+        for &(price, size) in asks {
+            Self::update_level(&mut self.asks, price.to_ticks(self.tick_size), size);
         }
         
         // Update metadata
-        self.last_update_id = delta.u;
-        self.last_sequence = delta.seq;
-        self.last_timestamp = delta.u;
-        
-        println!("✅ Delta applied: {} bids, {} asks", self.bids.len(), self.asks.len());
+        self.last_update_id = update_id;
+        self.last_sequence = sequence;
+        self.last_timestamp = update_id;
+        Ok(())
     }
 
-    /// Update a bid level (price, size)
-    fn update_bid_level(&mut self, price: f64, size: f64) {
-        if size == 0.0 {
-            // Remove the level
-            self.bids.retain(|level| level.price != price);
+    /// Set or remove a single level
+    fn update_level(side: &mut BTreeMap<i64, Qty>, ticks: i64, size: Qty) {
+        if size.is_zero() {
+            side.remove(&ticks);
         } else {
-            // Find existing level or insert new one
-            if let Some(pos) = self.bids.iter().position(|level| level.price == price) {
-                self.bids[pos].size = size;
-            } else {
-                self.bids.push(OrderbookLevel { price, size });
-                // Re-sort to maintain descending order
-                self.bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
-            }
+            side.insert(ticks, size);
         }
     }
 
-    /// Update an ask level (price, size)
-    fn update_ask_level(&mut self, price: f64, size: f64) {
-        if size == 0.0 {
-            // Remove the level
-            self.asks.retain(|level| level.price != price);
-        } else {
-            // Find existing level or insert new one
-            if let Some(pos) = self.asks.iter().position(|level| level.price == price) {
-                self.asks[pos].size = size;
-            } else {
-                self.asks.push(OrderbookLevel { price, size });
-                // Re-sort to maintain ascending order
-                self.asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
-            }
-        }
+    fn to_level(&self, (ticks, size): (&i64, &Qty)) -> OrderbookLevel {
+        OrderbookLevel { price: Price::from_ticks(*ticks, self.tick_size), size: *size }
     }
 
     /// Get the best bid (highest bid price)
-    pub fn best_bid(&self) -> Option<&OrderbookLevel> {
-        self.bids.first()
+    pub fn best_bid(&self) -> Option<OrderbookLevel> {
+        self.bids.iter().next_back().map(|level| self.to_level(level))
     }
 
     /// Get the best ask (lowest ask price)
-    pub fn best_ask(&self) -> Option<&OrderbookLevel> {
-        self.asks.first()
+    pub fn best_ask(&self) -> Option<OrderbookLevel> {
+        self.asks.iter().next().map(|level| self.to_level(level))
     }
 
     /// Get the spread (ask - bid)
-    pub fn spread(&self) -> Option<f64> {
+    pub fn spread(&self) -> Option<Price> {
         if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) {
             Some(ask.price - bid.price)
         } else {
@@ -585,19 +1117,34 @@ impl LocalOrderbook {
         }
     }
 
+    /// Bids from best (highest) to worst
+    pub fn bids(&self) -> impl Iterator<Item = OrderbookLevel> + '_ {
+        self.bids.iter().rev().map(|level| self.to_level(level))
+    }
+
+    /// Asks from best (lowest) to worst
+    pub fn asks(&self) -> impl Iterator<Item = OrderbookLevel> + '_ {
+        self.asks.iter().map(|level| self.to_level(level))
+    }
+
+    /// Number of (bid, ask) levels
+    pub fn depth(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
     /// Print current orderbook state
     pub fn print_summary(&self) {
         println!("📊 Orderbook Summary for {}", self.symbol);
         println!("   Update ID: {}, Sequence: {}", self.last_update_id, self.last_sequence);
         
         if let Some(bid) = self.best_bid() {
-            println!("   Best Bid: {:.2} @ {:.6}", bid.price, bid.size);
+            println!("   Best Bid: {} @ {}", bid.price, bid.size);
         }
         if let Some(ask) = self.best_ask() {
-            println!("   Best Ask: {:.2} @ {:.6}", ask.price, ask.size);
+            println!("   Best Ask: {} @ {}", ask.price, ask.size);
         }
         if let Some(spread) = self.spread() {
-            println!("   Spread: {:.2}", spread);
+            println!("   Spread: {}", spread);
         }
         println!("   Total Levels: {} bids, {} asks", self.bids.len(), self.asks.len());
     }
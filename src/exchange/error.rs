@@ -0,0 +1,116 @@
+use thiserror::Error;
+
+use crate::decimal::DecimalError;
+use crate::enums::Exchanges;
+
+/*
+    Error taxonomy shared by the REST clients
+
+    Venue error codes are mapped onto these variants by each client (see
+    BybitRestClient::read_response and BinanceRestClient::read_response), so strategy code can
+    branch on what went wrong instead of matching on message strings.
+*/
+
+pub type ExchangeResult<T> = Result<T, ExchangeError>;
+
+#[derive(Error, Debug, Clone)]
+pub enum ExchangeError {
+    /// Connection, TLS or timeout failure, the request may or may not have reached the venue
+    #[error("transport error: {0}")]
+    Transport(String),
+
+    /// Non-2xx status without a venue error body (gateway errors, maintenance pages)
+    #[error("HTTP {status}: {body}")]
+    Http { status: u16, body: String },
+
+    /// Venue understood the request and refused it (Bybit retCode, Binance code)
+    #[error("{exchange:?} rejected request, code {code}: {msg}")]
+    Rejected { exchange: Exchanges, code: i64, msg: String },
+
+    /// Request or order rate limit hit, `retry_after_ms` when the venue says how long to back off
+    #[error("{exchange:?} rate limited, code {code}: {msg}")]
+    RateLimited { exchange: Exchanges, code: i64, msg: String, retry_after_ms: Option<u64> },
+
+    /// Bad key, bad signature, missing permission or IP not whitelisted
+    #[error("{exchange:?} authentication failed, code {code}: {msg}")]
+    Auth { exchange: Exchanges, code: i64, msg: String },
+
+    /// Request timestamp fell outside recvWindow, usually local clock drift
+    #[error("{exchange:?} timestamp outside recvWindow, code {code}: {msg}")]
+    Timestamp { exchange: Exchanges, code: i64, msg: String },
+
+    /// Response body did not match the expected shape
+    #[error("failed to decode response: {0}")]
+    Decode(String),
+
+    /// Request was refused locally before being sent
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+}
+
+impl ExchangeError {
+    /// Venue error code, when the venue returned one
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            ExchangeError::Rejected { code, .. }
+            | ExchangeError::RateLimited { code, .. }
+            | ExchangeError::Auth { code, .. }
+            | ExchangeError::Timestamp { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Venue does not know the order, it was already filled or cancelled or never existed
+    pub fn is_order_not_found(&self) -> bool {
+        matches!(
+            self,
+            ExchangeError::Rejected { exchange: Exchanges::Bybit, code: 110001, .. }
+                | ExchangeError::Rejected { exchange: Exchanges::Binance, code: -2011, .. }
+        )
+    }
+
+    /// Whether sending the same request again later can succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ExchangeError::Transport(_)
+            | ExchangeError::RateLimited { .. }
+            | ExchangeError::Timestamp { .. } => true,
+            ExchangeError::Http { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ExchangeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            ExchangeError::Decode(e.to_string())
+        } else {
+            ExchangeError::Transport(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for ExchangeError {
+    fn from(e: serde_json::Error) -> Self {
+        ExchangeError::Decode(e.to_string())
+    }
+}
+
+impl From<DecimalError> for ExchangeError {
+    fn from(e: DecimalError) -> Self {
+        ExchangeError::Decode(e.to_string())
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for ExchangeError {
+    fn from(e: reqwest::header::InvalidHeaderValue) -> Self {
+        ExchangeError::InvalidRequest(format!("invalid header value: {}", e))
+    }
+}
+
+impl From<hmac::digest::InvalidLength> for ExchangeError {
+    fn from(e: hmac::digest::InvalidLength) -> Self {
+        ExchangeError::InvalidRequest(format!("invalid API secret: {}", e))
+    }
+}
@@ -1,2 +1,4 @@
+pub mod base_rest;
+pub mod error;
 pub mod bybit;
 pub mod binance;
@@ -0,0 +1,7 @@
+// Library side of the crate, so benches and integration tests can use the same modules as main
+pub mod enums;
+pub mod decimal;
+pub mod data_structure;
+pub mod exchange;
+pub mod oms;
+pub mod strategy;
@@ -1,12 +1,8 @@
+// The test_* helpers are switched on and off by hand in main
 
-// Import Class Objects
-mod enums;
-mod data_structure;
-mod exchange;
-mod strategy;
-
-use crate::strategy::eye::ee::ElectronicEye;
-use crate::exchange::bybit::response::{
+use rust_qts::strategy::eye::ee::ElectronicEye;
+use rust_qts::strategy::eye::feeds::FeedConfig;
+use rust_qts::exchange::bybit::response::{
     BybitOrderbookResponse, 
     BybitTickersResponse, 
     BybitAccInfoResponse,
@@ -15,30 +11,19 @@ use crate::exchange::bybit::response::{
     BybitOpenOrdersResponse
 
 };
-use crate::exchange::binance::binance_rest::BinanceRestClient;
-use crate::exchange::binance::response::{
-    BinanceOrderbookResponse, 
-    BinanceTickerResponse, 
-    BinanceTickersResponse, 
-    BinanceTickerItem,
-    BinancePositionResponse, 
-    BinancePositionItem
-};
-use crate::exchange::bybit::bybit_rest::BybitRestClient;
-use crate::exchange::bybit::bybit_ws::run_orderbook_example;
-use crate::exchange::binance::binance_ws::run_orderbook_example as run_binance_orderbook_example;
-use data_structure::{APIKey};
-use strategy::eye::params::EEConfig;
+use rust_qts::exchange::binance::binance_rest::BinanceRestClient;
+use rust_qts::exchange::binance::response::BinancePositionResponse;
+use rust_qts::exchange::bybit::bybit_rest::BybitRestClient;
+use rust_qts::exchange::bybit::bybit_ws::run_orderbook_example;
+use rust_qts::exchange::binance::binance_ws::run_orderbook_example as run_binance_orderbook_example;
+use rust_qts::data_structure::{APIKey};
+use rust_qts::strategy::eye::params::EEConfig;
 
 
 
 
-use reqwest::{Client, Response};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::{collections::HashMap, fmt::format,fs};
+use std::fs;
 use std::error::Error;
-use tokio::time::{sleep, Duration}; // Import tokio for async runtime and sleep function
 
 
 
@@ -49,14 +34,14 @@ async fn test_bybit_rest()-> Result<(), Box<dyn Error>>{
 
 
 
-    let mut by_rest_client: BybitRestClient = BybitRestClient::new(config.api_key,config.api_secret);
+    let by_rest_client: BybitRestClient = BybitRestClient::new(config.api_key,config.api_secret);
     let ord_response: BybitOpenOrdersResponse = by_rest_client.get_open_orders("linear",Some("TAUSDT"),None,None,None,None,None,None,None,None).await?;
 
 
     let pos_response: BybitPositionResponse = by_rest_client.get_positions("linear",None,None,Some("USDT"),None,None).await?;
 
     let acc_info_response: BybitWalletBalanceResponse = by_rest_client.get_account_info(None,None).await?;
-    let acc_type_response: BybitAccInfoResponse = by_rest_client.get_account_type().await?;
+    let _acc_type_response: BybitAccInfoResponse = by_rest_client.get_account_type().await?;
     let ob_response: BybitOrderbookResponse = by_rest_client.get_orderbook("linear","BTCUSDT").await?;
     let tk_response: BybitTickersResponse = by_rest_client.get_tickers("linear", None,None, None).await?;
 
@@ -81,7 +66,7 @@ async fn test_bybit_ws()-> Result<(), Box<dyn Error>>{
     // - Symbol: "BTCUSDT", "ETHUSDT", etc.
     // - Depth: 1, 50, 200, 500, 1000
     // - Testnet: true for testnet, false for mainnet
-    run_orderbook_example("BTCUSDT", 1, false).await?;
+    run_orderbook_example("BTCUSDT", 1, "0.1".parse()?, false).await?;
 
     Ok(())
 }
@@ -95,7 +80,7 @@ async fn test_binance_ws()-> Result<(), Box<dyn Error>>{
     // - Symbol: "BTCUSDT", "ETHUSDT", etc.
     // - Limit: Some(5), Some(10), Some(20), Some(50), Some(100), Some(500), Some(1000), or None for default
     // - Testnet: true for testnet, false for mainnet
-    run_binance_orderbook_example("BTCUSDT", Some(10), true).await?;
+    run_binance_orderbook_example("BTCUSDT", Some(10), "0.1".parse()?, true).await?;
 
     Ok(())
 }
@@ -110,8 +95,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Test EE Strategy
     if false{
         let config = EEConfig::from_yaml_file("config/ee_config.yaml")?;
-        let mut ee = ElectronicEye::new(config);
-        ee.run().await;
+        let quote_key: APIKey = serde_json::from_str(&fs::read_to_string("config/bybit_main.json")?)?;
+        let hedge_key: APIKey = serde_json::from_str(&fs::read_to_string("config/binance_main.json")?)?;
+        let quote_feed = FeedConfig { credentials: Some((quote_key.api_key.clone(), quote_key.api_secret.clone())), ..Default::default() };
+        let hedge_feed = FeedConfig { credentials: Some((hedge_key.api_key.clone(), hedge_key.api_secret.clone())), ..Default::default() };
+        let mut ee = ElectronicEye::new(config, quote_key, hedge_key)?;
+        ee.start_feeds(quote_feed, hedge_feed).await?;
+        ee.watch_config("config/ee_config.yaml");
+        ee.stop_on_signals();
+
+        // Non-zero when orders may have been left on a venue
+        if !ee.run().await {
+            std::process::exit(1);
+        }
     }
 
 
@@ -140,6 +136,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
         println!("Position Info: {:?}", pos_response.list);
     }
 
+    // test bybit rest
+    if false {
+        test_bybit_rest().await?;
+    }
+
+    // test bybit websocket
+    if false {
+        test_bybit_ws().await?;
+    }
+
     // test binance websocket
     if true {
         test_binance_ws().await?;
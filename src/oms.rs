@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::data_structure::{QTSFill, QTSOpenOrder, QTSOrderAck, QTSOrderRequest, Symbol};
+use crate::decimal::{Price, Qty};
+use crate::enums::{Exchanges, OrderType, Side, TimeInForce};
+use crate::exchange::error::{ExchangeError, ExchangeResult};
+
+/*
+    Order management
+
+    Every order we send is tracked by its client order id (orderLinkId on Bybit,
+    newClientOrderId on Binance) from the moment it is built until the venue closes it:
+
+        PendingNew ──ack──> New ──fill──> PartiallyFilled ──fill──> Filled
+            │                │                   │
+            │                └──────cancel sent──┴──> PendingCancel ──ack / stream──> Cancelled
+            └──reject──> Rejected
+
+    The REST ack and the private stream race each other, whichever reports further along
+    wins and the other is ignored, e.g. a fill streamed before the ack leaves the order
+    PartiallyFilled. Fill quantities only ever go up, so a fill arriving after a cancel was
+    acked still ends up Filled.
+
+    Legs without a stream are reconciled against the venue's open orders instead, anything
+    we track that is no longer listed is closed, anything still listed is working again, and
+    orders carrying our prefix that we don't track are handed back as orphans.
+*/
+
+// Both venues cap client order ids at 36 characters
+pub const MAX_CLIENT_ORDER_ID_LEN: usize = 36;
+// An order only just sent may not be listed yet
+pub const RECONCILE_GRACE_MS: i64 = 2000;
+// How long closed orders are kept around for late stream updates
+pub const CLOSED_RETENTION_MS: i64 = 60_000;
+
+/// New client order id, the prefix followed by as much of a v4 uuid as fits
+pub fn new_client_order_id(prefix: &str) -> String {
+    let uuid = Uuid::new_v4().simple().to_string();
+    let room = MAX_CLIENT_ORDER_ID_LEN.saturating_sub(prefix.len()).min(uuid.len());
+    format!("{}{}", prefix, &uuid[..room])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    PendingNew,         // sent, no ack yet
+    New,
+    PartiallyFilled,
+    Filled,
+    PendingCancel,      // cancel sent, still live until the venue says otherwise
+    Cancelled,          // cancelled or expired, possibly after partial fills
+    Rejected,
+}
+
+impl OrderState {
+    /// Still working on the venue, or may be
+    pub fn is_working(self) -> bool {
+        !matches!(self, OrderState::Filled | OrderState::Cancelled | OrderState::Rejected)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ManagedOrder {
+    pub symbol: String,
+    pub exchange: Exchanges,
+    pub client_order_id: String,
+    pub order_id: Option<String>,       // venue id, once acked or listed
+    pub side: Side,
+    pub order_type: OrderType,
+    pub price: Option<Price>,
+    pub qty: Qty,
+    pub filled_qty: Qty,
+    pub time_in_force: TimeInForce,
+    pub reduce_only: bool,
+    pub state: OrderState,
+    pub created_ms: i64,
+    pub updated_ms: i64,
+}
+
+impl ManagedOrder {
+    fn is_for(&self, symbol: &Symbol) -> bool {
+        self.symbol == symbol.symbol && self.exchange == symbol.exchange
+    }
+
+    /// Same shape as the venue's open orders, e.g. for the quoter's diff
+    pub fn to_open_order(&self) -> QTSOpenOrder {
+        QTSOpenOrder {
+            symbol: self.symbol.clone(),
+            exchange: self.exchange.clone(),
+            order_id: self.order_id.clone().unwrap_or_default(),
+            client_order_id: self.client_order_id.clone(),
+            side: self.side,
+            price: self.price.unwrap_or(Price::ZERO),
+            qty: self.qty,
+            filled_qty: self.filled_qty,
+            status: format!("{:?}", self.state),
+            reduce_only: self.reduce_only,
+            time: self.created_ms,
+        }
+    }
+
+    fn apply_fill(&mut self, filled_qty: Qty) {
+        if filled_qty <= self.filled_qty {
+            return;
+        }
+        self.filled_qty = filled_qty;
+        if self.filled_qty >= self.qty {
+            self.state = OrderState::Filled;
+        } else if matches!(self.state, OrderState::PendingNew | OrderState::New) {
+            self.state = OrderState::PartiallyFilled;
+        }
+    }
+
+    fn close(&mut self) {
+        if self.state.is_working() {
+            self.state = if self.filled_qty >= self.qty { OrderState::Filled } else { OrderState::Cancelled };
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OrderManager {
+    orders: HashMap<String, ManagedOrder>,      // client order id -> order
+}
+
+impl OrderManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn order(&self, client_order_id: &str) -> Option<&ManagedOrder> {
+        self.orders.get(client_order_id)
+    }
+
+    /// Orders that are or may still be live on a symbol, oldest first
+    pub fn working_orders(&self, symbol: &Symbol) -> Vec<&ManagedOrder> {
+        let mut orders: Vec<_> = self.orders.values().filter(|order| order.is_for(symbol) && order.state.is_working()).collect();
+        orders.sort_by_key(|order| order.created_ms);
+        orders
+    }
+
+    /// Start tracking an order about to be sent, as PendingNew
+    ///
+    /// # Arguments
+    /// * `symbol` - Instrument the order is for
+    /// * `request` - Order as it will be sent, its client order id is the key from here on
+    /// * `now_ms` - Current time
+    pub fn track(&mut self, symbol: &Symbol, request: &QTSOrderRequest, now_ms: i64) {
+        self.orders.insert(request.client_order_id.clone(), ManagedOrder {
+            symbol: symbol.symbol.clone(),
+            exchange: symbol.exchange.clone(),
+            client_order_id: request.client_order_id.clone(),
+            order_id: None,
+            side: request.side,
+            order_type: request.order_type,
+            price: request.price,
+            qty: request.qty,
+            filled_qty: Qty::ZERO,
+            time_in_force: request.time_in_force,
+            reduce_only: request.reduce_only,
+            state: OrderState::PendingNew,
+            created_ms: now_ms,
+            updated_ms: now_ms,
+        });
+    }
+
+    /// Start tracking an order found on the venue, e.g. one left by an earlier run
+    ///
+    /// Only limit orders rest, so it is taken as a GTC limit order
+    pub fn adopt(&mut self, symbol: &Symbol, open: &QTSOpenOrder, now_ms: i64) {
+        let state = if open.filled_qty.is_zero() { OrderState::New } else { OrderState::PartiallyFilled };
+        self.orders.insert(open.client_order_id.clone(), ManagedOrder {
+            symbol: symbol.symbol.clone(),
+            exchange: symbol.exchange.clone(),
+            client_order_id: open.client_order_id.clone(),
+            order_id: Some(open.order_id.clone()),
+            side: open.side,
+            order_type: OrderType::Limit,
+            price: Some(open.price),
+            qty: open.qty,
+            filled_qty: open.filled_qty,
+            time_in_force: TimeInForce::GTC,
+            reduce_only: open.reduce_only,
+            state,
+            created_ms: if open.time > 0 { open.time } else { now_ms },
+            updated_ms: now_ms,
+        });
+    }
+
+    /// Result of sending an order
+    ///
+    /// A transport failure leaves it PendingNew, the order may have reached the venue and
+    /// the next stream update or reconcile settles it
+    pub fn on_place_result(&mut self, client_order_id: &str, result: &ExchangeResult<QTSOrderAck>, now_ms: i64) {
+        let Some(order) = self.orders.get_mut(client_order_id) else {
+            return;
+        };
+        order.updated_ms = now_ms;
+        match result {
+            Ok(ack) => {
+                order.order_id = Some(ack.order_id.clone());
+                if order.state == OrderState::PendingNew {
+                    order.state = OrderState::New;
+                }
+            }
+            Err(ExchangeError::Transport(_) | ExchangeError::Http { .. }) => {}
+            Err(_) => {
+                if order.state == OrderState::PendingNew {
+                    order.state = OrderState::Rejected;
+                }
+            }
+        }
+    }
+
+    /// A cancel is about to be sent
+    pub fn on_cancel_sent(&mut self, client_order_id: &str, now_ms: i64) {
+        if let Some(order) = self.orders.get_mut(client_order_id)
+            && order.state.is_working() {
+            order.state = OrderState::PendingCancel;
+            order.updated_ms = now_ms;
+        }
+    }
+
+    /// Result of a cancel, the order is closed on an ack or when the venue no longer knows it,
+    /// any other error leaves it PendingCancel until the stream or a reconcile says otherwise
+    pub fn on_cancel_result(&mut self, client_order_id: &str, result: &ExchangeResult<QTSOrderAck>, now_ms: i64) {
+        let Some(order) = self.orders.get_mut(client_order_id) else {
+            return;
+        };
+        order.updated_ms = now_ms;
+        match result {
+            Ok(_) => order.close(),
+            Err(e) if e.is_order_not_found() => order.close(),
+            Err(_) => {}
+        }
+    }
+
+    /// A fill from a private stream, or from a paper venue
+    pub fn on_fill(&mut self, fill: &QTSFill, now_ms: i64) {
+        if let Some(order) = self.orders.get_mut(&fill.client_order_id) {
+            // Cumulative, a fill already counted by a reconcile or seen twice doesn't add up again
+            order.apply_fill(fill.filled_qty);
+            order.updated_ms = now_ms;
+        }
+    }
+
+    /// The venue closed an order
+    ///
+    /// # Arguments
+    /// * `client_order_id` - Our id of the order
+    /// * `status` - Venue status, Filled, Rejected or any of the cancelled and expired ones
+    /// * `filled_qty` - Final cumulative fill
+    /// * `now_ms` - Current time
+    ///
+    /// # Returns
+    /// * `Option<OrderState>` - State the order was in before, None for orders we don't track
+    pub fn on_closed(&mut self, client_order_id: &str, status: &str, filled_qty: Qty, now_ms: i64) -> Option<OrderState> {
+        let order = self.orders.get_mut(client_order_id)?;
+        let previous = order.state;
+        order.apply_fill(filled_qty);
+        order.updated_ms = now_ms;
+        if status == "Rejected" && previous == OrderState::PendingNew {
+            order.state = OrderState::Rejected;
+        }
+        order.close();
+        Some(previous)
+    }
+
+    /// Line the tracked orders on a symbol up with the venue's open orders
+    ///
+    /// # Arguments
+    /// * `symbol` - Instrument the snapshot is for
+    /// * `open_orders` - Venue's open orders on it
+    /// * `prefix` - Client order id prefix of the orders we own
+    /// * `now_ms` - Current time
+    ///
+    /// # Returns
+    /// * `Vec<QTSOpenOrder>` - Open orders carrying the prefix that aren't tracked
+    pub fn reconcile(&mut self, symbol: &Symbol, open_orders: &[QTSOpenOrder], prefix: &str, now_ms: i64) -> Vec<QTSOpenOrder> {
+        let mut orphans = Vec::new();
+        for open in open_orders {
+            match self.orders.get_mut(&open.client_order_id) {
+                Some(order) => {
+                    // Still live whatever we thought, including closed orders. A cancel still out
+                    // or refused stays PendingCancel so it is sent again
+                    order.order_id = Some(open.order_id.clone());
+                    order.filled_qty = order.filled_qty.max(open.filled_qty);
+                    if order.state != OrderState::PendingCancel {
+                        order.state = if order.filled_qty > Qty::ZERO { OrderState::PartiallyFilled } else { OrderState::New };
+                    }
+                    order.updated_ms = now_ms;
+                }
+                None if open.client_order_id.starts_with(prefix) => orphans.push(open.clone()),
+                None => {}
+            }
+        }
+
+        for order in self.orders.values_mut().filter(|order| order.is_for(symbol) && order.state.is_working()) {
+            let listed = open_orders.iter().any(|open| open.client_order_id == order.client_order_id);
+            let in_flight = order.state == OrderState::PendingNew && now_ms - order.created_ms < RECONCILE_GRACE_MS;
+            if !listed && !in_flight {
+                order.close();
+                order.updated_ms = now_ms;
+            }
+        }
+        orphans
+    }
+
+    /// Forget orders closed for longer than CLOSED_RETENTION_MS
+    pub fn prune(&mut self, now_ms: i64) {
+        self.orders.retain(|_, order| order.state.is_working() || now_ms - order.updated_ms < CLOSED_RETENTION_MS);
+    }
+}
@@ -1,41 +1,208 @@
 use crate::strategy::eye::params::EEConfig;
+use crate::strategy::eye::quoter::{build_ladder, diff_orders, quote_level, quote_order_id, reduce_only_ladder, DesiredOrder, QuoteDiff, QUOTE_ID_PREFIX};
+use crate::strategy::eye::risk::MarginMonitor;
+use crate::strategy::eye::ledger::Ledger;
+use crate::strategy::eye::reload;
+use crate::strategy::eye::hedger::{HedgeAction, Hedger, HEDGE_ID_PREFIX};
+use crate::strategy::eye::events::{self, EEReceivers, EESenders, ExecutionEvent, FeedEvent, FeedStatus, Leg, MarketUpdate, Stream};
+use crate::strategy::eye::feeds::{self, FeedConfig};
+use crate::strategy::eye::paper::PaperExchange;
+use crate::exchange::base_rest::{ExchangeRest, new_rest_client};
+use crate::data_structure::{APIKey, QTSOpenOrder, QTSOrderBook, QTSOrderRequest};
+use crate::exchange::error::ExchangeResult;
+use crate::oms::{ManagedOrder, OrderManager, OrderState};
+use crate::decimal::{Price, Qty};
+use crate::enums::{OrderType, Side, TimeInForce};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
 use chrono;
 use std::time::Duration;
 use tokio;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+
+// Quote and hedge leg clients
+type ExchangePair = (Box<dyn ExchangeRest>, Box<dyn ExchangeRest>);
+
+// Timer driving hedge batches, held back requotes and the backoffice
+const TIMER_INTERVAL_MS: u64 = 100;
+// How often legs without a stream are polled over REST instead
+const REST_POLL_INTERVAL_MS: i64 = 1000;
 
 pub struct ElectronicEye {
     params: EEConfig,
 
+    // Exchange clients for each leg, resolved from the instrument's exchange
+    quote_exchange: Box<dyn ExchangeRest>,
+    hedge_exchange: Box<dyn ExchangeRest>,
 
-
+    // Paper venues behind both legs in debug mode, they need the books to match against
+    paper_venues: Vec<PaperExchange>,
 
     // Class variables
     last_poll_time: i64,
+    fair_price: Option<Price>,                          // hedge mid * price_ratio
+    quote_top_of_book: (Option<Price>, Option<Price>),
+    hedge_top_of_book: (Option<Price>, Option<Price>),
+    quote_position: Option<Qty>,                        // None until first read from the venue
+    hedge_position: Option<Qty>,
+    hedger: Hedger,
+    oms: OrderManager,                                  // every order we sent on either leg
+    armed: bool,                                        // no quoting until recover() has read both legs
+    last_recover_time: i64,
+    ledger: Ledger,                                     // position and PnL from our fills, checked against the venue
+
+    // Run loop channels, senders() hands out the sending side
+    senders: EESenders,
+    receivers: EEReceivers,
+
+    // Legs with a stream, the others are polled over REST
+    quote_book_streamed: bool,
+    hedge_book_streamed: bool,
+    quote_fills_streamed: bool,
+    hedge_fills_streamed: bool,
+    quote_book_down: bool,                              // stream dropped, no quoting until the book is back
+    hedge_book_down: bool,
+    last_book_poll_time: i64,
+    last_fill_poll_time: i64,
+
+    // Per level throttles, order_refresh_rate_ms goes by when the OMS saw the order sent
+    level_filled_at: HashMap<(Side, u32), i64>,     // last fill on a ladder level, for fill_refresh_delay
+    requote_at: Option<i64>,                        // a throttle held levels back, look again then
+    last_ladder: Vec<DesiredOrder>,                 // ladder as of the last requote
+
+    // Margin checks, only with run_mode.risk_management_mode on
+    margin_monitor: MarginMonitor,
+    last_margin_check_time: i64,
 }
 
 
 impl ElectronicEye {
-    pub fn new(params: EEConfig) -> Self {
-        // Initialize the ElectronicEye constructor
-        let mut ee = ElectronicEye {
-            params,
-            last_poll_time: 0
-        };
-        
+    pub fn new(params: EEConfig, quote_key: APIKey, hedge_key: APIKey) -> Result<Self, Box<dyn Error>> {
         // Call Initalization methods
-        ee.init_exchanges();
-        ee // return the ElectronicEye instance
+        let (quote_exchange, hedge_exchange) = Self::init_exchanges(&params, quote_key, hedge_key)?;
+
+        Ok(Self::with_clients(params, quote_exchange, hedge_exchange)) // return the ElectronicEye instance
+    }
+
+    /// Build from ready made clients, e.g. pointed at testnet or a mock venue
+    ///
+    /// With run_mode.debug set the clients only serve market data, orders go to paper venues
+    pub fn with_clients(params: EEConfig, quote_exchange: Box<dyn ExchangeRest>, hedge_exchange: Box<dyn ExchangeRest>) -> Self {
+        let hedger = Hedger::new(params.hedge_params.clone(), params.hedge_instrument.clone());
+        let ledger = Ledger::new(&params.hedge_params);
+        let margin_monitor = MarginMonitor::new(params.quote_params.margin_ratio_threshold);
+        let (senders, receivers) = events::channels();
+
+        let mut paper_venues = Vec::new();
+        let (quote_exchange, hedge_exchange): ExchangePair = if params.run_mode.debug {
+            println!("Debug mode, orders go to the paper venue....");
+            let quote_paper = PaperExchange::new(quote_exchange, params.quote_instrument.clone(), Leg::Quote, senders.executions.clone());
+            let hedge_paper = PaperExchange::new(hedge_exchange, params.hedge_instrument.clone(), Leg::Hedge, senders.executions.clone());
+            paper_venues.push(quote_paper.clone());
+            paper_venues.push(hedge_paper.clone());
+            (Box::new(quote_paper), Box::new(hedge_paper))
+        } else {
+            (quote_exchange, hedge_exchange)
+        };
+        // Paper venues report every fill and close on the execution channel
+        let paper = !paper_venues.is_empty();
+
+        ElectronicEye {
+            params,
+            quote_exchange,
+            hedge_exchange,
+            paper_venues,
+            last_poll_time: 0,
+            fair_price: None,
+            quote_top_of_book: (None, None),
+            hedge_top_of_book: (None, None),
+            quote_position: None,
+            hedge_position: None,
+            hedger,
+            oms: OrderManager::new(),
+            armed: false,
+            last_recover_time: 0,
+            ledger,
+            senders,
+            receivers,
+            quote_book_streamed: false,
+            hedge_book_streamed: false,
+            quote_fills_streamed: paper,
+            hedge_fills_streamed: paper,
+            quote_book_down: false,
+            hedge_book_down: false,
+            last_book_poll_time: 0,
+            last_fill_poll_time: 0,
+            level_filled_at: HashMap::new(),
+            requote_at: None,
+            last_ladder: Vec::new(),
+            margin_monitor,
+            last_margin_check_time: 0,
+        }
+    }
+
+    /// Orders sent on both legs, working_orders(symbol) has the live ones per instrument
+    pub fn oms(&self) -> &OrderManager {
+        &self.oms
+    }
+
+    /// Position and PnL per leg and net
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Channels into the run loop, for feeds or anything else producing events
+    pub fn senders(&self) -> EESenders {
+        self.senders.clone()
+    }
+
+    /// Stream both books, and fills where the venue has a private stream, into the run loop
+    ///
+    /// Legs left without a stream are polled over REST by the run loop instead
+    ///
+    /// # Arguments
+    /// * `quote_feed` - Hosts and credentials for the quote venue
+    /// * `hedge_feed` - Hosts and credentials for the hedge venue
+    pub async fn start_feeds(&mut self, quote_feed: FeedConfig, hedge_feed: FeedConfig) -> Result<(), Box<dyn Error>> {
+        println!("start_feeds....");
+
+        let quote_instrument = self.params.quote_instrument.clone();
+        let hedge_instrument = self.params.hedge_instrument.clone();
+
+        // Paper venues fill resting orders on public trades as well as the book
+        let trades = !self.paper_venues.is_empty();
+        feeds::spawn_book_feed(Leg::Quote, quote_instrument.clone(), &quote_feed, trades, self.senders.market_data.clone(), self.senders.feed_state.clone()).await?;
+        self.quote_book_streamed = true;
+        feeds::spawn_book_feed(Leg::Hedge, hedge_instrument.clone(), &hedge_feed, trades, self.senders.market_data.clone(), self.senders.feed_state.clone()).await?;
+        self.hedge_book_streamed = true;
+
+        // Paper fills already come in on the execution channel
+        if !self.paper_venues.is_empty() {
+            return Ok(());
+        }
+
+        self.quote_fills_streamed = feeds::spawn_execution_feed(
+            Leg::Quote, quote_instrument, &quote_feed, self.params.quote_params.dcp_window, self.senders.executions.clone(), self.senders.feed_state.clone(),
+        ).await?;
+        self.hedge_fills_streamed = feeds::spawn_execution_feed(
+            Leg::Hedge, hedge_instrument, &hedge_feed, 0, self.senders.executions.clone(), self.senders.feed_state.clone(),
+        ).await?;
+        println!("Fills streamed, quote: {}, hedge: {}", self.quote_fills_streamed, self.hedge_fills_streamed);
+        if self.params.quote_params.dcp_window > 0 && !self.quote_fills_streamed {
+            println!("No private stream on the quote leg, disconnect-cancel-all is not armed");
+        }
+
+        Ok(())
     }
     
-    fn init_exchanges(&mut self) {
+    fn init_exchanges(params: &EEConfig, quote_key: APIKey, hedge_key: APIKey) -> Result<ExchangePair, Box<dyn Error>> {
 
         println!("init_exchanges....");
 
+        let quote_exchange = new_rest_client(&params.quote_instrument.exchange, quote_key.api_key, quote_key.api_secret)?;
+        let hedge_exchange = new_rest_client(&params.hedge_instrument.exchange, hedge_key.api_key, hedge_key.api_secret)?;
 
-
-        
+        Ok((quote_exchange, hedge_exchange))
     }
 
     pub async fn process_book_updates(&mut self) {
@@ -43,12 +210,362 @@ impl ElectronicEye {
 
         println!("process_book_updates....");
 
-        // Process book updates
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        // Fair price from the hedge leg, top of book from the quote leg
+        self.fetch_book(Leg::Hedge).await;
+        self.fetch_book(Leg::Quote).await;
+
+        self.requote().await;
 
 
 
 
+    }
+
+    async fn fetch_book(&mut self, leg: Leg) {
+        let (exchange, symbol) = match leg {
+            Leg::Quote => (&self.quote_exchange, &self.params.quote_instrument),
+            Leg::Hedge => (&self.hedge_exchange, &self.params.hedge_instrument),
+        };
+        match exchange.get_orderbook(symbol).await {
+            Ok(book) => self.set_top_of_book(leg, Self::top_of_book(&book)),
+            Err(e) => println!("Failed to get {:?} orderbook: {}", leg, e),
+        }
+    }
+
+    fn apply_market_update(&mut self, update: MarketUpdate) {
+        // Trades printed before the book they left behind
+        if !update.trades.is_empty() {
+            for paper in self.paper_venues.iter().filter(|paper| paper.leg() == update.leg) {
+                paper.on_trades(&update.trades);
+            }
+        }
+        self.set_top_of_book(update.leg, update.top_of_book);
+    }
+
+    fn set_top_of_book(&mut self, leg: Leg, top_of_book: (Option<Price>, Option<Price>)) {
+        for paper in self.paper_venues.iter().filter(|paper| paper.leg() == leg) {
+            paper.on_top_of_book(top_of_book);
+        }
+        if let Some(mid) = Self::mid_price(top_of_book) {
+            self.ledger.set_mark(leg, mid);
+        }
+        // Anything on the book means it is updating again
+        let down = top_of_book == (None, None) && self.book_down(leg);
+        match leg {
+            Leg::Quote => self.quote_book_down = down,
+            Leg::Hedge => self.hedge_book_down = down,
+        }
+        match leg {
+            Leg::Quote => self.quote_top_of_book = top_of_book,
+            Leg::Hedge => {
+                self.hedge_top_of_book = top_of_book;
+                self.update_fair_price();
+            }
+        }
+    }
+
+    fn update_fair_price(&mut self) {
+        self.fair_price = Self::mid_price(self.hedge_top_of_book)
+            .and_then(|mid| Price::from_f64(mid.to_f64() * self.params.hedge_params.price_ratio).ok());
+    }
+
+    fn top_of_book(book: &QTSOrderBook) -> (Option<Price>, Option<Price>) {
+        (book.bids.first().map(|level| level.0), book.asks.first().map(|level| level.0))
+    }
+
+    fn mid_price(top_of_book: (Option<Price>, Option<Price>)) -> Option<Price> {
+        match top_of_book {
+            (Some(best_bid), Some(best_ask)) => Some(Price::from_raw((best_bid.raw() + best_ask.raw()) / 2)),
+            _ => None,
+        }
+    }
+
+    fn book_down(&self, leg: Leg) -> bool {
+        match leg {
+            Leg::Quote => self.quote_book_down,
+            Leg::Hedge => self.hedge_book_down,
+        }
+    }
+
+    /// Handle a stream dropping, coming back or giving up on either leg
+    ///
+    /// A book that stopped updating takes the quotes down with it until it is back, either
+    /// from the stream's next snapshot or, once the stream gave up, from REST polling. A
+    /// private stream that was down missed executions, the leg is caught up over REST, and
+    /// once it gave up the leg is polled like one that never had a stream
+    pub async fn process_feed_event(&mut self, event: FeedEvent) {
+        println!("{:?} {:?} feed: {:?}", event.leg, event.stream, event.status);
+
+        match (event.stream, event.status) {
+            (Stream::Book, FeedStatus::Down) => self.on_book_down(event.leg).await,
+            // The replayed subscription's snapshot brings the book back
+            (Stream::Book, FeedStatus::Resubscribed) => {}
+            (Stream::Book, FeedStatus::GaveUp) => {
+                match event.leg {
+                    Leg::Quote => self.quote_book_streamed = false,
+                    Leg::Hedge => self.hedge_book_streamed = false,
+                }
+                self.on_book_down(event.leg).await;
+            }
+            (Stream::Executions, FeedStatus::Down) => {}
+            (Stream::Executions, FeedStatus::Resubscribed) => self.catch_up(event.leg).await,
+            (Stream::Executions, FeedStatus::GaveUp) => {
+                match event.leg {
+                    Leg::Quote => self.quote_fills_streamed = false,
+                    Leg::Hedge => self.hedge_fills_streamed = false,
+                }
+                self.catch_up(event.leg).await;
+            }
+        }
+    }
+
+    /// Catch a leg up on whatever its private stream missed while it was down
+    ///
+    /// Executions already queued are handled first, then the leg's open orders and position
+    /// are read back. A position that moved further than the stream reported was filled in
+    /// the gap and goes to the hedger like a fill on a leg without a stream.
+    async fn catch_up(&mut self, leg: Leg) {
+        while let Ok(event) = self.receivers.executions.try_recv() {
+            self.process_execution(event).await;
+        }
+        let now = chrono::Utc::now().timestamp_millis();
+
+        match self.reconcile_orders(leg, now).await {
+            Ok(orphans) => {
+                for orphan in orphans {
+                    self.recover_order(leg, orphan, now).await;
+                }
+            }
+            Err(e) => println!("Failed to get open {:?} orders: {}", leg, e),
+        }
+        self.read_position(leg, true).await;
+
+        if leg == Leg::Hedge {
+            // A hedge that closed in the gap never reported it
+            if let Some(client_order_id) = self.hedger.working_order().map(str::to_string)
+                && !self.oms.order(&client_order_id).is_some_and(|order| order.state.is_working()) {
+                self.hedger.on_order_done(&client_order_id);
+            }
+        }
+        self.hedge().await;
+        self.requote_at = Some(now);
+    }
+
+    async fn on_book_down(&mut self, leg: Leg) {
+        self.set_top_of_book(leg, (None, None));
+        match leg {
+            Leg::Quote => self.quote_book_down = true,
+            Leg::Hedge => self.hedge_book_down = true,
+        }
+        self.requote().await;
+    }
+
+    /// Handle a top of book change on either leg
+    pub async fn process_market_data(&mut self, update: MarketUpdate) {
+        self.apply_market_update(update);
+        // Only the latest top of book matters, skip past anything already superseded
+        while let Ok(update) = self.receivers.market_data.try_recv() {
+            self.apply_market_update(update);
+        }
+
+        self.hedge().await;
+        if self.ladder_moved() {
+            self.requote().await;
+        }
+    }
+
+    fn desired_ladder(&self, fair_price: Price) -> Vec<DesiredOrder> {
+        let position = self.quote_position.unwrap_or(Qty::ZERO);
+        let ladder = build_ladder(&self.params.quote_params, &self.params.quote_instrument, fair_price, position, self.quote_top_of_book);
+        if self.margin_monitor.reduce_only() {
+            return reduce_only_ladder(ladder, position);
+        }
+        ladder
+    }
+
+    fn relist_tolerance(&self, fair_price: Price) -> Price {
+        Price::from_f64(fair_price.to_f64() * self.params.quote_params.relist_interval).unwrap_or(Price::ZERO)
+    }
+
+    /// Whether the ladder for the current fair price has moved away from the one last quoted
+    fn ladder_moved(&self) -> bool {
+        let Some(fair_price) = self.fair_price else {
+            return false;
+        };
+        let desired = self.desired_ladder(fair_price);
+        let tolerance = self.relist_tolerance(fair_price);
+
+        desired.len() != self.last_ladder.len()
+            || desired.iter().zip(&self.last_ladder).any(|(order, last)| {
+                order.side != last.side || order.level != last.level || order.qty != last.qty || (order.price - last.price).abs() > tolerance
+            })
+    }
+
+    /// Bring the resting quotes in line with the ladder for the current fair price
+    pub async fn requote(&mut self) {
+        self.requote_at = None;
+        if !self.armed {
+            println!("Not recovered yet, not quoting");
+            return;
+        }
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // Nothing to price or check the quotes against, pull them
+        if self.quote_book_down || self.hedge_book_down {
+            println!("Book down, pulling quotes");
+            self.pull_quotes(now).await;
+            return;
+        }
+        let Some(fair_price) = self.fair_price else {
+            println!("No fair price yet, not quoting");
+            return;
+        };
+
+        // Without a stream the venue's open orders are the only news of fills and closes,
+        // quotes of ours the OMS doesn't know went out of step with it and are cancelled
+        let mut orphans = Vec::new();
+        if !self.quote_fills_streamed {
+            match self.reconcile_orders(Leg::Quote, now).await {
+                Ok(found) => orphans = found,
+                Err(e) => {
+                    println!("Failed to get open quotes: {}", e);
+                    self.requote_at = Some(now + REST_POLL_INTERVAL_MS);
+                    return;
+                }
+            }
+        }
+        // Quotes already being cancelled aren't part of the ladder any more, a cancel still
+        // pending here was refused for something other than the order being gone and is sent again
+        let (cancelling, resting): (Vec<_>, Vec<_>) = self.oms.working_orders(&self.params.quote_instrument).into_iter()
+            .filter(|order| order.client_order_id.starts_with(QUOTE_ID_PREFIX))
+            .partition(|order| order.state == OrderState::PendingCancel);
+        let resting: Vec<_> = resting.into_iter().map(ManagedOrder::to_open_order).collect();
+        let cancelling: Vec<_> = cancelling.into_iter().map(ManagedOrder::to_open_order).collect();
+
+        // In reduce-only mode quotes that went in before it are replaced with flagged ones
+        let (resting, unflagged): (Vec<_>, Vec<_>) = resting.into_iter().partition(|order| order.reduce_only || !self.margin_monitor.reduce_only());
+
+        let desired = self.desired_ladder(fair_price);
+        let mut diff = diff_orders(&desired, &resting, self.relist_tolerance(fair_price));
+        diff.cancels.extend(unflagged);
+        diff.cancels.extend(orphans);
+        diff.cancels.extend(cancelling);
+        self.hold_throttled_levels(&mut diff, &desired, &resting, now);
+        self.last_ladder = desired;
+        if diff.is_empty() {
+            return;
+        }
+
+        // Pull stale quotes first so the new ladder never overlaps the old one, the cancels and
+        // the places each go out as a batch so the loop waits two round trips, not one per order
+        self.cancel_quotes(&diff.cancels, now).await;
+        if diff.places.is_empty() {
+            return;
+        }
+
+        let requests: Vec<_> = diff.places.iter().map(|order| self.quote_request(order)).collect();
+        for request in &requests {
+            self.oms.track(&self.params.quote_instrument, request, now);
+        }
+        let results = self.quote_exchange.place_orders(&self.params.quote_instrument, &requests).await;
+        for ((order, request), result) in diff.places.iter().zip(&requests).zip(results) {
+            match &result {
+                Ok(ack) => println!("Placed quote {} {:?} {} @ {}", ack.client_order_id, order.side, order.qty, order.price),
+                Err(e) => println!("Failed to place quote {}: {}", request.client_order_id, e),
+            }
+            self.oms.on_place_result(&request.client_order_id, &result, now);
+        }
+    }
+
+    /// Cancel every quote still working, the ladder is rebuilt from scratch once quoting resumes
+    async fn pull_quotes(&mut self, now: i64) {
+        self.last_ladder.clear();
+        let working: Vec<_> = self.oms.working_orders(&self.params.quote_instrument).into_iter()
+            .filter(|order| order.client_order_id.starts_with(QUOTE_ID_PREFIX))
+            .map(ManagedOrder::to_open_order)
+            .collect();
+        self.cancel_quotes(&working, now).await;
+    }
+
+    /// Cancel quotes all at once, the results are applied once every one is back
+    async fn cancel_quotes(&mut self, orders: &[QTSOpenOrder], now: i64) {
+        if orders.is_empty() {
+            return;
+        }
+        for order in orders {
+            self.oms.on_cancel_sent(&order.client_order_id, now);
+        }
+        let client_order_ids: Vec<_> = orders.iter().map(|order| order.client_order_id.clone()).collect();
+        let results = self.quote_exchange.cancel_orders(&self.params.quote_instrument, &client_order_ids).await;
+        for (order, result) in orders.iter().zip(results) {
+            match &result {
+                Ok(ack) => println!("Cancelled quote {}", ack.client_order_id),
+                Err(e) => println!("Failed to cancel quote {}: {}", order.client_order_id, e),
+            }
+            self.oms.on_cancel_result(&order.client_order_id, &result, now);
+        }
+        // Still pending means the venue refused the cancel but has the order, try again shortly
+        let refused = orders.iter().any(|order| self.oms.order(&order.client_order_id).is_some_and(|order| order.state == OrderState::PendingCancel));
+        if refused {
+            let retry_at = now + REST_POLL_INTERVAL_MS;
+            self.requote_at = Some(self.requote_at.map_or(retry_at, |requote_at| requote_at.min(retry_at)));
+        }
+    }
+
+    /// Leave a level alone until order_refresh_rate_ms has passed since its order went in and
+    /// fill_refresh_delay since its last fill, and note when the first held level frees up
+    fn hold_throttled_levels(&mut self, diff: &mut QuoteDiff, desired: &[DesiredOrder], resting: &[QTSOpenOrder], now: i64) {
+        let refresh_ms = self.params.quote_params.order_refresh_rate_ms as i64;
+        let fill_delay_ms = self.params.quote_params.fill_refresh_delay as i64 * 1000;
+        self.level_filled_at.retain(|_, filled_at| now - *filled_at < fill_delay_ms);
+
+        // Level -> when it may change again, for levels still held
+        let mut held: HashMap<(Side, u32), i64> = HashMap::new();
+        for order in desired {
+            let level = (order.side, order.level);
+            let placed = resting.iter()
+                .filter(|open| quote_level(&open.client_order_id) == Some(level))
+                .filter_map(|open| self.oms.order(&open.client_order_id))
+                .map(|order| order.created_ms + refresh_ms);
+            let filled = self.level_filled_at.get(&level).map(|filled_at| filled_at + fill_delay_ms);
+            if let Some(release) = placed.chain(filled).max()
+                && release > now {
+                held.insert(level, release);
+            }
+        }
+
+        // Levels that are no longer wanted at all are still pulled straight away
+        let before = diff.cancels.len() + diff.places.len();
+        diff.cancels.retain(|open| !quote_level(&open.client_order_id).is_some_and(|level| held.contains_key(&level)));
+        diff.places.retain(|order| !held.contains_key(&(order.side, order.level)));
+        if diff.cancels.len() + diff.places.len() < before {
+            self.requote_at = held.values().min().copied();
+        }
+    }
+
+    fn quote_request(&self, order: &DesiredOrder) -> QTSOrderRequest {
+        QTSOrderRequest {
+            side: order.side,
+            order_type: OrderType::Limit,
+            price: Some(order.price),
+            qty: order.qty,
+            time_in_force: TimeInForce::PostOnly,
+            client_order_id: quote_order_id(order.side, order.level),
+            reduce_only: self.margin_monitor.reduce_only(),
+        }
+    }
+
+    /// Line the OMS up with a leg's open orders, for legs without a stream
+    ///
+    /// # Returns
+    /// * `ExchangeResult<Vec<QTSOpenOrder>>` - Open orders with the leg's prefix the OMS doesn't know
+    async fn reconcile_orders(&mut self, leg: Leg, now: i64) -> ExchangeResult<Vec<QTSOpenOrder>> {
+        let (exchange, symbol, prefix) = match leg {
+            Leg::Quote => (&self.quote_exchange, &self.params.quote_instrument, QUOTE_ID_PREFIX),
+            Leg::Hedge => (&self.hedge_exchange, &self.params.hedge_instrument, HEDGE_ID_PREFIX),
+        };
+        let open_orders = exchange.get_open_orders(symbol).await?;
+        Ok(self.oms.reconcile(symbol, &open_orders, prefix, now))
     }
 
     pub async fn process_fills(&mut self) {
@@ -57,63 +574,620 @@ impl ElectronicEye {
         // Process fills
         println!("process_fills....");
 
-        //Sleep for 1 second
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        // Without a stream, fills show up as position changes on either leg
+        self.sync_positions().await;
+        self.hedge().await;
 
 
     }
 
+    /// Handle a fill or a closed order from a leg's private stream
+    pub async fn process_execution(&mut self, event: ExecutionEvent) {
+        let now = chrono::Utc::now().timestamp_millis();
 
-    pub async fn process_backoffice(&mut self) {
+        match event {
+            ExecutionEvent::Fill { leg: Leg::Quote, fill } => {
+                println!("Quote fill: {} {:?} {} @ {}", fill.client_order_id, fill.side, fill.qty, fill.price);
+                self.oms.on_fill(&fill, now);
+                self.ledger.on_fill(Leg::Quote, &fill);
+                self.hedger.on_quote_fill(fill.side, fill.qty, fill.price, now);
+                self.quote_position = Some(self.quote_position.unwrap_or(Qty::ZERO) + Self::signed(fill.side, fill.qty));
+                if let Some(level) = quote_level(&fill.client_order_id) {
+                    self.level_filled_at.insert(level, now);
+                }
+
+                // Hedge before anything else goes out
+                self.hedge().await;
+                self.requote().await;
+            }
+            ExecutionEvent::Fill { leg: Leg::Hedge, fill } => {
+                println!("Hedge fill: {} {:?} {} @ {}", fill.client_order_id, fill.side, fill.qty, fill.price);
+                self.oms.on_fill(&fill, now);
+                self.ledger.on_fill(Leg::Hedge, &fill);
+                self.hedger.on_hedge_fill(fill.side, fill.qty, fill.price);
+                self.hedge_position = Some(self.hedge_position.unwrap_or(Qty::ZERO) + Self::signed(fill.side, fill.qty));
+                self.hedge().await;
+            }
+            ExecutionEvent::OrderClosed { leg: Leg::Quote, client_order_id, status, filled_qty } => {
+                // Only quotes we thought were resting leave a gap, our own cancels are already closed
+                let previous = self.oms.on_closed(&client_order_id, &status, filled_qty, now);
+                if previous.is_some_and(|state| matches!(state, OrderState::PendingNew | OrderState::New | OrderState::PartiallyFilled)) {
+                    println!("Quote {} closed: {}", client_order_id, status);
+                    self.requote_at = Some(now);
+                }
+            }
+            ExecutionEvent::OrderClosed { leg: Leg::Hedge, client_order_id, status, filled_qty } => {
+                println!("Hedge {} closed: {}, filled {}", client_order_id, status, filled_qty);
+                self.oms.on_closed(&client_order_id, &status, filled_qty, now);
+                self.hedger.on_order_closed(&client_order_id, filled_qty);
+                self.hedge().await;
+            }
+            ExecutionEvent::Funding { leg, amount } => {
+                println!("{:?} funding: {:.6}", leg, amount);
+                self.ledger.on_funding(leg, amount);
+            }
+        }
+    }
+
+    /// # Returns
+    /// * `bool` - Whether both legs were read
+    async fn sync_positions(&mut self) -> bool {
+        let quote = self.sync_position(Leg::Quote).await;
+        let hedge = self.sync_position(Leg::Hedge).await;
+        quote && hedge
+    }
+
+    async fn sync_position(&mut self, leg: Leg) -> bool {
+        let streamed = match leg {
+            Leg::Quote => self.quote_fills_streamed,
+            Leg::Hedge => self.hedge_fills_streamed,
+        };
+        self.read_position(leg, !streamed).await
+    }
+
+    /// # Arguments
+    /// * `leg` - Leg to read
+    /// * `infer_fills` - Take a change in size as fills we never saw, streamed fills have
+    ///   otherwise already moved the position and the venue's figure only corrects drift
+    async fn read_position(&mut self, leg: Leg, infer_fills: bool) -> bool {
+        let now = chrono::Utc::now().timestamp_millis();
+        let (exchange, symbol) = match leg {
+            Leg::Quote => (&self.quote_exchange, &self.params.quote_instrument),
+            Leg::Hedge => (&self.hedge_exchange, &self.params.hedge_instrument),
+        };
+
+        let positions = match exchange.get_positions(symbol).await {
+            Ok(positions) => positions,
+            Err(e) => {
+                println!("Failed to sync {:?} positions: {}", leg, e);
+                return false;
+            }
+        };
+        let size: Qty = positions.iter().map(|position| position.size).sum();
+
+        let previous = match leg {
+            Leg::Quote => self.quote_position.replace(size),
+            Leg::Hedge => self.hedge_position.replace(size),
+        };
+        if infer_fills {
+            if let Some(change) = previous.map(|previous| size - previous)
+                && !change.is_zero() {
+                self.on_position_change(leg, change, now);
+            }
+            if leg == Leg::Hedge {
+                self.hedger.on_hedge_synced();
+            }
+        }
+
+        // Off on the first read, or after a fill we never saw
+        if let Some(drift) = self.ledger.check(leg, &positions) {
+            println!("{:?} ledger off the venue position by {}, now {}", leg, drift, size);
+        }
+        true
+    }
+
+    /// Treat a position change on a leg without a stream as a fill
+    fn on_position_change(&mut self, leg: Leg, change: Qty, now: i64) {
+        let side = Self::side_of(change);
+
+        match leg {
+            Leg::Quote => {
+                // Mid is the nearest thing to a fill price we have without the execution stream
+                let Some(price) = Self::mid_price(self.quote_top_of_book).or(self.fair_price) else {
+                    return;
+                };
+                println!("Quote fill: {} @ ~{}", change, price);
+                self.ledger.leg_mut(leg).on_fill(side, change.abs(), price, 0.0);
+                self.hedger.on_quote_fill(side, change.abs(), price, now);
+
+                // No order id to go on, hold the whole side
+                for level in 0..self.params.quote_params.quote_levels {
+                    self.level_filled_at.insert((side, level), now);
+                }
+                self.requote_at = Some(now);
+            }
+            Leg::Hedge => {
+                let Some(price) = Self::mid_price(self.hedge_top_of_book) else {
+                    return;
+                };
+                println!("Hedge fill: {} @ ~{}", change, price);
+                self.ledger.leg_mut(leg).on_fill(side, change.abs(), price, 0.0);
+                self.hedger.on_hedge_fill(side, change.abs(), price);
+            }
+        }
+    }
+
+    fn side_of(change: Qty) -> Side {
+        if change.is_negative() { Side::Sell } else { Side::Buy }
+    }
+
+    fn signed(side: Side, qty: Qty) -> Qty {
+        if side == Side::Sell { -qty } else { qty }
+    }
+
+    /// Send whatever the hedger wants on the hedge leg
+    pub async fn hedge(&mut self) {
+        let now = chrono::Utc::now().timestamp_millis();
+        let Some(action) = self.hedger.next_action(now, self.hedge_top_of_book) else {
+            return;
+        };
+        self.send_hedge(action, now).await;
+        println!("Unhedged: {:.2} USD", self.hedger.unhedged_usd());
+    }
 
-        // Process backoffice   
-        println!("process_backoffice....");
+    /// # Returns
+    /// * `bool` - Whether the venue took the order or cancel
+    async fn send_hedge(&mut self, action: HedgeAction, now: i64) -> bool {
+        match action {
+            HedgeAction::Place(request) => {
+                self.oms.track(&self.params.hedge_instrument, &request, now);
+                let result = self.hedge_exchange.place_order(&self.params.hedge_instrument, &request).await;
+                self.oms.on_place_result(&request.client_order_id, &result, now);
+                match result {
+                    Ok(ack) => {
+                        println!("Placed hedge {} {:?} {} @ {:?}", ack.client_order_id, request.side, request.qty, request.price);
+                        true
+                    }
+                    Err(e) => {
+                        println!("Failed to place hedge {}: {}", request.client_order_id, e);
+                        self.hedger.on_order_done(&request.client_order_id);
+                        false
+                    }
+                }
+            }
+            HedgeAction::Cancel(client_order_id) => {
+                self.oms.on_cancel_sent(&client_order_id, now);
+                let result = self.hedge_exchange.cancel_order(&self.params.hedge_instrument, &client_order_id).await;
+                self.oms.on_cancel_result(&client_order_id, &result, now);
+                match result {
+                    Ok(_) => {
+                        println!("Cancelled hedge {}", client_order_id);
+                        self.hedger.on_order_done(&client_order_id);
+                        true
+                    }
+                    // Already filled or gone is as good as cancelled
+                    Err(e) if e.is_order_not_found() => {
+                        self.hedger.on_order_done(&client_order_id);
+                        true
+                    }
+                    // Still resting, the hedger sends the cancel again
+                    Err(e) => {
+                        println!("Failed to cancel hedge {}: {}", client_order_id, e);
+                        self.hedger.on_cancel_failed(&client_order_id);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+
+    pub async fn process_backoffice(&mut self) {
 
         // Get current time
         // Check if its poll_interval_seconds, if so, poll
         let current_time = chrono::Utc::now().timestamp_millis();
         if current_time - self.last_poll_time > (self.params.quote_params.poll_interval_seconds as i64) * 1000 {
+            // Process backoffice
+            println!("process_backoffice....");
             self.poll().await;
             self.last_poll_time = current_time;
         }
-        
-        tokio::time::sleep(Duration::from_secs(1)).await;
-    
+
 
 
     }
 
+    /// Pick up what an earlier run left behind, the quoter arms once this has gone through
+    ///
+    /// Positions on both legs seed the ledger. Open orders carrying our prefixes are orphans,
+    /// quotes are adopted into the OMS and kept or cancelled by the next requote like any
+    /// other, hedges are cancelled as the hedger has no batch they belong to. Safe to call
+    /// again after a failure, anything already adopted is no longer an orphan.
+    ///
+    /// # Returns
+    /// * `bool` - Whether both legs were read and the quoter is armed
+    pub async fn recover(&mut self) -> bool {
+        println!("recover....");
+        let now = chrono::Utc::now().timestamp_millis();
+        self.last_recover_time = now;
 
-    pub async fn process_config_updates(&mut self) {
-        // Process config updates
+        if !self.sync_positions().await {
+            return false;
+        }
+        for leg in [Leg::Quote, Leg::Hedge] {
+            let orphans = match self.reconcile_orders(leg, now).await {
+                Ok(orphans) => orphans,
+                Err(e) => {
+                    println!("Failed to get open {:?} orders: {}", leg, e);
+                    return false;
+                }
+            };
+            for orphan in orphans {
+                self.recover_order(leg, orphan, now).await;
+            }
+        }
+
+        self.armed = true;
+        println!("Recovered, quote position: {:?}, hedge position: {:?}", self.quote_position, self.hedge_position);
+        println!("Ledger: {}", self.ledger);
+        true
     }
 
+    async fn recover_order(&mut self, leg: Leg, orphan: QTSOpenOrder, now: i64) {
+        let (exchange, symbol) = match leg {
+            Leg::Quote => (&self.quote_exchange, &self.params.quote_instrument),
+            Leg::Hedge => (&self.hedge_exchange, &self.params.hedge_instrument),
+        };
+        self.oms.adopt(symbol, &orphan, now);
+        if leg == Leg::Quote {
+            println!("Adopted quote {} {:?} {} @ {}", orphan.client_order_id, orphan.side, orphan.qty - orphan.filled_qty, orphan.price);
+            return;
+        }
 
-    pub async fn poll(&self) {
-        // Add your hedge logic here
+        self.oms.on_cancel_sent(&orphan.client_order_id, now);
+        let result = exchange.cancel_order(symbol, &orphan.client_order_id).await;
+        match &result {
+            Ok(ack) => println!("Cancelled orphaned hedge {}", ack.client_order_id),
+            Err(e) => println!("Failed to cancel orphaned hedge {}: {}", orphan.client_order_id, e),
+        }
+        self.oms.on_cancel_result(&orphan.client_order_id, &result, now);
+    }
 
+    /// Timer tick, covers everything that is due rather than triggered by an event
+    pub async fn process_timer(&mut self) {
+        let now = chrono::Utc::now().timestamp_millis();
 
-        println!("Polled....");
+        if !self.armed && now - self.last_recover_time >= REST_POLL_INTERVAL_MS && self.recover().await {
+            self.requote_at = Some(now);
+        }
+
+        // Legs without a stream are polled over REST instead
+        if (!self.quote_book_streamed || !self.hedge_book_streamed) && now - self.last_book_poll_time >= REST_POLL_INTERVAL_MS {
+            self.last_book_poll_time = now;
+            if !self.hedge_book_streamed {
+                self.fetch_book(Leg::Hedge).await;
+            }
+            if !self.quote_book_streamed {
+                self.fetch_book(Leg::Quote).await;
+            }
+            if self.ladder_moved() {
+                self.requote_at = Some(now);
+            }
+        }
+        if (!self.quote_fills_streamed || !self.hedge_fills_streamed) && now - self.last_fill_poll_time >= REST_POLL_INTERVAL_MS {
+            self.last_fill_poll_time = now;
+            if !self.quote_fills_streamed {
+                self.sync_position(Leg::Quote).await;
+            }
+            if !self.hedge_fills_streamed {
+                self.sync_position(Leg::Hedge).await;
+                if let Err(e) = self.reconcile_orders(Leg::Hedge, now).await {
+                    println!("Failed to get open hedges: {}", e);
+                }
+            }
+        }
+        self.oms.prune(now);
+
+        if self.params.run_mode.risk_management_mode && now - self.last_margin_check_time >= self.params.quote_params.margin_check_interval as i64 {
+            self.last_margin_check_time = now;
+            self.check_margin().await;
+        }
+
+        if self.requote_at.is_some_and(|requote_at| now >= requote_at) {
+            self.requote().await;
+        }
+        // Sends batches whose hedge_delay has run out
+        self.hedge().await;
+        self.process_backoffice().await;
+    }
+
+
+    /// Read the margin ratio on both legs, and switch reduce-only quoting on or off
+    pub async fn check_margin(&mut self) {
+        println!("check_margin....");
 
+        let mut changed = false;
+        for leg in [Leg::Quote, Leg::Hedge] {
+            let exchange = match leg {
+                Leg::Quote => &self.quote_exchange,
+                Leg::Hedge => &self.hedge_exchange,
+            };
+            match exchange.get_margin().await {
+                Ok(margin) => {
+                    println!("{:?} margin ratio: {:.4}, threshold: {}", leg, margin.margin_ratio, self.params.quote_params.margin_ratio_threshold);
+                    changed |= self.margin_monitor.on_margin(leg, margin.margin_ratio);
+                }
+                Err(e) => println!("Failed to get {:?} margin: {}", leg, e),
+            }
+        }
+        if changed {
+            self.on_reduce_only_change().await;
+        }
     }
-    
-    pub async fn run(mut self) {
-        // Query Pool during initialization
-        self.poll().await;
-        self.last_poll_time = chrono::Utc::now().timestamp_millis();
 
-        loop {
+    async fn on_reduce_only_change(&mut self) {
+        if self.margin_monitor.reduce_only() {
+            println!("Margin ratio over threshold, quoting reduce-only");
+        } else {
+            println!("Margin ratio back under threshold, quoting both sides");
+        }
+        self.requote().await;
+    }
+
+    /// Shut down on Ctrl-C or SIGTERM, run() then cleans up and returns
+    pub fn stop_on_signals(&self) {
+        let shutdown = self.senders.shutdown.clone();
+        tokio::spawn(async move {
+            match shutdown_signal().await {
+                Ok(signal) => println!("{} received, shutting down....", signal),
+                Err(e) => {
+                    println!("Failed to listen for signals: {}", e);
+                    return;
+                }
+            }
+            let _ = shutdown.send(());
+        });
+    }
+
+    /// Stop quoting and take our orders off both venues
+    ///
+    /// Quoting stops first so nothing new goes out, then every order the OMS has working
+    /// on either leg is cancelled. With run_mode.flatten_on_shutdown the residual hedge
+    /// delta goes out as one IOC. The venues' open orders are read back last and anything
+    /// still carrying our prefixes is cancelled again.
+    ///
+    /// # Returns
+    /// * `bool` - Whether every cancel, the flatten and the read back went through
+    pub async fn shutdown(&mut self) -> bool {
+        println!("shutdown....");
+        self.armed = false;
+        self.requote_at = None;
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut clean = true;
+
+        for leg in [Leg::Quote, Leg::Hedge] {
+            let symbol = match leg {
+                Leg::Quote => &self.params.quote_instrument,
+                Leg::Hedge => &self.params.hedge_instrument,
+            };
+            let working: Vec<_> = self.oms.working_orders(symbol).iter().map(|order| order.client_order_id.clone()).collect();
+            for client_order_id in working {
+                clean &= self.cancel_on_shutdown(leg, &client_order_id, now).await;
+            }
+        }
+
+        if self.params.run_mode.flatten_on_shutdown {
+            self.fetch_book(Leg::Hedge).await;
+            if let Some(action) = self.hedger.flatten(self.hedge_top_of_book) {
+                clean &= self.send_hedge(action, now).await;
+            }
+        }
+
+        // Read back, an order sent just before may only show up now, and a leg is only flat
+        // once the venue lists none of ours
+        for leg in [Leg::Quote, Leg::Hedge] {
+            let left = match self.own_open_orders(leg).await {
+                Ok(left) => left,
+                Err(e) => {
+                    println!("Failed to read back {:?} orders: {}", leg, e);
+                    clean = false;
+                    continue;
+                }
+            };
+            if left.is_empty() {
+                continue;
+            }
+            for client_order_id in left {
+                clean &= self.cancel_on_shutdown(leg, &client_order_id, now).await;
+            }
+            match self.own_open_orders(leg).await {
+                Ok(left) if left.is_empty() => {}
+                Ok(left) => {
+                    println!("{:?} orders still open: {:?}", leg, left);
+                    clean = false;
+                }
+                Err(e) => {
+                    println!("Failed to read back {:?} orders: {}", leg, e);
+                    clean = false;
+                }
+            }
+        }
+
+        self.sync_positions().await;
+        println!("Ledger: {}", self.ledger);
+        println!("Unhedged: {:.2} USD", self.hedger.unhedged_usd());
+        if clean {
+            println!("Shut down clean");
+        } else {
+            println!("Shut down with orders possibly left on the venues");
+        }
+        let _ = std::io::stdout().flush();
+        clean
+    }
+
+    async fn cancel_on_shutdown(&mut self, leg: Leg, client_order_id: &str, now: i64) -> bool {
+        let (exchange, symbol) = match leg {
+            Leg::Quote => (&self.quote_exchange, &self.params.quote_instrument),
+            Leg::Hedge => (&self.hedge_exchange, &self.params.hedge_instrument),
+        };
+        self.oms.on_cancel_sent(client_order_id, now);
+        let result = exchange.cancel_order(symbol, client_order_id).await;
+        self.oms.on_cancel_result(client_order_id, &result, now);
+
+        let cancelled = match result {
+            Ok(_) => {
+                println!("Cancelled {:?} order {}", leg, client_order_id);
+                true
+            }
+            // The venue no longer has it
+            Err(e) if e.is_order_not_found() => true,
+            Err(e) => {
+                println!("Failed to cancel {:?} order {}: {}", leg, client_order_id, e);
+                false
+            }
+        };
+        if leg == Leg::Hedge {
+            if cancelled {
+                self.hedger.on_order_done(client_order_id);
+            } else {
+                self.hedger.on_cancel_failed(client_order_id);
+            }
+        }
+        cancelled
+    }
 
+    /// Client order ids of the leg's open orders carrying our prefix
+    async fn own_open_orders(&self, leg: Leg) -> ExchangeResult<Vec<String>> {
+        let (exchange, symbol, prefix) = match leg {
+            Leg::Quote => (&self.quote_exchange, &self.params.quote_instrument, QUOTE_ID_PREFIX),
+            Leg::Hedge => (&self.hedge_exchange, &self.params.hedge_instrument, HEDGE_ID_PREFIX),
+        };
+        Ok(exchange.get_open_orders(symbol).await?.into_iter()
+            .filter(|order| order.client_order_id.starts_with(prefix))
+            .map(|order| order.client_order_id)
+            .collect())
+    }
 
-            self.process_book_updates().await;
-            self.process_fills().await;
-            self.process_backoffice().await;
+    /// Reload the config whenever the YAML file it was loaded from changes
+    ///
+    /// # Arguments
+    /// * `path` - Same path as given to `EEConfig::from_yaml_file`
+    pub fn watch_config(&self, path: &str) {
+        println!("Watching config {}", path);
+        reload::spawn_config_watcher(path.to_string(), self.senders.config.clone());
+    }
 
+    /// Pick up a changed config
+    ///
+    /// A config that fails validation, or changes anything that needs a restart, is rejected
+    /// whole, see reload.rs
+    ///
+    /// # Returns
+    /// * `bool` - Whether the config was applied
+    pub async fn process_config_updates(&mut self, config: EEConfig) -> bool {
+        // Process config updates
+        println!("process_config_updates....");
 
+        if let Err(e) = config.validate() {
+            println!("Config rejected, {}", e);
+            return false;
+        }
+        let changes = reload::config_diff(&self.params, &config);
+        if changes.is_empty() {
+            println!("Config unchanged");
+            return true;
+        }
+        let restart: Vec<_> = changes.iter().filter(|change| change.needs_restart()).collect();
+        if !restart.is_empty() {
+            for change in restart {
+                println!("Config rejected, needs a restart: {}", change);
+            }
+            return false;
+        }
+        for change in &changes {
+            println!("Config change: {}", change);
+        }
 
+        // Instruments, strategy and paper or live are the same, checked above
+        self.params = config;
+        self.hedger.set_params(self.params.hedge_params.clone());
+        self.ledger.set_params(&self.params.hedge_params);
+        self.update_fair_price();
 
+        let changed = if self.params.run_mode.risk_management_mode {
+            self.margin_monitor.set_threshold(self.params.quote_params.margin_ratio_threshold)
+        } else {
+            self.margin_monitor.clear()
+        };
+        if changed {
+            self.on_reduce_only_change().await;
+            return true;
         }
+
+        self.requote().await;
+        true
+    }
+
+
+    pub async fn poll(&mut self) {
+        // Same calls for both legs regardless of venue
+        self.sync_positions().await;
+        println!("Quote position: {:?}, hedge position: {:?}", self.quote_position, self.hedge_position);
+        println!("Ledger: {}", self.ledger);
+
+        println!("Polled....");
+
+    }
+
+    /// Run until something is sent on the shutdown channel, see stop_on_signals
+    ///
+    /// # Returns
+    /// * `bool` - Whether shutdown left both venues without orders of ours
+    pub async fn run(mut self) -> bool {
+        // State left by an earlier run first, retried from the timer if a venue is down
+        self.recover().await;
+        self.last_poll_time = chrono::Utc::now().timestamp_millis();
+
+        // Margin is known before the first quote goes out
+        if self.params.run_mode.risk_management_mode {
+            self.last_margin_check_time = self.last_poll_time;
+            self.check_margin().await;
+        }
+
+        // Seed both books over REST, streamed legs take over from their first update
+        self.process_book_updates().await;
+
+        let mut timer = tokio::time::interval(Duration::from_millis(TIMER_INTERVAL_MS));
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            // Whichever is ready first is handled first, nothing waits behind a sleep
+            tokio::select! {
+                Some(event) = self.receivers.executions.recv() => self.process_execution(event).await,
+                Some(update) = self.receivers.market_data.recv() => self.process_market_data(update).await,
+                Some(event) = self.receivers.feed_state.recv() => self.process_feed_event(event).await,
+                Some(config) = self.receivers.config.recv() => {
+                    self.process_config_updates(config).await;
+                }
+                _ = timer.tick() => self.process_timer().await,
+                Some(()) = self.receivers.shutdown.recv() => break,
+            }
+        }
+        self.shutdown().await
     }
 }
 
+#[cfg(unix)]
+async fn shutdown_signal() -> std::io::Result<&'static str> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result.map(|_| "SIGINT"),
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
 
+#[cfg(not(unix))]
+async fn shutdown_signal() -> std::io::Result<&'static str> {
+    tokio::signal::ctrl_c().await.map(|_| "Ctrl-C")
+}
@@ -0,0 +1,92 @@
+use tokio::sync::mpsc;
+use crate::data_structure::QTSFill;
+use crate::decimal::{Price, Qty};
+use crate::strategy::eye::params::EEConfig;
+
+/*
+    Events the Electronic Eye run loop reacts to
+
+    Market data, executions, feed state and config changes each come in on their own
+    channel, the loop selects over them and a timer so whichever arrives first is handled
+    first. Feed state is how a stream tells the loop it dropped, came back or gave up, so
+    quotes aren't left priced off a book that stopped updating.
+    Anything sent on shutdown ends the loop, see ElectronicEye::shutdown.
+    The feeds in feeds.rs are the usual producers, anything else holding the senders
+    can drive the strategy the same way.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leg {
+    Quote,
+    Hedge,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarketUpdate {
+    pub leg: Leg,
+    pub top_of_book: (Option<Price>, Option<Price>),   // best bid, best ask
+    pub trades: Vec<Price>,                             // public prints since the last update, only streamed for paper venues
+}
+
+#[derive(Debug, Clone)]
+pub enum ExecutionEvent {
+    Fill { leg: Leg, fill: QTSFill },
+    // Filled, cancelled, rejected or expired, filled_qty is the order's final cumulative fill
+    OrderClosed { leg: Leg, client_order_id: String, status: String, filled_qty: Qty },
+    // Funding paid on the leg's position, negative when received
+    Funding { leg: Leg, amount: f64 },
+}
+
+/// Which of a leg's streams a FeedEvent is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Book,
+    Executions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedStatus {
+    Down,               // connection lost, reconnecting
+    Resubscribed,       // back up, anything the venue sent in between was missed
+    GaveUp,             // no more reconnects, the leg is polled over REST from here on
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FeedEvent {
+    pub leg: Leg,
+    pub stream: Stream,
+    pub status: FeedStatus,
+}
+
+/// Sending side of the run loop's channels
+#[derive(Debug, Clone)]
+pub struct EESenders {
+    pub market_data: mpsc::UnboundedSender<MarketUpdate>,
+    pub executions: mpsc::UnboundedSender<ExecutionEvent>,
+    pub feed_state: mpsc::UnboundedSender<FeedEvent>,
+    pub config: mpsc::UnboundedSender<EEConfig>,
+    pub shutdown: mpsc::UnboundedSender<()>,
+}
+
+/// Receiving side, owned by the run loop
+pub struct EEReceivers {
+    pub market_data: mpsc::UnboundedReceiver<MarketUpdate>,
+    pub executions: mpsc::UnboundedReceiver<ExecutionEvent>,
+    pub feed_state: mpsc::UnboundedReceiver<FeedEvent>,
+    pub config: mpsc::UnboundedReceiver<EEConfig>,
+    pub shutdown: mpsc::UnboundedReceiver<()>,
+}
+
+/// Create the run loop's channels
+pub fn channels() -> (EESenders, EEReceivers) {
+    let (market_tx, market_rx) = mpsc::unbounded_channel();
+    let (execution_tx, execution_rx) = mpsc::unbounded_channel();
+    let (feed_tx, feed_rx) = mpsc::unbounded_channel();
+    let (config_tx, config_rx) = mpsc::unbounded_channel();
+    let (shutdown_tx, shutdown_rx) = mpsc::unbounded_channel();
+
+    (
+        EESenders { market_data: market_tx, executions: execution_tx, feed_state: feed_tx, config: config_tx, shutdown: shutdown_tx },
+        EEReceivers { market_data: market_rx, executions: execution_rx, feed_state: feed_rx, config: config_rx, shutdown: shutdown_rx },
+    )
+}
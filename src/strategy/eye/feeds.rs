@@ -0,0 +1,526 @@
+use std::error::Error;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use crate::data_structure::Symbol;
+use crate::decimal::{Price, Qty};
+use crate::enums::{Environment, Exchanges, InstrumentType};
+use crate::exchange::base_rest::RestClientBuilder;
+use crate::exchange::binance::binance_rest::BinanceRestClient;
+use crate::exchange::binance::binance_ws::{BinanceWebSocketClient, BinanceWebSocketConfig, BinanceWebSocketMessage};
+use crate::exchange::binance::response::{BinanceDepthUpdateStatus, BinanceLocalOrderbook};
+use crate::exchange::bybit::bybit_ws::{BybitDcpProduct, BybitPrivateTopic, BybitStreamType, BybitWebSocketClient, BybitWebSocketConfig, BybitWebSocketMessage};
+use crate::exchange::bybit::response::{BybitDcpRequest, LocalOrderbook, OrderbookSyncError};
+use crate::strategy::eye::events::{ExecutionEvent, FeedEvent, FeedStatus, Leg, MarketUpdate, Stream};
+
+/*
+    WebSocket feeds for the Electronic Eye
+
+    Each leg gets a book feed pushing its top of book into the market data channel, and
+    where the venue has a private stream, an execution feed pushing its fills and closed
+    orders into the execution channel. For the paper venues the book feed also streams
+    public trades, sent along with the top of book last sent. Binance has no user data
+    stream here yet, so fills on a Binance leg are still found by polling positions.
+
+    A Bybit execution feed can also hold the account's disconnect-cancel-all (DCP). The
+    window is set over REST for the leg's product and the stream subscribes to that
+    product's dcp topic, once that connection has been gone for the window, reconnects
+    included, Bybit cancels every order we have on the product, so a network partition
+    can't leave quotes resting unattended.
+
+    A feed only returns once the venue has acknowledged the subscription, anything the
+    venue sends from then on reaches the run loop. Drops, book resyncs, resubscribes and a
+    feed giving up go into the feed state channel, a book feed that dropped or reset always
+    sends its next top of book even when it matches the last one sent.
+*/
+
+// Depth of the Bybit book subscription, only the top is used
+const BYBIT_BOOK_DEPTH: u32 = 50;
+// Depth of the Binance REST snapshot the diff stream is applied on
+const BINANCE_SNAPSHOT_LIMIT: u32 = 100;
+// How long to wait for the venue to acknowledge a subscription
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+// Pause before retrying a Binance snapshot that failed or did not line up
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+type TopOfBook = (Option<Price>, Option<Price>);
+
+/// Where a leg's streams connect to
+#[derive(Debug, Clone, Default)]
+pub struct FeedConfig {
+    pub environment: Environment,               // Testnet picks the venue's testnet hosts, Custom is the REST base for snapshots
+    pub ws_url: Option<String>,                 // stream base URL override, e.g. a local mock, same form as the venue's ws config
+    pub credentials: Option<(String, String)>,  // api key and secret, needed for the private stream
+}
+
+impl FeedConfig {
+    fn is_testnet(&self) -> bool {
+        self.environment == Environment::Testnet
+    }
+}
+
+/// Stream a leg's top of book into the market data channel
+///
+/// # Arguments
+/// * `leg` - Which leg the instrument is
+/// * `symbol` - Instrument to stream
+/// * `config` - Hosts for the venue
+/// * `trades` - Whether to stream public trades as well, for the paper venues
+/// * `market_tx` - Run loop market data channel
+/// * `feed_tx` - Run loop feed state channel
+///
+/// # Returns
+/// * `Result<(), Box<dyn Error>>` - Error if the stream could not be connected and subscribed
+pub async fn spawn_book_feed(
+    leg: Leg,
+    symbol: Symbol,
+    config: &FeedConfig,
+    trades: bool,
+    market_tx: mpsc::UnboundedSender<MarketUpdate>,
+    feed_tx: mpsc::UnboundedSender<FeedEvent>,
+) -> Result<(), Box<dyn Error>> {
+    match symbol.exchange {
+        Exchanges::Bybit => spawn_bybit_book_feed(leg, symbol, config, trades, market_tx, feed_tx).await,
+        Exchanges::Binance => spawn_binance_book_feed(leg, symbol, config, trades, market_tx, feed_tx).await,
+        _ => Err(format!("No book feed implemented for {:?}", symbol.exchange).into()),
+    }
+}
+
+/// Stream a leg's fills and closed orders into the execution channel
+///
+/// # Arguments
+/// * `leg` - Which leg the instrument is
+/// * `symbol` - Instrument to report executions for, everything else on the account is ignored
+/// * `config` - Hosts and credentials for the venue
+/// * `dcp_window` - Seconds without the stream before the venue cancels our orders, 0 leaves it off
+/// * `execution_tx` - Run loop execution channel
+/// * `feed_tx` - Run loop feed state channel, a resubscribe means executions were missed
+///
+/// # Returns
+/// * `Result<bool, Box<dyn Error>>` - Whether a stream was started, false when the venue has none
+///   or no credentials were given, error if it could not be connected, authenticated or subscribed,
+///   or the disconnect-cancel-all window could not be set
+pub async fn spawn_execution_feed(
+    leg: Leg,
+    symbol: Symbol,
+    config: &FeedConfig,
+    dcp_window: u32,
+    execution_tx: mpsc::UnboundedSender<ExecutionEvent>,
+    feed_tx: mpsc::UnboundedSender<FeedEvent>,
+) -> Result<bool, Box<dyn Error>> {
+    let Some(credentials) = config.credentials.clone() else {
+        return Ok(false);
+    };
+    match symbol.exchange {
+        Exchanges::Bybit => {
+            spawn_bybit_execution_feed(leg, symbol, config, dcp_window, credentials, execution_tx, feed_tx).await?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn bybit_config(config: &FeedConfig, stream_type: BybitStreamType) -> BybitWebSocketConfig {
+    BybitWebSocketConfig {
+        stream_type,
+        is_testnet: config.is_testnet(),
+        base_url: config.ws_url.clone(),
+        ..Default::default()
+    }
+}
+
+fn bybit_stream_type(instrument_type: &InstrumentType) -> BybitStreamType {
+    match instrument_type {
+        InstrumentType::Spot => BybitStreamType::Spot,
+        InstrumentType::Inverse => BybitStreamType::Inverse,
+        InstrumentType::Options => BybitStreamType::Option,
+        InstrumentType::Perp | InstrumentType::Linear | InstrumentType::Futures => BybitStreamType::Linear,
+    }
+}
+
+/// DCP product a Bybit instrument falls under
+fn bybit_dcp_product(instrument_type: &InstrumentType) -> BybitDcpProduct {
+    match instrument_type {
+        InstrumentType::Spot => BybitDcpProduct::Spot,
+        InstrumentType::Options => BybitDcpProduct::Options,
+        InstrumentType::Perp | InstrumentType::Linear | InstrumentType::Inverse | InstrumentType::Futures => BybitDcpProduct::Derivatives,
+    }
+}
+
+/// Read until the venue answers the subscribe, keeping whatever arrives before it
+async fn await_bybit_subscribed(receiver: &mut mpsc::UnboundedReceiver<BybitWebSocketMessage>) -> Result<Vec<BybitWebSocketMessage>, Box<dyn Error>> {
+    let mut early = Vec::new();
+    tokio::time::timeout(SUBSCRIBE_TIMEOUT, async {
+        while let Some(message) = receiver.recv().await {
+            match message {
+                BybitWebSocketMessage::Auth(reply) if !reply.success => return Err(format!("Bybit auth failed: {}", reply.ret_msg)),
+                BybitWebSocketMessage::Subscription(reply) if reply.success => return Ok(()),
+                BybitWebSocketMessage::Subscription(reply) => return Err(format!("Bybit subscribe failed: {}", reply.ret_msg)),
+                message => early.push(message),
+            }
+        }
+        Err("Bybit stream closed before the subscription was acknowledged".to_string())
+    }).await.map_err(|_| "Timed out waiting for the Bybit subscription")??;
+    Ok(early)
+}
+
+/// What a Bybit book message meant for the local book
+enum BookStep {
+    Updated,
+    Traded(Vec<Price>),
+    Resync,
+    Down,       // connection lost, the book stops updating
+    Reset,      // reconnected, the book is empty until the replayed subscription's snapshot
+    Ignored,
+}
+
+fn apply_bybit_book_message(book: &mut LocalOrderbook, symbol: &Symbol, message: BybitWebSocketMessage) -> BookStep {
+    match message {
+        BybitWebSocketMessage::Orderbook(update) => match update.data_type.as_str() {
+            "snapshot" => match book.apply_snapshot(&update.data) {
+                Ok(()) => BookStep::Updated,
+                Err(e) => {
+                    println!("Bybit snapshot for {} refused ({}), resubscribing", symbol.symbol, e);
+                    BookStep::Resync
+                }
+            },
+            "delta" => match book.apply_delta(&update.data) {
+                Ok(()) => BookStep::Updated,
+                Err(OrderbookSyncError::StaleSequence { .. }) | Err(OrderbookSyncError::NoSnapshot) => BookStep::Ignored,
+                Err(e) => {
+                    println!("Bybit book for {} out of sync ({}), resubscribing", symbol.symbol, e);
+                    BookStep::Resync
+                }
+            },
+            _ => BookStep::Ignored,
+        },
+        BybitWebSocketMessage::Trade(trades) => {
+            BookStep::Traded(trades.data.iter().filter(|trade| trade.s == symbol.symbol).map(|trade| trade.p).collect())
+        }
+        // The replayed subscription starts over with a snapshot, drop the stale book until then
+        BybitWebSocketMessage::Reconnected { .. } => {
+            book.reset();
+            BookStep::Reset
+        }
+        BybitWebSocketMessage::Disconnected(reason) => {
+            println!("Bybit book feed for {} disconnected: {}", symbol.symbol, reason);
+            BookStep::Down
+        }
+        BybitWebSocketMessage::Error(err) => {
+            println!("Bybit book feed for {}: {}", symbol.symbol, err);
+            BookStep::Ignored
+        }
+        _ => BookStep::Ignored,
+    }
+}
+
+fn local_top_of_book(book: &LocalOrderbook) -> TopOfBook {
+    (book.best_bid().map(|level| level.price), book.best_ask().map(|level| level.price))
+}
+
+fn send_feed_status(feed_tx: &mpsc::UnboundedSender<FeedEvent>, leg: Leg, stream: Stream, status: FeedStatus) {
+    // Run loop gone, the feed stops on its next send to it
+    let _ = feed_tx.send(FeedEvent { leg, stream, status });
+}
+
+async fn spawn_bybit_book_feed(
+    leg: Leg,
+    symbol: Symbol,
+    config: &FeedConfig,
+    trades: bool,
+    market_tx: mpsc::UnboundedSender<MarketUpdate>,
+    feed_tx: mpsc::UnboundedSender<FeedEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let mut book = LocalOrderbook::from_symbol(&symbol)?;
+    let mut client = BybitWebSocketClient::new(bybit_config(config, bybit_stream_type(&symbol.instrument_type)));
+    let (_sender, mut receiver) = client.connect().await?;
+    client.subscribe_orderbook(&symbol.symbol, BYBIT_BOOK_DEPTH).await?;
+    let mut early = await_bybit_subscribed(&mut receiver).await?;
+    if trades {
+        client.subscribe_trades(&symbol.symbol).await?;
+        early.extend(await_bybit_subscribed(&mut receiver).await?);
+    }
+
+    tokio::spawn(async move {
+        let mut last_sent: Option<TopOfBook> = None;
+        let mut early = early.into_iter();
+
+        loop {
+            let message = match early.next() {
+                Some(message) => message,
+                None => match receiver.recv().await {
+                    Some(message) => message,
+                    None => break,
+                },
+            };
+            if matches!(message, BybitWebSocketMessage::GaveUp) {
+                println!("Bybit book feed for {} gave up reconnecting", symbol.symbol);
+                send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::GaveUp);
+                break;
+            }
+
+            match apply_bybit_book_message(&mut book, &symbol, message) {
+                BookStep::Updated => {
+                    let top_of_book = local_top_of_book(&book);
+                    if last_sent != Some(top_of_book) {
+                        last_sent = Some(top_of_book);
+                        if market_tx.send(MarketUpdate { leg, top_of_book, trades: Vec::new() }).is_err() {
+                            break;
+                        }
+                    }
+                }
+                // Nothing to match them against before the first top of book
+                BookStep::Traded(trades) => {
+                    if let Some(top_of_book) = last_sent
+                        && !trades.is_empty()
+                        && market_tx.send(MarketUpdate { leg, top_of_book, trades }).is_err() {
+                        break;
+                    }
+                }
+                // The book is thrown away until the fresh snapshot, same as a dropped connection
+                BookStep::Resync => {
+                    last_sent = None;
+                    send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::Down);
+                    if let Err(e) = client.resubscribe_orderbook(&symbol.symbol, BYBIT_BOOK_DEPTH).await {
+                        println!("Failed to resubscribe {}: {}", symbol.symbol, e);
+                    }
+                    send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::Resubscribed);
+                }
+                BookStep::Down => {
+                    last_sent = None;
+                    send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::Down);
+                }
+                BookStep::Reset => {
+                    last_sent = None;
+                    send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::Resubscribed);
+                }
+                BookStep::Ignored => {}
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn spawn_bybit_execution_feed(
+    leg: Leg,
+    symbol: Symbol,
+    config: &FeedConfig,
+    dcp_window: u32,
+    (api_key, api_secret): (String, String),
+    execution_tx: mpsc::UnboundedSender<ExecutionEvent>,
+    feed_tx: mpsc::UnboundedSender<FeedEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let mut topics = vec![BybitPrivateTopic::Order, BybitPrivateTopic::Execution];
+    if dcp_window > 0 {
+        // Window first, the subscription is what arms it
+        let rest_client = RestClientBuilder::new(api_key.clone(), api_secret.clone())
+            .environment(config.environment.clone())
+            .build_bybit()?;
+        let product = bybit_dcp_product(&symbol.instrument_type);
+        rest_client.set_dcp(&BybitDcpRequest { product: Some(product.as_str().to_string()), timeWindow: dcp_window }).await?;
+        println!("Bybit disconnect-cancel-all armed on {} with a {}s window", product.as_str(), dcp_window);
+        topics.push(BybitPrivateTopic::Dcp(product));
+    }
+
+    let mut client = BybitWebSocketClient::new_private(bybit_config(config, BybitStreamType::Private), api_key, api_secret);
+    let (_sender, mut receiver) = client.connect().await?;
+    client.subscribe_private(&topics).await?;
+    let early = await_bybit_subscribed(&mut receiver).await?;
+
+    tokio::spawn(async move {
+        // Keeps the connection open, it closes once every handle to the client is gone
+        let _client = client;
+        let mut early = early.into_iter();
+
+        loop {
+            let message = match early.next() {
+                Some(message) => message,
+                None => match receiver.recv().await {
+                    Some(message) => message,
+                    None => break,
+                },
+            };
+
+            let events: Vec<ExecutionEvent> = match message {
+                BybitWebSocketMessage::Execution(response) => response.data.iter()
+                    .filter(|execution| execution.symbol == symbol.symbol)
+                    .filter_map(|execution| match execution.execType.as_str() {
+                        "Trade" => match execution.to_fill() {
+                            Ok(fill) => Some(ExecutionEvent::Fill { leg, fill }),
+                            Err(e) => {
+                                println!("Failed to parse execution {}: {}", execution.execId, e);
+                                None
+                            }
+                        },
+                        // execFee of a funding execution is the funding paid, negative when received
+                        "Funding" => Some(ExecutionEvent::Funding { leg, amount: execution.execFee.parse().unwrap_or(0.0) }),
+                        // ADL and liquidations aren't ours, the position check picks them up
+                        _ => None,
+                    })
+                    .collect(),
+                BybitWebSocketMessage::Order(response) => response.data.iter()
+                    .filter(|order| order.symbol == symbol.symbol && order.is_closed())
+                    .map(|order| ExecutionEvent::OrderClosed {
+                        leg,
+                        client_order_id: order.orderLinkId.clone(),
+                        status: order.orderStatus.clone(),
+                        filled_qty: order.cumExecQty.parse().unwrap_or(Qty::ZERO),
+                    })
+                    .collect(),
+                BybitWebSocketMessage::Disconnected(reason) => {
+                    println!("Bybit execution feed disconnected: {}", reason);
+                    send_feed_status(&feed_tx, leg, Stream::Executions, FeedStatus::Down);
+                    Vec::new()
+                }
+                // Bybit doesn't replay what was sent while we were gone
+                BybitWebSocketMessage::Reconnected { .. } => {
+                    send_feed_status(&feed_tx, leg, Stream::Executions, FeedStatus::Resubscribed);
+                    Vec::new()
+                }
+                BybitWebSocketMessage::GaveUp => {
+                    println!("Bybit execution feed gave up reconnecting");
+                    send_feed_status(&feed_tx, leg, Stream::Executions, FeedStatus::GaveUp);
+                    break;
+                }
+                BybitWebSocketMessage::Error(err) => {
+                    println!("Bybit execution feed: {}", err);
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            };
+
+            for event in events {
+                if execution_tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn spawn_binance_book_feed(
+    leg: Leg,
+    symbol: Symbol,
+    config: &FeedConfig,
+    trades: bool,
+    market_tx: mpsc::UnboundedSender<MarketUpdate>,
+    feed_tx: mpsc::UnboundedSender<FeedEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let mut book = BinanceLocalOrderbook::new(symbol.symbol.clone(), symbol.min_tick)?;
+    // Snapshots are public, no credentials needed
+    let rest_client = RestClientBuilder::new(String::new(), String::new())
+        .environment(config.environment.clone())
+        .build_binance()?;
+
+    let mut client = BinanceWebSocketClient::new(BinanceWebSocketConfig {
+        is_testnet: config.is_testnet(),
+        base_url: config.ws_url.clone(),
+    });
+    let (_sender, mut receiver) = client.connect().await?;
+    client.subscribe_depth(&symbol.symbol).await?;
+    let mut early = await_binance_subscribed(&mut receiver).await?;
+    if trades {
+        client.subscribe_agg_trades(&symbol.symbol).await?;
+        early.extend(await_binance_subscribed(&mut receiver).await?);
+    }
+
+    tokio::spawn(async move {
+        let _client = client;
+        let mut last_sent: Option<TopOfBook> = None;
+        let mut resyncing = false;
+        let mut early = early.into_iter();
+
+        loop {
+            let message = match early.next() {
+                Some(message) => message,
+                None => match receiver.recv().await {
+                    Some(message) => message,
+                    // The Binance client doesn't reconnect, a closed stream is the end of it
+                    None => {
+                        send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::GaveUp);
+                        break;
+                    }
+                },
+            };
+            match message {
+                // A gap or a crossed book throws the book away until a new snapshot lines up,
+                // the run loop hears of it like a dropped connection
+                BinanceWebSocketMessage::DepthUpdate(update) => {
+                    if book.apply_update(update) == BinanceDepthUpdateStatus::OutOfSync && !resyncing {
+                        println!("Binance book for {} out of sync, taking a new snapshot", symbol.symbol);
+                        resyncing = true;
+                        last_sent = None;
+                        send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::Down);
+                    }
+                }
+                // Nothing to match them against before the first top of book
+                BinanceWebSocketMessage::AggTrade(trade) => {
+                    if let Some(top_of_book) = last_sent
+                        && trade.s == symbol.symbol
+                        && market_tx.send(MarketUpdate { leg, top_of_book, trades: vec![trade.p] }).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                BinanceWebSocketMessage::Error(err) => {
+                    println!("Binance book feed for {}: {}", symbol.symbol, err);
+                    continue;
+                }
+                _ => continue,
+            }
+
+            // Snapshot once a diff is buffered, anything arriving meanwhile queues on the channel
+            while book.needs_snapshot() && book.buffered() > 0 {
+                sync_binance_snapshot(&rest_client, &symbol, &mut book).await;
+            }
+            if book.needs_snapshot() {
+                continue;
+            }
+            if resyncing {
+                resyncing = false;
+                send_feed_status(&feed_tx, leg, Stream::Book, FeedStatus::Resubscribed);
+            }
+
+            let top_of_book = local_top_of_book(&book.book);
+            if last_sent != Some(top_of_book) {
+                last_sent = Some(top_of_book);
+                if market_tx.send(MarketUpdate { leg, top_of_book, trades: Vec::new() }).is_err() {
+                    break;
+                }
+            }
+        }
+        println!("Binance book feed for {} stopped", symbol.symbol);
+    });
+    Ok(())
+}
+
+/// Read until the venue answers the subscribe, keeping whatever arrives before it
+async fn await_binance_subscribed(receiver: &mut mpsc::UnboundedReceiver<BinanceWebSocketMessage>) -> Result<Vec<BinanceWebSocketMessage>, Box<dyn Error>> {
+    let mut early = Vec::new();
+    tokio::time::timeout(SUBSCRIBE_TIMEOUT, async {
+        while let Some(message) = receiver.recv().await {
+            match message {
+                BinanceWebSocketMessage::Subscription(reply) if reply.result.is_none() => return Ok(()),
+                BinanceWebSocketMessage::Subscription(reply) => return Err(format!("Binance subscribe failed: {:?}", reply.result)),
+                message => early.push(message),
+            }
+        }
+        Err("Binance stream closed before the subscription was acknowledged".to_string())
+    }).await.map_err(|_| "Timed out waiting for the Binance subscription")??;
+    Ok(early)
+}
+
+async fn sync_binance_snapshot(rest_client: &BinanceRestClient, symbol: &Symbol, book: &mut BinanceLocalOrderbook) {
+    match rest_client.get_orderbook(&symbol.symbol, Some(BINANCE_SNAPSHOT_LIMIT)).await {
+        Ok(snapshot) => {
+            if book.apply_snapshot(&snapshot) == BinanceDepthUpdateStatus::OutOfSync {
+                println!("Binance snapshot for {} does not line up with buffered updates, retrying", symbol.symbol);
+                tokio::time::sleep(SNAPSHOT_RETRY_DELAY).await;
+            }
+        }
+        Err(e) => {
+            println!("Failed to get Binance snapshot for {}: {}", symbol.symbol, e);
+            tokio::time::sleep(SNAPSHOT_RETRY_DELAY).await;
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::data_structure::{QTSOrderRequest, Symbol};
+use crate::decimal::{Price, Qty, Rounding};
+use crate::enums::{HedgeMode, OrderType, Side, TimeInForce};
+use crate::oms::new_client_order_id;
+use crate::strategy::strategy_structs::HedgeParams;
+
+/*
+    Hedger for the Electronic Eye
+
+    Quote fills are converted to USD and scaled by hedge_ratio, a $1 buy on the quote leg
+    means hedge_ratio dollars to sell on the hedge leg. The running total not yet filled on
+    the hedge leg is the residual unhedged delta, it only shrinks on actual hedge fills.
+
+    Fills are batched, the first unhedged fill starts a hedge_delay window and everything
+    that arrives inside it goes out as one order:
+
+        hedge_mode 0    track the residual, never trade
+        hedge_mode 1    IOC limit through the touch, capped at max_slippage from it
+        hedge_mode 2    passive limit hedge_offset behind the touch, left to rest and only
+                        replaced when the residual changes
+
+    Orders are sized to the nearest lot and the window stays open until the residual is
+    under half a lot, so whatever an IOC leaves unfilled is retried as soon as the venue
+    reports the order closed, or the hedge position shows it. Anything smaller stays on
+    the books until later fills add to it.
+*/
+
+// Client order ids of hedge orders
+pub const HEDGE_ID_PREFIX: &str = "eh-";
+
+#[derive(Debug, Clone)]
+pub enum HedgeAction {
+    Place(QTSOrderRequest),
+    Cancel(String),             // client order id
+}
+
+#[derive(Debug, Clone)]
+struct WorkingHedge {
+    client_order_id: String,
+    side: Side,
+    qty: Qty,
+    filled: Qty,
+    passive: bool,
+    cancel_sent: bool,
+}
+
+pub struct Hedger {
+    params: HedgeParams,
+    symbol: Symbol,
+
+    unhedged_usd: f64,              // signed, positive when the hedge leg still has to buy
+    batch_start_ms: Option<i64>,
+    working: Option<WorkingHedge>,
+}
+
+impl Hedger {
+    pub fn new(params: HedgeParams, symbol: Symbol) -> Self {
+        Hedger {
+            params,
+            symbol,
+            unhedged_usd: 0.0,
+            batch_start_ms: None,
+            working: None,
+        }
+    }
+
+    /// Signed USD notional still to be hedged, positive means buy on the hedge leg
+    pub fn unhedged_usd(&self) -> f64 {
+        self.unhedged_usd
+    }
+
+    /// Client order id of the hedge order currently out, if any
+    pub fn working_order(&self) -> Option<&str> {
+        self.working.as_ref().map(|working| working.client_order_id.as_str())
+    }
+
+    /// Pick up new hedge parameters, e.g. from a config change
+    pub fn set_params(&mut self, params: HedgeParams) {
+        self.params = params;
+    }
+
+    /// Record a fill on the quote instrument
+    ///
+    /// # Arguments
+    /// * `side` - Side of the quote fill, the hedge goes the other way
+    /// * `qty` - Filled size
+    /// * `price` - Fill price
+    /// * `now_ms` - Fill time, opens the hedge_delay window if none is open
+    pub fn on_quote_fill(&mut self, side: Side, qty: Qty, price: Price, now_ms: i64) {
+        let usd = price.notional(qty) * self.params.hedge_ratio;
+        match side {
+            Side::Buy => self.unhedged_usd -= usd,
+            Side::Sell => self.unhedged_usd += usd,
+        }
+        self.batch_start_ms.get_or_insert(now_ms);
+    }
+
+    /// Record a fill on the hedge instrument
+    ///
+    /// # Arguments
+    /// * `side` - Side of the hedge fill
+    /// * `qty` - Filled size
+    /// * `price` - Fill price
+    pub fn on_hedge_fill(&mut self, side: Side, qty: Qty, price: Price) {
+        let usd = price.notional(qty);
+        match side {
+            Side::Buy => self.unhedged_usd -= usd,
+            Side::Sell => self.unhedged_usd += usd,
+        }
+
+        if let Some(working) = &mut self.working
+            && working.side == side {
+            working.filled += qty;
+            if working.filled >= working.qty {
+                self.working = None;
+            }
+        }
+    }
+
+    /// The hedge position has been read back, an IOC sent before it has played out by now
+    pub fn on_hedge_synced(&mut self) {
+        if self.working.as_ref().is_some_and(|working| !working.passive) {
+            self.working = None;
+        }
+    }
+
+    /// The venue reports a hedge order finished, nothing fills on it past `filled_qty`
+    ///
+    /// Fills can still be on their way, the order stays working until they have all come in
+    pub fn on_order_closed(&mut self, client_order_id: &str, filled_qty: Qty) {
+        if let Some(working) = &mut self.working
+            && working.client_order_id == client_order_id {
+            working.qty = filled_qty;
+            if working.filled >= working.qty {
+                self.working = None;
+            }
+        }
+    }
+
+    /// A hedge order was rejected, cancelled or is otherwise no longer working
+    pub fn on_order_done(&mut self, client_order_id: &str) {
+        if self.working.as_ref().is_some_and(|working| working.client_order_id == client_order_id) {
+            self.working = None;
+        }
+    }
+
+    /// The venue did not take the cancel of a hedge order, it is still working and the cancel
+    /// goes out again on the next call
+    pub fn on_cancel_failed(&mut self, client_order_id: &str) {
+        if let Some(working) = &mut self.working
+            && working.client_order_id == client_order_id {
+            working.cancel_sent = false;
+        }
+    }
+
+    /// Decide what to send to the hedge venue next
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time, for the hedge_delay window
+    /// * `top_of_book` - Best bid and ask on the hedge instrument
+    ///
+    /// # Returns
+    /// * `Option<HedgeAction>` - At most one order or cancel per call
+    pub fn next_action(&mut self, now_ms: i64, top_of_book: (Option<Price>, Option<Price>)) -> Option<HedgeAction> {
+        let side = if self.unhedged_usd > 0.0 { Side::Buy } else { Side::Sell };
+        let (best_bid, best_ask) = top_of_book;
+
+        match self.params.hedge_mode {
+            HedgeMode::Active => {
+                // Wait for the IOC already out to show up in the hedge position
+                if self.working.is_some() || !self.batch_due(now_ms) {
+                    return None;
+                }
+                let touch = if side == Side::Buy { best_ask? } else { best_bid? };
+                let qty = self.hedge_qty(touch)?;
+                self.ioc(side, qty, touch)
+            }
+            HedgeMode::Passive => {
+                let touch = if side == Side::Buy { best_bid? } else { best_ask? };
+                let needed = [self.residual_qty(touch, Rounding::Down), self.residual_qty(touch, Rounding::Nearest)];
+
+                if let Some(working) = &mut self.working {
+                    // Resting order still covers what is left to hedge, rounded either way so
+                    // partial fills away from the touch don't cause a replace
+                    let covered = needed.contains(&(working.qty - working.filled));
+                    if working.cancel_sent || (working.side == side && covered) {
+                        return None;
+                    }
+                    working.cancel_sent = true;
+                    return Some(HedgeAction::Cancel(working.client_order_id.clone()));
+                }
+                if !self.batch_due(now_ms) {
+                    return None;
+                }
+
+                let qty = self.hedge_qty(touch)?;
+                let offset = touch.to_f64() * self.params.hedge_offset;
+                let limit = match side {
+                    Side::Buy => self.symbol.round_bid(Price::from_f64(touch.to_f64() - offset).ok()?),
+                    Side::Sell => self.symbol.round_ask(Price::from_f64(touch.to_f64() + offset).ok()?),
+                };
+                Some(self.place(side, qty, limit, TimeInForce::PostOnly, true))
+            }
+            HedgeMode::NoHedge => None,
+        }
+    }
+
+    /// The whole residual as one IOC through the touch whatever the hedge_mode, for shutting down
+    ///
+    /// # Arguments
+    /// * `top_of_book` - Best bid and ask on the hedge instrument
+    ///
+    /// # Returns
+    /// * `Option<HedgeAction>` - None when under half a lot is left or that side has no touch
+    pub fn flatten(&mut self, top_of_book: (Option<Price>, Option<Price>)) -> Option<HedgeAction> {
+        let side = if self.unhedged_usd > 0.0 { Side::Buy } else { Side::Sell };
+        let touch = if side == Side::Buy { top_of_book.1? } else { top_of_book.0? };
+        let qty = self.residual_qty(touch, Rounding::Nearest);
+        if !qty.is_positive() {
+            return None;
+        }
+        self.ioc(side, qty, touch)
+    }
+
+    /// IOC limit through the touch, capped at max_slippage from it
+    fn ioc(&mut self, side: Side, qty: Qty, touch: Price) -> Option<HedgeAction> {
+        let slippage = touch.to_f64() * self.params.max_slippage;
+        let limit = match side {
+            Side::Buy => self.symbol.round_bid(Price::from_f64(touch.to_f64() + slippage).ok()?),
+            Side::Sell => self.symbol.round_ask(Price::from_f64(touch.to_f64() - slippage).ok()?),
+        };
+        Some(self.place(side, qty, limit, TimeInForce::IOC, false))
+    }
+
+    fn batch_due(&self, now_ms: i64) -> bool {
+        self.batch_start_ms.is_some_and(|start| now_ms - start >= (self.params.hedge_delay * 1000.0) as i64)
+    }
+
+    /// Residual in whole lots at a price, closes the batch window once under half a lot
+    fn hedge_qty(&mut self, price: Price) -> Option<Qty> {
+        // Nearest, rounding down would leave a lot behind whenever the legs trade a few ticks apart
+        let qty = self.residual_qty(price, Rounding::Nearest);
+        if !qty.is_positive() {
+            self.batch_start_ms = None;
+            return None;
+        }
+        Some(qty)
+    }
+
+    fn residual_qty(&self, price: Price, rounding: Rounding) -> Qty {
+        Qty::from_f64(self.unhedged_usd.abs() / price.to_f64())
+            .map(|qty| qty.round_to_lot(self.symbol.qty_decimal, rounding))
+            .unwrap_or(Qty::ZERO)
+    }
+
+    fn place(&mut self, side: Side, qty: Qty, price: Price, time_in_force: TimeInForce, passive: bool) -> HedgeAction {
+        let client_order_id = new_client_order_id(HEDGE_ID_PREFIX);
+
+        self.working = Some(WorkingHedge {
+            client_order_id: client_order_id.clone(),
+            side,
+            qty,
+            filled: Qty::ZERO,
+            passive,
+            cancel_sent: false,
+        });
+
+        HedgeAction::Place(QTSOrderRequest {
+            side,
+            order_type: OrderType::Limit,
+            price: Some(price),
+            qty,
+            time_in_force,
+            client_order_id,
+            reduce_only: false,
+        })
+    }
+}
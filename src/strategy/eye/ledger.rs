@@ -0,0 +1,224 @@
+use crate::data_structure::{QTSFill, QTSPosition};
+use crate::decimal::{Price, Qty};
+use crate::enums::Side;
+use crate::strategy::eye::events::Leg;
+use crate::strategy::strategy_structs::HedgeParams;
+
+/*
+    Position and PnL ledger
+
+    Built from our own fills rather than read off the venue. Every fill moves the leg's
+    signed size and average entry, the closed part of a reducing fill is realized against
+    that entry. Fees and funding are kept apart from trading PnL as amounts paid, so a
+    maker rebate or funding received is negative. Unrealized PnL marks the open size at
+    the leg's mid.
+
+    The net position is what the hedger works to keep flat, in hedge leg contracts. The
+    quote size is scaled by hedge_ratio * price_ratio, the hedge size each quote contract
+    calls for, and added to the hedge size. Amounts are in the quote currency.
+
+    The venue's position endpoints are the check on it, a size that disagrees means a fill
+    we never saw, the venue's size and entry are taken over and the drift is logged.
+*/
+
+#[derive(Debug, Clone, Default)]
+pub struct PositionKeeper {
+    size: Qty,                  // signed, negative when short
+    entry_price: f64,           // average entry of the open size, 0 when flat
+    realized_pnl: f64,
+    fees: f64,
+    funding: f64,
+    mark_price: Option<f64>,
+}
+
+impl PositionKeeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn size(&self) -> Qty {
+        self.size
+    }
+
+    /// Average entry, None when flat
+    pub fn entry_price(&self) -> Option<f64> {
+        (!self.size.is_zero()).then_some(self.entry_price)
+    }
+
+    pub fn mark_price(&self) -> Option<f64> {
+        self.mark_price
+    }
+
+    pub fn realized_pnl(&self) -> f64 {
+        self.realized_pnl
+    }
+
+    pub fn fees(&self) -> f64 {
+        self.fees
+    }
+
+    pub fn funding(&self) -> f64 {
+        self.funding
+    }
+
+    /// Open size at the mark, 0 until there is one
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.mark_price.map_or(0.0, |mark| (mark - self.entry_price) * self.size.to_f64())
+    }
+
+    /// Realized and unrealized, less fees and funding
+    pub fn net_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl() - self.fees - self.funding
+    }
+
+    /// Move the position by a fill
+    ///
+    /// # Arguments
+    /// * `side` - Side of the fill
+    /// * `qty` - Filled size
+    /// * `price` - Fill price
+    /// * `fee` - Fee paid, negative for a rebate
+    pub fn on_fill(&mut self, side: Side, qty: Qty, price: Price, fee: f64) {
+        self.fees += fee;
+
+        let signed = if side == Side::Sell { -qty } else { qty };
+        let size = self.size.to_f64();
+        let change = signed.to_f64();
+        let fill_price = price.to_f64();
+
+        if size == 0.0 || size.signum() == change.signum() {
+            // Opening or adding, average the entry
+            self.entry_price = (self.entry_price * size.abs() + fill_price * change.abs()) / (size.abs() + change.abs());
+        } else {
+            // Reducing, realize on the closed part and start over if it flips
+            let closed = change.abs().min(size.abs());
+            self.realized_pnl += (fill_price - self.entry_price) * closed * size.signum();
+            if change.abs() > size.abs() {
+                self.entry_price = fill_price;
+            }
+        }
+        self.size += signed;
+        if self.size.is_zero() {
+            self.entry_price = 0.0;
+        }
+    }
+
+    /// Funding paid, negative when received
+    pub fn on_funding(&mut self, amount: f64) {
+        self.funding += amount;
+    }
+
+    pub fn set_mark(&mut self, price: Price) {
+        self.mark_price = Some(price.to_f64());
+    }
+
+    /// Take over the venue's size and entry, PnL so far is kept
+    pub fn reset(&mut self, size: Qty, entry_price: Price) {
+        self.size = size;
+        self.entry_price = if size.is_zero() { 0.0 } else { entry_price.to_f64() };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    quote: PositionKeeper,
+    hedge: PositionKeeper,
+    hedge_per_quote: f64,       // hedge contracts per quote contract, hedge_ratio * price_ratio
+}
+
+impl Ledger {
+    pub fn new(params: &HedgeParams) -> Self {
+        Ledger {
+            quote: PositionKeeper::new(),
+            hedge: PositionKeeper::new(),
+            hedge_per_quote: params.hedge_ratio * params.price_ratio,
+        }
+    }
+
+    /// Hedge and price ratios edited in the config
+    pub fn set_params(&mut self, params: &HedgeParams) {
+        self.hedge_per_quote = params.hedge_ratio * params.price_ratio;
+    }
+
+    pub fn leg(&self, leg: Leg) -> &PositionKeeper {
+        match leg {
+            Leg::Quote => &self.quote,
+            Leg::Hedge => &self.hedge,
+        }
+    }
+
+    pub fn leg_mut(&mut self, leg: Leg) -> &mut PositionKeeper {
+        match leg {
+            Leg::Quote => &mut self.quote,
+            Leg::Hedge => &mut self.hedge,
+        }
+    }
+
+    pub fn on_fill(&mut self, leg: Leg, fill: &QTSFill) {
+        self.leg_mut(leg).on_fill(fill.side, fill.qty, fill.price, fill.fee);
+    }
+
+    pub fn on_funding(&mut self, leg: Leg, amount: f64) {
+        self.leg_mut(leg).on_funding(amount);
+    }
+
+    pub fn set_mark(&mut self, leg: Leg, price: Price) {
+        self.leg_mut(leg).set_mark(price);
+    }
+
+    /// Quote size in hedge contracts plus the hedge size, 0 when fully hedged
+    pub fn net_position(&self) -> f64 {
+        self.quote.size.to_f64() * self.hedge_per_quote + self.hedge.size.to_f64()
+    }
+
+    pub fn realized_pnl(&self) -> f64 {
+        self.quote.realized_pnl + self.hedge.realized_pnl
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.quote.unrealized_pnl() + self.hedge.unrealized_pnl()
+    }
+
+    pub fn fees(&self) -> f64 {
+        self.quote.fees + self.hedge.fees
+    }
+
+    pub fn funding(&self) -> f64 {
+        self.quote.funding + self.hedge.funding
+    }
+
+    pub fn net_pnl(&self) -> f64 {
+        self.quote.net_pnl() + self.hedge.net_pnl()
+    }
+
+    /// Check a leg against the venue's positions, taking the venue's over on a mismatch
+    ///
+    /// # Arguments
+    /// * `leg` - Leg the positions were read from
+    /// * `positions` - Venue positions on the leg's instrument
+    ///
+    /// # Returns
+    /// * `Option<Qty>` - Venue size less ours, None when they agree
+    pub fn check(&mut self, leg: Leg, positions: &[QTSPosition]) -> Option<Qty> {
+        let size: Qty = positions.iter().map(|position| position.size).sum();
+        let keeper = self.leg_mut(leg);
+        let drift = size - keeper.size;
+        if drift.is_zero() {
+            return None;
+        }
+
+        let entry_price = positions.iter().find(|position| !position.size.is_zero()).map_or(Price::ZERO, |position| position.entry_price);
+        keeper.reset(size, entry_price);
+        Some(drift)
+    }
+}
+
+impl std::fmt::Display for Ledger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "quote {} hedge {} net {:.8}, realized {:.4}, unrealized {:.4}, fees {:.4}, funding {:.4}, pnl {:.4}",
+            self.quote.size, self.hedge.size, self.net_position(), self.realized_pnl(), self.unrealized_pnl(), self.fees(), self.funding(), self.net_pnl(),
+        )
+    }
+}
@@ -1,2 +1,10 @@
 pub mod ee;
+pub mod events;
+pub mod feeds;
+pub mod hedger;
+pub mod ledger;
+pub mod paper;
 pub mod params;
+pub mod quoter;
+pub mod reload;
+pub mod risk;
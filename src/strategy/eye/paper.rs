@@ -0,0 +1,363 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use crate::data_structure::{QTSBalance, QTSFill, QTSMargin, QTSOpenOrder, QTSOrderAck, QTSOrderBook, QTSOrderRequest, QTSPosition, QTSTicker, Symbol};
+use crate::decimal::{Price, Qty};
+use crate::enums::{Exchanges, OrderType, Side, TimeInForce};
+use crate::exchange::base_rest::ExchangeRest;
+use crate::exchange::error::{ExchangeError, ExchangeResult};
+use crate::strategy::eye::events::{ExecutionEvent, Leg};
+use crate::strategy::eye::ledger::PositionKeeper;
+
+/*
+    Paper trading venue, used for both legs when RunMode.debug is set
+
+    Market data comes from the real venue client, nothing else touches it. Orders are
+    matched locally against the top of book and the public trades the strategy sees:
+
+        marketable on arrival   fills in full at the touch, taker fee
+        resting                 fills in full at its own price once the other side of the
+                                book reaches it or a trade prints at or through it, maker fee
+        post-only that crosses  rejected
+        IOC / FOK not crossing  closed unfilled
+
+    Queue position and touch size aren't known, a trade at a resting order's price is
+    assumed to reach it and a marketable order to get its whole size at the touch.
+    Cancels of orders it doesn't hold fail with the venue's own unknown order code.
+
+    Fills and closed orders go out on the execution channel exactly like the private
+    stream's, positions and balances are the simulated ones.
+*/
+
+// Code on locally rejected orders, no venue involved
+const PAPER_REJECT_CODE: i64 = -1;
+
+/// Fee rates as fractions of notional, positive is a cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaperFees {
+    pub maker: f64,
+    pub taker: f64,
+}
+
+impl PaperFees {
+    /// Base tier fees of the venue's USDT perpetuals
+    pub fn for_exchange(exchange: &Exchanges) -> Self {
+        match exchange {
+            Exchanges::Bybit => PaperFees { maker: 0.0002, taker: 0.00055 },
+            Exchanges::Binance => PaperFees { maker: 0.0002, taker: 0.0005 },
+            _ => PaperFees { maker: 0.0002, taker: 0.0005 },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PaperState {
+    top_of_book: (Option<Price>, Option<Price>),
+    resting: Vec<QTSOpenOrder>,
+    position: PositionKeeper,
+    next_order_id: u64,
+}
+
+/// Simulated venue for one instrument, clones share the same book of orders
+#[derive(Clone)]
+pub struct PaperExchange {
+    market: Arc<dyn ExchangeRest>,
+    symbol: Symbol,
+    leg: Leg,
+    fees: PaperFees,
+    state: Arc<Mutex<PaperState>>,
+    executions: mpsc::UnboundedSender<ExecutionEvent>,
+}
+
+impl PaperExchange {
+    /// Create a paper venue for one leg
+    ///
+    /// # Arguments
+    /// * `market` - Real venue client, only used for market data
+    /// * `symbol` - Instrument traded on this venue
+    /// * `leg` - Leg the fills are reported for
+    /// * `executions` - Execution channel of the run loop
+    pub fn new(market: Box<dyn ExchangeRest>, symbol: Symbol, leg: Leg, executions: mpsc::UnboundedSender<ExecutionEvent>) -> Self {
+        let fees = PaperFees::for_exchange(&symbol.exchange);
+        PaperExchange {
+            market: Arc::from(market),
+            symbol,
+            leg,
+            fees,
+            state: Arc::new(Mutex::new(PaperState::default())),
+            executions,
+        }
+    }
+
+    pub fn with_fees(mut self, fees: PaperFees) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn leg(&self) -> Leg {
+        self.leg
+    }
+
+    /// Realized PnL and fees paid so far, in the quote currency
+    pub fn pnl(&self) -> (f64, f64) {
+        let state = self.state.lock().unwrap();
+        (state.position.realized_pnl(), state.position.fees())
+    }
+
+    /// Feed a new top of book, resting orders the book has reached are filled
+    ///
+    /// # Arguments
+    /// * `top_of_book` - Best bid and ask on the instrument
+    pub fn on_top_of_book(&self, top_of_book: (Option<Price>, Option<Price>)) {
+        let mut state = self.state.lock().unwrap();
+        state.top_of_book = top_of_book;
+        let (best_bid, best_ask) = top_of_book;
+        self.fill_reached(&mut state, best_ask, best_bid);
+    }
+
+    /// Feed public trades, resting orders a trade printed at or through are filled
+    ///
+    /// # Arguments
+    /// * `trades` - Prices of the trades
+    pub fn on_trades(&self, trades: &[Price]) {
+        let mut state = self.state.lock().unwrap();
+        self.fill_reached(&mut state, trades.iter().min().copied(), trades.iter().max().copied());
+    }
+
+    /// Fill in full the resting buys at or above `sell_at` and sells at or below `buy_at`
+    fn fill_reached(&self, state: &mut PaperState, sell_at: Option<Price>, buy_at: Option<Price>) {
+        let (reached, resting): (Vec<_>, Vec<_>) = std::mem::take(&mut state.resting).into_iter().partition(|order| match order.side {
+            Side::Buy => sell_at.is_some_and(|price| price <= order.price),
+            Side::Sell => buy_at.is_some_and(|price| price >= order.price),
+        });
+        state.resting = resting;
+
+        for order in reached {
+            let remaining = order.qty - order.filled_qty;
+            self.fill(state, &order.order_id, &order.client_order_id, order.side, remaining, order.qty, order.price, true);
+            self.closed(&order.client_order_id, "Filled", order.qty);
+        }
+    }
+
+    fn reject(&self, msg: &str) -> ExchangeError {
+        ExchangeError::Rejected { exchange: self.symbol.exchange.clone(), code: PAPER_REJECT_CODE, msg: msg.to_string() }
+    }
+
+    /// What the venue answers a cancel of an order it doesn't have
+    fn order_not_found(&self) -> ExchangeError {
+        let (code, msg) = match self.symbol.exchange {
+            Exchanges::Bybit => (110001, "Order does not exist"),
+            Exchanges::Binance => (-2011, "Unknown order sent."),
+            _ => (PAPER_REJECT_CODE, "Order does not exist"),
+        };
+        ExchangeError::Rejected { exchange: self.symbol.exchange.clone(), code, msg: msg.to_string() }
+    }
+
+    fn check_symbol(&self, symbol: &Symbol) -> ExchangeResult<()> {
+        if symbol.symbol != self.symbol.symbol {
+            return Err(ExchangeError::InvalidRequest(format!("Paper venue only trades {}, not {}", self.symbol.symbol, symbol.symbol)));
+        }
+        Ok(())
+    }
+
+    fn ack(&self, order_id: &str, client_order_id: &str) -> QTSOrderAck {
+        QTSOrderAck {
+            exchange: self.symbol.exchange.clone(),
+            order_id: order_id.to_string(),
+            client_order_id: client_order_id.to_string(),
+        }
+    }
+
+    /// Book a fill against the simulated position and report it
+    #[allow(clippy::too_many_arguments)]
+    fn fill(&self, state: &mut PaperState, order_id: &str, client_order_id: &str, side: Side, qty: Qty, filled_qty: Qty, price: Price, is_maker: bool) {
+        let rate = if is_maker { self.fees.maker } else { self.fees.taker };
+        let fee = price.notional(qty) * rate;
+        state.position.on_fill(side, qty, price, fee);
+
+        println!("Paper fill {:?} {} {:?} {} @ {} fee {:.6}", self.leg, client_order_id, side, qty, price, fee);
+        let fill = QTSFill {
+            symbol: self.symbol.symbol.clone(),
+            exchange: self.symbol.exchange.clone(),
+            order_id: order_id.to_string(),
+            client_order_id: client_order_id.to_string(),
+            side,
+            price,
+            qty,
+            filled_qty,
+            fee,
+            is_maker,
+            time: chrono::Utc::now().timestamp_millis(),
+        };
+        let _ = self.executions.send(ExecutionEvent::Fill { leg: self.leg, fill });
+    }
+
+    fn closed(&self, client_order_id: &str, status: &str, filled_qty: Qty) {
+        let _ = self.executions.send(ExecutionEvent::OrderClosed {
+            leg: self.leg,
+            client_order_id: client_order_id.to_string(),
+            status: status.to_string(),
+            filled_qty,
+        });
+    }
+}
+
+#[async_trait]
+impl ExchangeRest for PaperExchange {
+
+    fn exchange(&self) -> Exchanges {
+        self.symbol.exchange.clone()
+    }
+
+    async fn get_orderbook(&self, symbol: &Symbol) -> ExchangeResult<QTSOrderBook> {
+        self.market.get_orderbook(symbol).await
+    }
+
+    async fn get_ticker(&self, symbol: &Symbol) -> ExchangeResult<QTSTicker> {
+        self.market.get_ticker(symbol).await
+    }
+
+    async fn get_positions(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSPosition>> {
+        self.check_symbol(symbol)?;
+        let state = self.state.lock().unwrap();
+
+        // Marked at the mid, or at entry with no book yet
+        let entry_price = state.position.entry_price().unwrap_or(0.0);
+        let mark = match state.top_of_book {
+            (Some(best_bid), Some(best_ask)) => (best_bid.to_f64() + best_ask.to_f64()) / 2.0,
+            _ => entry_price,
+        };
+        Ok(vec![QTSPosition {
+            symbol: self.symbol.symbol.clone(),
+            exchange: self.symbol.exchange.clone(),
+            size: state.position.size(),
+            entry_price: Price::from_f64(entry_price)?,
+            mark_price: Price::from_f64(mark)?,
+            unrealized_pnl: (mark - entry_price) * state.position.size().to_f64(),
+            time: chrono::Utc::now().timestamp_millis(),
+        }])
+    }
+
+    async fn get_balances(&self) -> ExchangeResult<Vec<QTSBalance>> {
+        let state = self.state.lock().unwrap();
+        let balance = state.position.realized_pnl() - state.position.fees();
+
+        Ok(vec![QTSBalance {
+            exchange: self.symbol.exchange.clone(),
+            coin: "USDT".to_string(),
+            wallet_balance: balance,
+            available_balance: balance,
+            unrealized_pnl: 0.0,
+        }])
+    }
+
+    async fn get_open_orders(&self, symbol: &Symbol) -> ExchangeResult<Vec<QTSOpenOrder>> {
+        self.check_symbol(symbol)?;
+        Ok(self.state.lock().unwrap().resting.clone())
+    }
+
+    async fn get_margin(&self) -> ExchangeResult<QTSMargin> {
+        let state = self.state.lock().unwrap();
+
+        // No leverage is simulated, paper positions never use margin
+        Ok(QTSMargin {
+            exchange: self.symbol.exchange.clone(),
+            margin_balance: state.position.realized_pnl() - state.position.fees(),
+            initial_margin: 0.0,
+            maintenance_margin: 0.0,
+            margin_ratio: 0.0,
+            time: chrono::Utc::now().timestamp_millis(),
+        })
+    }
+
+    async fn place_order(&self, symbol: &Symbol, order: &QTSOrderRequest) -> ExchangeResult<QTSOrderAck> {
+        self.check_symbol(symbol)?;
+        let mut state = self.state.lock().unwrap();
+
+        if state.resting.iter().any(|resting| resting.client_order_id == order.client_order_id) {
+            return Err(self.reject("Duplicate client order id"));
+        }
+        // Reduce-only must shrink the position, and is capped at its size like on the venues
+        let mut qty = order.qty;
+        if order.reduce_only {
+            let reduces = match order.side {
+                Side::Buy => state.position.size().is_negative(),
+                Side::Sell => state.position.size().is_positive(),
+            };
+            if !reduces {
+                return Err(self.reject("Reduce-only order would increase the position"));
+            }
+            qty = qty.min(state.position.size().abs());
+        }
+
+        let (best_bid, best_ask) = state.top_of_book;
+        let touch = if order.side == Side::Buy { best_ask } else { best_bid };
+        let marketable = match (order.order_type, order.price, touch) {
+            (_, _, None) => false,
+            (OrderType::Market, _, Some(_)) => true,
+            (OrderType::Limit, Some(price), Some(touch)) => if order.side == Side::Buy { price >= touch } else { price <= touch },
+            (OrderType::Limit, None, _) => return Err(ExchangeError::InvalidRequest("Limit order without a price".to_string())),
+        };
+
+        state.next_order_id += 1;
+        let order_id = format!("paper-{}", state.next_order_id);
+
+        if marketable {
+            if order.time_in_force == TimeInForce::PostOnly {
+                return Err(self.reject("Post-only order would take liquidity"));
+            }
+            let touch = touch.unwrap_or(Price::ZERO);
+            self.fill(&mut state, &order_id, &order.client_order_id, order.side, qty, qty, touch, false);
+            self.closed(&order.client_order_id, "Filled", qty);
+            return Ok(self.ack(&order_id, &order.client_order_id));
+        }
+
+        match (order.order_type, order.time_in_force, order.price) {
+            (OrderType::Market, _, _) => Err(self.reject("No liquidity for market order")),
+            // Venue accepts them, then they expire
+            (_, TimeInForce::IOC | TimeInForce::FOK, _) => {
+                self.closed(&order.client_order_id, "Cancelled", Qty::ZERO);
+                Ok(self.ack(&order_id, &order.client_order_id))
+            }
+            (_, _, Some(price)) => {
+                state.resting.push(QTSOpenOrder {
+                    symbol: self.symbol.symbol.clone(),
+                    exchange: self.symbol.exchange.clone(),
+                    order_id: order_id.clone(),
+                    client_order_id: order.client_order_id.clone(),
+                    side: order.side,
+                    price,
+                    qty,
+                    filled_qty: Qty::ZERO,
+                    status: "New".to_string(),
+                    reduce_only: order.reduce_only,
+                    time: chrono::Utc::now().timestamp_millis(),
+                });
+                Ok(self.ack(&order_id, &order.client_order_id))
+            }
+            (_, _, None) => Err(ExchangeError::InvalidRequest("Limit order without a price".to_string())),
+        }
+    }
+
+    async fn cancel_order(&self, symbol: &Symbol, client_order_id: &str) -> ExchangeResult<QTSOrderAck> {
+        self.check_symbol(symbol)?;
+        let mut state = self.state.lock().unwrap();
+
+        let Some(index) = state.resting.iter().position(|order| order.client_order_id == client_order_id) else {
+            return Err(self.order_not_found());
+        };
+        let order = state.resting.remove(index);
+        self.closed(&order.client_order_id, "Cancelled", order.filled_qty);
+        Ok(self.ack(&order.order_id, &order.client_order_id))
+    }
+
+    async fn cancel_all_orders(&self, symbol: &Symbol) -> ExchangeResult<()> {
+        self.check_symbol(symbol)?;
+        let mut state = self.state.lock().unwrap();
+
+        for order in std::mem::take(&mut state.resting) {
+            self.closed(&order.client_order_id, "Cancelled", order.filled_qty);
+        }
+        Ok(())
+    }
+}
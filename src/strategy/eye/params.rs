@@ -1,10 +1,18 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
+use thiserror::Error;
 use crate::data_structure::Symbol;
+use crate::decimal::{DECIMALS, Qty};
+use crate::enums::{Exchanges, StrategyTypes};
+use crate::exchange::bybit::bybit_rest::{BYBIT_DCP_MAX_WINDOW, BYBIT_DCP_MIN_WINDOW};
 use crate::strategy::strategy_structs::{StrategyParams, HedgeParams, QuoteParams, RunMode};
 
 /*
     Yaml Configuration Paramers For EE strategy
+
+    Loading parses the YAML, then validate() checks the values make sense together. Every
+    problem found is reported with the path of its field, so a bad config can be fixed in
+    one go instead of one error per run.
 */
 
 
@@ -20,20 +28,151 @@ pub struct EEConfig {
     pub run_mode: RunMode,
 }
 
+/// One value that parses but can't be run with
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    pub path: String,           // field as in the YAML, e.g. quote_params.clip_size
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read config: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Not valid YAML, or a field of the wrong type (e.g. an unknown hedge_mode)
+    #[error("failed to parse config: {0}")]
+    Parse(#[from] serde_yaml::Error),
+
+    #[error("invalid config: {}", .0.iter().map(ConfigProblem::to_string).collect::<Vec<_>>().join(", "))]
+    Invalid(Vec<ConfigProblem>),
+}
+
 
 
 impl EEConfig {
     /// Load configuration from YAML file
-    pub fn from_yaml_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_yaml_file(path: &str) -> Result<Self, ConfigError> {
         let content = fs::read_to_string(path)?;
-        let config: EEConfig = serde_yaml::from_str(&content)?;
-        Ok(config)
+        Self::from_yaml_str(&content)
     }
-    
+
     /// Load configuration from YAML string
-    pub fn from_yaml_str(yaml_content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_yaml_str(yaml_content: &str) -> Result<Self, ConfigError> {
         let config: EEConfig = serde_yaml::from_str(yaml_content)?;
+        config.validate()?;
         Ok(config)
     }
+
+    /// Check the values make sense on their own and together
+    ///
+    /// # Returns
+    /// * `Result<(), ConfigError>` - ConfigError::Invalid with every problem found
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut problem = |path: &str, message: &str| problems.push(ConfigProblem { path: path.to_string(), message: message.to_string() });
+
+        if !matches!(self.strategy.strategy_type, StrategyTypes::EE) {
+            problem("strategy.strategy_type", "must be EE for the Electronic Eye");
+        }
+
+        for (leg, symbol) in [("quote_instrument", &self.quote_instrument), ("hedge_instrument", &self.hedge_instrument)] {
+            if symbol.symbol.is_empty() {
+                problem(&format!("{}.symbol", leg), "must not be empty");
+            }
+            if !matches!(symbol.exchange, Exchanges::Bybit | Exchanges::Binance) {
+                problem(&format!("{}.exchange", leg), &format!("no client for {:?}, only bybit and binance", symbol.exchange));
+            }
+            if !symbol.min_tick.is_positive() {
+                problem(&format!("{}.min_tick", leg), "must be greater than 0");
+            }
+            if symbol.qty_decimal > DECIMALS {
+                problem(&format!("{}.qty_decimal", leg), &format!("must be at most {}", DECIMALS));
+            }
+        }
+        if self.quote_instrument.symbol == self.hedge_instrument.symbol && self.quote_instrument.exchange == self.hedge_instrument.exchange {
+            problem("hedge_instrument", "must not be the same instrument as quote_instrument");
+        }
+
+        let hedge = &self.hedge_params;
+        for (path, value, allow_zero) in [
+            ("hedge_params.hedge_ratio", hedge.hedge_ratio, false),
+            ("hedge_params.price_ratio", hedge.price_ratio, false),
+            ("hedge_params.hedge_delay", hedge.hedge_delay, true),
+            ("hedge_params.max_slippage", hedge.max_slippage, true),
+            ("hedge_params.hedge_offset", hedge.hedge_offset, true),
+        ] {
+            check_amount(&mut problem, path, value, allow_zero);
+        }
+
+        let quote = &self.quote_params;
+        if !quote.bid_on && !quote.ask_on {
+            problem("quote_params.ask_on", "bid_on and ask_on are both off, nothing would be quoted");
+        }
+        for (path, value, allow_zero) in [
+            ("quote_params.min_spread", quote.min_spread, true),
+            ("quote_params.vol_spread_mult", quote.vol_spread_mult, true),
+            ("quote_params.relist_interval", quote.relist_interval, true),
+            ("quote_params.max_position", quote.max_position, true),
+            ("quote_params.clip_size", quote.clip_size, false),
+            ("quote_params.clip_step_size", quote.clip_step_size, true),
+            ("quote_params.clip_interval", quote.clip_interval, true),
+            ("quote_params.margin_ratio_threshold", quote.margin_ratio_threshold, false),
+        ] {
+            check_amount(&mut problem, path, value, allow_zero);
+        }
+        for (path, value) in [("quote_params.bid_offset", quote.bid_offset), ("quote_params.ask_offset", quote.ask_offset)] {
+            if !value.is_finite() || value <= -1.0 || value >= 1.0 {
+                problem(path, "must be a fraction of the fair price between -1 and 1");
+            }
+        }
+
+        if quote.clip_size > 0.0 && Qty::from_f64(quote.clip_size).is_ok_and(|clip| self.quote_instrument.round_qty(clip).is_zero()) {
+            problem("quote_params.clip_size", &format!("rounds to nothing at qty_decimal {}", self.quote_instrument.qty_decimal));
+        }
+        if quote.quote_levels == 0 {
+            problem("quote_params.quote_levels", "must be at least 1");
+        }
+        if quote.quote_levels > 1 && quote.relist_interval >= quote.clip_interval {
+            problem("quote_params.relist_interval", "must be less than clip_interval, or relisted levels overlap");
+        }
+        if quote.poll_interval_seconds == 0 {
+            problem("quote_params.poll_interval_seconds", "must be at least 1");
+        }
+        if quote.margin_ratio_threshold > 1.0 {
+            problem("quote_params.margin_ratio_threshold", "must be at most 1, the account is liquidated at 1");
+        }
+        if quote.margin_check_interval == 0 {
+            problem("quote_params.margin_check_interval", "must be greater than 0");
+        }
+        if quote.dcp_window != 0 {
+            if self.quote_instrument.exchange != Exchanges::Bybit {
+                problem("quote_params.dcp_window", "disconnect-cancel-all is only on Bybit, must be 0");
+            } else if !(BYBIT_DCP_MIN_WINDOW..=BYBIT_DCP_MAX_WINDOW).contains(&quote.dcp_window) {
+                problem("quote_params.dcp_window", &format!("must be 0 or between {} and {} seconds", BYBIT_DCP_MIN_WINDOW, BYBIT_DCP_MAX_WINDOW));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
 }
 
+fn check_amount(problem: &mut impl FnMut(&str, &str), path: &str, value: f64, allow_zero: bool) {
+    if !value.is_finite() {
+        problem(path, "must be a number");
+    } else if allow_zero && value < 0.0 {
+        problem(path, "must not be negative");
+    } else if !allow_zero && value <= 0.0 {
+        problem(path, "must be greater than 0");
+    }
+}
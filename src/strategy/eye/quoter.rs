@@ -0,0 +1,201 @@
+use crate::data_structure::{QTSOpenOrder, Symbol};
+use crate::decimal::{Price, Qty};
+use crate::enums::Side;
+use crate::oms::new_client_order_id;
+use crate::strategy::strategy_structs::QuoteParams;
+
+/*
+    Quote ladder for the Electronic Eye
+
+    Everything is priced off the hedge instrument, fair = hedge mid * price_ratio.
+    Spreads, offsets and intervals in QuoteParams are fractions of the fair price:
+
+        inner bid = fair * (1 - bid_offset)
+        inner ask = fair * (1 + ask_offset)
+
+    widened around their midpoint until they are at least min_spread apart, then each
+    further level steps out by clip_interval and grows by clip_step_size:
+
+        level i price = inner -/+ fair * clip_interval * i
+        level i size  = clip_size + clip_step_size * i
+
+    A side stops quoting once the position reaches max_position in that direction,
+    the ladder itself is allowed on top of it. In reduce-only mode reduce_only_ladder
+    trims the ladder down to what can only bring the position back to flat.
+*/
+
+// Client order ids of the orders the quoter owns, everything else on the symbol is left alone
+pub const QUOTE_ID_PREFIX: &str = "ee-";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesiredOrder {
+    pub side: Side,
+    pub level: u32,
+    pub price: Price,
+    pub qty: Qty,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QuoteDiff {
+    pub cancels: Vec<QTSOpenOrder>,     // resting orders no longer wanted
+    pub places: Vec<DesiredOrder>,      // levels with no resting order close enough
+}
+
+/// Client order id for a ladder level, e.g. `ee-b0-<uuid>` for the inner bid
+pub fn quote_order_id(side: Side, level: u32) -> String {
+    let side = if side == Side::Buy { "b" } else { "a" };
+    new_client_order_id(&format!("{}{}{}-", QUOTE_ID_PREFIX, side, level))
+}
+
+/// Side and level a quote was placed for, None for orders the quoter does not own
+pub fn quote_level(client_order_id: &str) -> Option<(Side, u32)> {
+    let rest = client_order_id.strip_prefix(QUOTE_ID_PREFIX)?;
+    let side = match rest.chars().next()? {
+        'b' => Side::Buy,
+        'a' => Side::Sell,
+        _ => return None,
+    };
+    let (level, _) = rest[1..].split_once('-')?;
+    Some((side, level.parse().ok()?))
+}
+
+impl QuoteDiff {
+    pub fn is_empty(&self) -> bool {
+        self.cancels.is_empty() && self.places.is_empty()
+    }
+}
+
+/// Build the bid and ask ladders around a fair price
+///
+/// # Arguments
+/// * `params` - Quote parameters from the config
+/// * `symbol` - Quote instrument, for tick and lot rounding
+/// * `fair` - Fair price of the quote instrument
+/// * `position` - Current signed position on the quote instrument
+/// * `top_of_book` - Best bid and ask on the quote instrument, quotes are kept from crossing them
+///
+/// # Returns
+/// * `Vec<DesiredOrder>` - Bids from the inside out, then asks from the inside out
+pub fn build_ladder(params: &QuoteParams, symbol: &Symbol, fair: Price, position: Qty, top_of_book: (Option<Price>, Option<Price>)) -> Vec<DesiredOrder> {
+    let fair = fair.to_f64();
+    if fair <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut inner_bid = fair * (1.0 - params.bid_offset);
+    let mut inner_ask = fair * (1.0 + params.ask_offset);
+    let shortfall = fair * params.min_spread - (inner_ask - inner_bid);
+    if shortfall > 0.0 {
+        inner_bid -= shortfall / 2.0;
+        inner_ask += shortfall / 2.0;
+    }
+
+    let max_position = Qty::from_f64(params.max_position).unwrap_or(Qty::ZERO);
+    let (best_bid, best_ask) = top_of_book;
+    let mut orders = Vec::new();
+
+    if params.bid_on && position < max_position {
+        for level in 0..params.quote_levels {
+            let raw = inner_bid - fair * params.clip_interval * level as f64;
+            let Ok(mut price) = Price::from_f64(raw) else { continue };
+            price = symbol.round_bid(price);
+            // Post-only, a bid at or through the best ask would only be rejected
+            if let Some(best_ask) = best_ask {
+                price = price.min(best_ask - symbol.min_tick);
+            }
+            push_level(&mut orders, params, symbol, Side::Buy, level, price);
+        }
+    }
+
+    if params.ask_on && position > -max_position {
+        for level in 0..params.quote_levels {
+            let raw = inner_ask + fair * params.clip_interval * level as f64;
+            let Ok(mut price) = Price::from_f64(raw) else { continue };
+            price = symbol.round_ask(price);
+            if let Some(best_bid) = best_bid {
+                price = price.max(best_bid + symbol.min_tick);
+            }
+            push_level(&mut orders, params, symbol, Side::Sell, level, price);
+        }
+    }
+
+    orders
+}
+
+/// Keep only the side that reduces the position, sized so the ladder never adds up to more
+///
+/// # Arguments
+/// * `ladder` - Ladder from `build_ladder`, inner levels first
+/// * `position` - Signed position on the quote leg
+///
+/// # Returns
+/// * `Vec<DesiredOrder>` - Nothing when flat, otherwise the reducing side from the inner level
+///   out, the last level cut down to what is left of the position
+pub fn reduce_only_ladder(ladder: Vec<DesiredOrder>, position: Qty) -> Vec<DesiredOrder> {
+    let reducing = if position.is_positive() {
+        Side::Sell
+    } else if position.is_negative() {
+        Side::Buy
+    } else {
+        return Vec::new();
+    };
+
+    let mut remaining = position.abs();
+    let mut orders = Vec::new();
+    for mut order in ladder.into_iter().filter(|order| order.side == reducing) {
+        if !remaining.is_positive() {
+            break;
+        }
+        order.qty = order.qty.min(remaining);
+        remaining -= order.qty;
+        orders.push(order);
+    }
+    orders
+}
+
+fn push_level(orders: &mut Vec<DesiredOrder>, params: &QuoteParams, symbol: &Symbol, side: Side, level: u32, price: Price) {
+    let size = params.clip_size + params.clip_step_size * level as f64;
+    let Ok(qty) = Qty::from_f64(size) else { return };
+    let qty = symbol.round_qty(qty);
+
+    // Levels pushed onto the same tick by the book clamp collapse into one
+    if !price.is_positive() || !qty.is_positive() || orders.iter().any(|order| order.side == side && order.price == price) {
+        return;
+    }
+    orders.push(DesiredOrder { side, level, price, qty });
+}
+
+/// Match the desired ladder against what is resting on the venue
+///
+/// # Arguments
+/// * `desired` - Ladder from `build_ladder`
+/// * `resting` - Open orders owned by the quoter
+/// * `tolerance` - How far a resting order may be from its level before it is relisted
+///
+/// # Returns
+/// * `QuoteDiff` - Orders to cancel and levels to place, a resting order is kept when it is on
+///   the same side, within tolerance and has the level's size left
+pub fn diff_orders(desired: &[DesiredOrder], resting: &[QTSOpenOrder], tolerance: Price) -> QuoteDiff {
+    let mut kept = vec![false; resting.len()];
+    let mut places = Vec::new();
+
+    for order in desired {
+        let matched = resting.iter().enumerate().position(|(index, open)| {
+            !kept[index]
+                && open.side == order.side
+                && (open.price - order.price).abs() <= tolerance
+                && open.qty - open.filled_qty == order.qty
+        });
+        match matched {
+            Some(index) => kept[index] = true,
+            None => places.push(order.clone()),
+        }
+    }
+
+    let cancels = resting.iter().zip(kept)
+        .filter(|(_, kept)| !kept)
+        .map(|(open, _)| open.clone())
+        .collect();
+
+    QuoteDiff { cancels, places }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use crate::strategy::eye::params::EEConfig;
+
+/*
+    Hot reload of the EE config
+
+    The watcher re-reads the YAML file every CONFIG_WATCH_INTERVAL_MS and sends each version
+    that parses and validates into the run loop's config channel, anything else is logged
+    with its problems and skipped until the file is saved again.
+
+    process_config_updates diffs it against the running config field by field. Spreads,
+    offsets, sizes, flags and intervals are applied in place, anything under RESTART_FIELDS
+    is wired into clients and feeds at startup, so a version changing one of those is
+    rejected as a whole and the running config is kept.
+*/
+
+pub const CONFIG_WATCH_INTERVAL_MS: u64 = 500;
+
+// Field paths that only take effect on a restart, a path matches itself and everything under it
+pub const RESTART_FIELDS: &[&str] = &["strategy", "quote_instrument", "hedge_instrument", "run_mode.debug", "quote_params.dcp_window"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,       // e.g. quote_params.min_spread
+    pub old: String,
+    pub new: String,
+}
+
+impl ConfigChange {
+    pub fn needs_restart(&self) -> bool {
+        RESTART_FIELDS.iter().any(|field| self.path == *field || self.path.starts_with(&format!("{}.", field)))
+    }
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+    }
+}
+
+/// Every field that differs between two configs
+///
+/// # Arguments
+/// * `old` - Running config
+/// * `new` - Config read from the file
+///
+/// # Returns
+/// * `Vec<ConfigChange>` - One entry per changed leaf field, ordered by path
+pub fn config_diff(old: &EEConfig, new: &EEConfig) -> Vec<ConfigChange> {
+    let mut old_fields = Vec::new();
+    let mut new_fields = Vec::new();
+    flatten("", &serde_json::to_value(old).unwrap_or(Value::Null), &mut old_fields);
+    flatten("", &serde_json::to_value(new).unwrap_or(Value::Null), &mut new_fields);
+
+    // Same struct on both sides, so the same paths in the same order
+    old_fields.into_iter().zip(new_fields)
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((path, old), (_, new))| ConfigChange { path, old: leaf(&old), new: leaf(&new) })
+        .collect()
+}
+
+fn flatten(prefix: &str, value: &Value, fields: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&path, value, fields);
+            }
+        }
+        _ => fields.push((prefix.to_string(), value.clone())),
+    }
+}
+
+fn leaf(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Watch the config file and send every new valid version into the run loop
+///
+/// # Arguments
+/// * `path` - YAML file the config was loaded from
+/// * `config_tx` - Config channel of the run loop
+pub fn spawn_config_watcher(path: String, config_tx: mpsc::UnboundedSender<EEConfig>) {
+    tokio::spawn(async move {
+        // Whatever is on disk now is what the strategy was started with
+        let mut last_content = tokio::fs::read_to_string(&path).await.ok();
+        let mut interval = tokio::time::interval(Duration::from_millis(CONFIG_WATCH_INTERVAL_MS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    println!("Failed to read config {}: {}", path, e);
+                    continue;
+                }
+            };
+            if last_content.as_ref() == Some(&content) {
+                continue;
+            }
+            last_content = Some(content.clone());
+
+            match EEConfig::from_yaml_str(&content) {
+                Ok(config) => {
+                    println!("Config {} changed, reloading....", path);
+                    if config_tx.send(config).is_err() {
+                        // Run loop is gone
+                        return;
+                    }
+                }
+                Err(e) => println!("Config {} changed but is invalid, keeping the running config: {}", path, e),
+            }
+        }
+    });
+}
@@ -0,0 +1,93 @@
+use crate::strategy::eye::events::Leg;
+
+/*
+    Margin monitor for the Electronic Eye
+
+    With RunMode.risk_management_mode on, the margin ratio of both venue accounts is read
+    every margin_check_interval ms:
+
+        margin ratio = maintenance margin / margin balance      (1 is liquidation)
+
+    Once either account is at or over margin_ratio_threshold the quoter goes reduce-only,
+    it only quotes the side that brings the quote position back to flat, never for more
+    than the position, and flags the orders reduce-only on the venue. Hedges are left to
+    run, they offset quote fills and can only bring the net delta down.
+
+    Quoting goes back to normal when the last reading on both accounts is under the
+    threshold again. A failed reading keeps the previous one.
+*/
+
+#[derive(Debug, Clone)]
+pub struct MarginMonitor {
+    threshold: f64,
+    quote_margin_ratio: Option<f64>,
+    hedge_margin_ratio: Option<f64>,
+    reduce_only: bool,
+}
+
+impl MarginMonitor {
+    pub fn new(threshold: f64) -> Self {
+        MarginMonitor {
+            threshold,
+            quote_margin_ratio: None,
+            hedge_margin_ratio: None,
+            reduce_only: false,
+        }
+    }
+
+    /// Whether the quoter may only reduce the position
+    pub fn reduce_only(&self) -> bool {
+        self.reduce_only
+    }
+
+    pub fn margin_ratio(&self, leg: Leg) -> Option<f64> {
+        match leg {
+            Leg::Quote => self.quote_margin_ratio,
+            Leg::Hedge => self.hedge_margin_ratio,
+        }
+    }
+
+    /// Take a new threshold, the last readings are judged against it straight away
+    ///
+    /// # Returns
+    /// * `bool` - True when reduce-only was switched on or off
+    pub fn set_threshold(&mut self, threshold: f64) -> bool {
+        self.threshold = threshold;
+        self.update()
+    }
+
+    /// Record a margin ratio read from a leg's account
+    ///
+    /// # Arguments
+    /// * `leg` - Leg whose venue account was read
+    /// * `margin_ratio` - Maintenance margin over margin balance
+    ///
+    /// # Returns
+    /// * `bool` - True when reduce-only was switched on or off
+    pub fn on_margin(&mut self, leg: Leg, margin_ratio: f64) -> bool {
+        match leg {
+            Leg::Quote => self.quote_margin_ratio = Some(margin_ratio),
+            Leg::Hedge => self.hedge_margin_ratio = Some(margin_ratio),
+        }
+        self.update()
+    }
+
+    /// Forget the readings and quote normally, when risk management is switched off
+    ///
+    /// # Returns
+    /// * `bool` - True when reduce-only was on
+    pub fn clear(&mut self) -> bool {
+        self.quote_margin_ratio = None;
+        self.hedge_margin_ratio = None;
+        self.update()
+    }
+
+    fn update(&mut self) -> bool {
+        let breached = [self.quote_margin_ratio, self.hedge_margin_ratio].into_iter()
+            .flatten()
+            .any(|margin_ratio| margin_ratio >= self.threshold);
+        let changed = breached != self.reduce_only;
+        self.reduce_only = breached;
+        changed
+    }
+}
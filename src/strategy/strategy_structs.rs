@@ -3,7 +3,7 @@
 
 
 use serde::{Deserialize, Serialize};
-use crate::enums::StrategyTypes;
+use crate::enums::{HedgeMode, StrategyTypes};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StrategyParams{
@@ -15,7 +15,7 @@ pub struct StrategyParams{
 pub struct HedgeParams{
     pub hedge_ratio: f64,        // in usd dollar terms, for every $1 quote, how much should you short
     pub price_ratio: f64,        // price ratio parameter
-    pub hedge_mode: u32,          // 0=no hedging, 1=active hedging, 2=submits limit order but doesn't do anything
+    pub hedge_mode: HedgeMode,   // 0=no hedging, 1=active hedging, 2=submits limit order but doesn't do anything
     pub hedge_delay: f64,        // in seconds
     pub max_slippage: f64,       // max allowable cross
     pub hedge_offset: f64,       // used when hedge_mode=2
@@ -36,16 +36,20 @@ pub struct QuoteParams{
     pub clip_step_size: f64,              // step size for clips
     pub clip_interval: f64,               // on the last level fill, target 1.5ATR or more
     pub poll_interval_seconds: u32,       // polling interval in seconds
-    pub order_refresh_rate_ms: u32,       // how long before we can update order
-    pub fill_refresh_delay: u32,          // how long to pause after a fill before we re-submit
+    pub order_refresh_rate_ms: u32,       // how long before we can update order, per order
+    pub fill_refresh_delay: u32,          // in seconds, how long to pause a level after a fill before we re-submit
     pub margin_ratio_threshold: f64,     // when margin ratio exceeds this level, we will actively quote to reduce
     pub margin_check_interval: u32,       // in milliseconds, 1 min = 60000ms
+    #[serde(default)]
+    pub dcp_window: u32,                  // in seconds, Bybit cancels our quotes once the private stream has been gone this long, 0 is off
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RunMode{
-    pub debug: bool,                      // if this is true, we will not place any orders in market
-    pub risk_management_mode: bool,      // if we have this on, we actively monitor margins and cannot increase position
+    pub debug: bool,                      // if this is true, we will not place any orders in market, they are paper traded
+    pub risk_management_mode: bool,      // if we have this on, we actively monitor margins and cannot increase position once over margin_ratio_threshold
+    #[serde(default)]
+    pub flatten_on_shutdown: bool,       // if this is true, the residual hedge delta is sent as one IOC when shutting down
 }
 
 
@@ -0,0 +1,167 @@
+mod common;
+mod mock_venue;
+
+use common::{price, qty};
+use mock_venue::{venue, MockOrderStatus, MOCK_API_KEY, MOCK_API_SECRET};
+use rust_qts::data_structure::QTSOrderRequest;
+use rust_qts::enums::{Environment, Exchanges, OrderType, Side, TimeInForce};
+use rust_qts::exchange::base_rest::RestClientBuilder;
+use rust_qts::exchange::error::ExchangeError;
+
+#[tokio::test]
+async fn market_data_through_the_common_interface() {
+    let venue = venue(Exchanges::Binance).await;
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    let book = client.get_orderbook(&symbol).await.unwrap();
+    assert_eq!(book.bids, vec![(price("65000"), qty("1")), (price("64999.9"), qty("2"))]);
+    assert_eq!(book.asks[0], (price("65000.5"), qty("1")));
+
+    let ticker = client.get_ticker(&symbol).await.unwrap();
+    assert_eq!(ticker.last_price, price("65000.25"));
+
+    let all = venue.binance_client().get_tickers(None).await.unwrap();
+    assert_eq!(all.list.len(), 1);
+}
+
+#[tokio::test]
+async fn signed_reads_verify_against_the_venue() {
+    let venue = venue(Exchanges::Binance).await;
+    venue.set_position("BTCUSDT", "-1.5", "66000");
+    venue.set_balance("USDT", "2500.5");
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    let positions = client.get_positions(&symbol).await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].size, qty("-1.5"));
+    assert_eq!(positions[0].entry_price, price("66000"));
+    assert_eq!(positions[0].mark_price, price("65000.25"));
+
+    let balances = client.get_balances().await.unwrap();
+    assert_eq!(balances[0].wallet_balance, 2500.5);
+
+    assert!(client.get_open_orders(&symbol).await.unwrap().is_empty());
+
+    let request = &venue.requests_to("/fapi/v3/positionRisk")[0];
+    assert!(request.query.contains("&signature="));
+    assert!(request.header("x-mbx-apikey").is_some());
+}
+
+#[tokio::test]
+async fn margin_ratio_sums_the_positions_over_the_margin_balance() {
+    let venue = venue(Exchanges::Binance).await;
+    assert_eq!(venue.rest_client().get_margin().await.unwrap().margin_ratio, 0.0);
+
+    venue.set_position("BTCUSDT", "0.5", "65000");
+    venue.set_book("ETHUSDT", &[("3000", "1")], &[("3000.5", "1")]);
+    venue.set_position("ETHUSDT", "-2", "3000");
+    venue.set_balance("USDT", "4000");
+    venue.set_margin(800.0, 200.0);
+
+    let margin = venue.rest_client().get_margin().await.unwrap();
+    assert_eq!(margin.margin_balance, 4000.0);
+    assert!((margin.maintenance_margin - 200.0).abs() < 1e-9);
+    assert!((margin.initial_margin - 800.0).abs() < 1e-9);
+    assert!((margin.margin_ratio - 0.05).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn flat_symbols_are_not_reported() {
+    let venue = venue(Exchanges::Binance).await;
+    let positions = venue.rest_client().get_positions(&venue.symbol("BTCUSDT")).await.unwrap();
+    assert!(positions.is_empty());
+}
+
+fn order(side: Side, order_type: OrderType, size: &str, limit_price: Option<&str>, tif: TimeInForce, client_order_id: &str) -> QTSOrderRequest {
+    QTSOrderRequest {
+        side,
+        order_type,
+        price: limit_price.map(price),
+        qty: qty(size),
+        time_in_force: tif,
+        client_order_id: client_order_id.to_string(),
+        reduce_only: false,
+    }
+}
+
+#[tokio::test]
+async fn order_entry_through_the_common_interface() {
+    let venue = venue(Exchanges::Binance).await;
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    let ack = client.place_order(&symbol, &order(Side::Buy, OrderType::Limit, "0.5", Some("64990"), TimeInForce::PostOnly, "bid-1")).await.unwrap();
+    assert_eq!(ack.client_order_id, "bid-1");
+    let request = &venue.requests_to("/fapi/v1/order")[0];
+    assert_eq!(request.method, "POST");
+    assert!(request.query.contains("timeInForce=GTX"));
+
+    client.place_order(&symbol, &order(Side::Sell, OrderType::Limit, "0.5", Some("65010"), TimeInForce::GTC, "ask-1")).await.unwrap();
+    assert_eq!(client.get_open_orders(&symbol).await.unwrap().len(), 2);
+
+    let ack = client.cancel_order(&symbol, "bid-1").await.unwrap();
+    assert_eq!(ack.order_id, venue.order("bid-1").unwrap().order_id.to_string());
+    assert_eq!(venue.order("bid-1").unwrap().status, MockOrderStatus::Cancelled);
+
+    // Market orders go without a price or timeInForce
+    client.place_order(&symbol, &order(Side::Sell, OrderType::Market, "0.4", None, TimeInForce::IOC, "mkt-1")).await.unwrap();
+    assert_eq!(venue.order("mkt-1").unwrap().status, MockOrderStatus::Filled);
+    assert_eq!(venue.position("BTCUSDT").size, qty("-0.4"));
+
+    client.cancel_all_orders(&symbol).await.unwrap();
+    assert!(venue.open_orders("BTCUSDT").is_empty());
+}
+
+#[tokio::test]
+async fn order_rejects_map_onto_the_taxonomy() {
+    let venue = venue(Exchanges::Binance).await;
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    // GTX that would take is rejected outright on Binance
+    let error = client.place_order(&symbol, &order(Side::Buy, OrderType::Limit, "0.1", Some("65000.5"), TimeInForce::PostOnly, "po-1")).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Rejected { code: -5022, .. }), "{:?}", error);
+
+    let error = client.cancel_order(&symbol, "never-placed").await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Rejected { code: -2011, .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn venue_errors_map_onto_the_taxonomy() {
+    let venue = venue(Exchanges::Binance).await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    let client = RestClientBuilder::new("someone-else".to_string(), MOCK_API_SECRET.to_string())
+        .environment(Environment::Custom(venue.rest_url()))
+        .build(&Exchanges::Binance)
+        .unwrap();
+    let error = client.get_balances().await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Auth { code: -2015, .. }), "{:?}", error);
+
+    let client = RestClientBuilder::new(MOCK_API_KEY.to_string(), "wrong".to_string())
+        .environment(Environment::Custom(venue.rest_url()))
+        .build(&Exchanges::Binance)
+        .unwrap();
+    let error = client.get_balances().await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Auth { code: -1022, .. }), "{:?}", error);
+
+    // Venue clock behind ours, our timestamp looks like it is from the future
+    venue.set_clock_offset_ms(-10_000);
+    let error = venue.rest_client().get_positions(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Timestamp { code: -1021, .. }), "{:?}", error);
+    venue.set_clock_offset_ms(0);
+
+    venue.rate_limit_next(1_500);
+    let error = venue.rest_client().get_orderbook(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::RateLimited { code: -1003, retry_after_ms: Some(2_000), .. }), "{:?}", error);
+
+    venue.fail_next(-1121, "Invalid symbol.");
+    let error = venue.rest_client().get_ticker(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Rejected { code: -1121, .. }), "{:?}", error);
+
+    venue.fail_next_http(503, "Service Unavailable");
+    let error = venue.rest_client().get_ticker(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Http { status: 503, .. }), "{:?}", error);
+}
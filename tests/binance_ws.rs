@@ -0,0 +1,67 @@
+mod mock_venue;
+
+use mock_venue::{next_matching, venue, MockVenue};
+use rust_qts::enums::Exchanges;
+use rust_qts::exchange::binance::binance_ws::{BinanceWebSocketClient, BinanceWebSocketConfig, BinanceWebSocketMessage};
+use rust_qts::exchange::binance::response::{BinanceDepthUpdateStatus, BinanceLocalOrderbook, BinanceWebSocketDepthUpdate};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+async fn connect(venue: &MockVenue) -> (BinanceWebSocketClient, UnboundedReceiver<BinanceWebSocketMessage>) {
+    let mut client = BinanceWebSocketClient::new(BinanceWebSocketConfig {
+        base_url: Some(venue.ws_url()),
+        ..Default::default()
+    });
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+    client.subscribe_depth("BTCUSDT").await.unwrap();
+
+    let ack = next_matching(&mut receiver, |message| matches!(message, BinanceWebSocketMessage::Subscription(_))).await;
+    assert!(matches!(ack, BinanceWebSocketMessage::Subscription(ref ack) if ack.result.is_none() && ack.id == 1));
+    (client, receiver)
+}
+
+async fn next_depth(receiver: &mut UnboundedReceiver<BinanceWebSocketMessage>) -> BinanceWebSocketDepthUpdate {
+    match next_matching(receiver, |message| matches!(message, BinanceWebSocketMessage::DepthUpdate(_))).await {
+        BinanceWebSocketMessage::DepthUpdate(update) => update,
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn buffered_diffs_sync_with_the_rest_snapshot() {
+    let venue = venue(Exchanges::Binance).await;
+    let (_client, mut receiver) = connect(&venue).await;
+    let mut book = BinanceLocalOrderbook::new("BTCUSDT".to_string(), venue.symbol("BTCUSDT").min_tick).unwrap();
+
+    venue.set_book("BTCUSDT", &[("65000", "1.5"), ("64999.9", "2")], &[("65000.5", "1"), ("65001", "2")]);
+    assert_eq!(book.apply_update(next_depth(&mut receiver).await), BinanceDepthUpdateStatus::Buffered);
+
+    let snapshot = venue.binance_client().get_orderbook("BTCUSDT", Some(100)).await.unwrap();
+    assert_eq!(book.apply_snapshot(&snapshot), BinanceDepthUpdateStatus::Applied);
+
+    venue.set_book("BTCUSDT", &[("65000.1", "1")], &[("65000.5", "1")]);
+    assert_eq!(book.apply_update(next_depth(&mut receiver).await), BinanceDepthUpdateStatus::Applied);
+
+    let (best_bid, best_ask) = venue.top_of_book("BTCUSDT");
+    assert_eq!(book.book.best_bid().map(|level| level.price), best_bid);
+    assert_eq!(book.book.best_ask().map(|level| level.price), best_ask);
+    assert_eq!(book.book.depth(), (1, 1));
+}
+
+#[tokio::test]
+async fn broken_pu_chain_goes_out_of_sync() {
+    let venue = venue(Exchanges::Binance).await;
+    let (_client, mut receiver) = connect(&venue).await;
+    let mut book = BinanceLocalOrderbook::new("BTCUSDT".to_string(), venue.symbol("BTCUSDT").min_tick).unwrap();
+
+    venue.set_book("BTCUSDT", &[("65000", "2")], &[("65000.5", "1")]);
+    book.apply_update(next_depth(&mut receiver).await);
+    let snapshot = venue.binance_client().get_orderbook("BTCUSDT", None).await.unwrap();
+    book.apply_snapshot(&snapshot);
+
+    venue.skip_book_updates(1);
+    venue.set_book("BTCUSDT", &[("65000", "3")], &[("65000.5", "1")]);
+    venue.set_book("BTCUSDT", &[("65000", "4")], &[("65000.5", "1")]);
+
+    assert_eq!(book.apply_update(next_depth(&mut receiver).await), BinanceDepthUpdateStatus::OutOfSync);
+    assert!(book.needs_snapshot());
+}
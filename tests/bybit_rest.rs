@@ -0,0 +1,354 @@
+mod common;
+mod mock_venue;
+
+use common::{price, qty};
+use mock_venue::{venue, MockOrderStatus, MOCK_API_KEY};
+use rust_qts::data_structure::QTSOrderRequest;
+use rust_qts::enums::{Environment, Exchanges, OrderType, Side, TimeInForce};
+use rust_qts::exchange::base_rest::RestClientBuilder;
+use rust_qts::exchange::bybit::response::{
+    BybitAmendOrderRequest, BybitBatchCancelItem, BybitBatchPlaceItem, BybitBatchRequest, BybitCancelAllRequest,
+    BybitCancelOrderRequest, BybitDcpRequest, BybitPlaceOrderRequest,
+};
+use rust_qts::exchange::error::ExchangeError;
+
+fn limit(side: Side, size: &str, limit_price: &str, link_id: &str, tif: TimeInForce) -> BybitPlaceOrderRequest {
+    BybitPlaceOrderRequest {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        orderType: OrderType::Limit,
+        qty: qty(size),
+        price: Some(price(limit_price)),
+        timeInForce: Some(tif),
+        orderLinkId: Some(link_id.to_string()),
+        reduceOnly: None,
+        positionIdx: None,
+    }
+}
+
+#[tokio::test]
+async fn market_data_through_the_common_interface() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    let book = client.get_orderbook(&symbol).await.unwrap();
+    assert_eq!(book.bids[0], (price("65000"), qty("1")));
+    assert_eq!(book.asks[0], (price("65000.5"), qty("1")));
+    assert_eq!(book.bids.len(), 2);
+
+    let ticker = client.get_ticker(&symbol).await.unwrap();
+    assert_eq!(ticker.bid_price, Some(price("65000")));
+    assert_eq!(ticker.ask_price, Some(price("65000.5")));
+    assert_eq!(ticker.mark_price, Some(price("65000.25")));
+}
+
+#[tokio::test]
+async fn signed_reads_verify_against_the_venue() {
+    let venue = venue(Exchanges::Bybit).await;
+    venue.set_position("BTCUSDT", "-0.25", "64000");
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    let positions = client.get_positions(&symbol).await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].size, qty("-0.25"));
+    assert_eq!(positions[0].entry_price, price("64000"));
+
+    let balances = client.get_balances().await.unwrap();
+    assert_eq!(balances[0].coin, "USDT");
+    assert_eq!(balances[0].wallet_balance, 100000.0);
+
+    assert!(client.get_open_orders(&symbol).await.unwrap().is_empty());
+    venue.bybit_client().get_account_type().await.unwrap();
+
+    let request = &venue.requests_to("/v5/position/list")[0];
+    assert_eq!(request.query, "category=linear&symbol=BTCUSDT");
+    assert!(request.header("x-bapi-sign").is_some());
+}
+
+#[tokio::test]
+async fn margin_ratio_is_the_account_maintenance_rate() {
+    let venue = venue(Exchanges::Bybit).await;
+    venue.set_margin(5000.0, 2500.0);
+
+    let margin = venue.rest_client().get_margin().await.unwrap();
+    assert_eq!(margin.margin_balance, 100000.0);
+    assert_eq!((margin.initial_margin, margin.maintenance_margin), (5000.0, 2500.0));
+    assert_eq!(margin.margin_ratio, 0.025);
+    assert_eq!(venue.requests_to("/v5/account/wallet-balance")[0].query, "accountType=UNIFIED");
+}
+
+#[tokio::test]
+async fn taker_order_sweeps_the_book_and_rests_the_remainder() {
+    let venue = venue(Exchanges::Bybit).await;
+    venue.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "1"), ("65001", "2"), ("65002", "5")]);
+    let client = venue.bybit_client();
+
+    // Takes 1 @ 65000.5 and 2 @ 65001, the last 1 rests at the limit
+    let response = client.place_order(&limit(Side::Buy, "4", "65001", "take-1", TimeInForce::GTC)).await.unwrap();
+    assert_eq!(response.result.orderLinkId, "take-1");
+
+    let order = venue.order("take-1").unwrap();
+    assert_eq!(order.status, MockOrderStatus::PartiallyFilled);
+    assert_eq!(order.filled_qty, qty("3"));
+    assert_eq!(order.avg_price(), price("65000.83333333"));
+    assert_eq!(venue.position("BTCUSDT").size, qty("3"));
+    assert_eq!(venue.top_of_book("BTCUSDT").1, Some(price("65002")));
+
+    let open = venue.rest_client().get_open_orders(&venue.symbol("BTCUSDT")).await.unwrap();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].client_order_id, "take-1");
+    assert_eq!(open[0].filled_qty, qty("3"));
+    assert_eq!(open[0].status, "PartiallyFilled");
+}
+
+#[tokio::test]
+async fn open_orders_are_read_across_pages() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+    for i in 0..120 {
+        client.place_order(&limit(Side::Buy, "1", "60000", &format!("rest-{}", i), TimeInForce::GTC)).await.unwrap();
+    }
+
+    let open = venue.rest_client().get_open_orders(&venue.symbol("BTCUSDT")).await.unwrap();
+    assert_eq!(open.len(), 120);
+    assert_eq!(open[0].client_order_id, "rest-119");
+    assert_eq!(open[119].client_order_id, "rest-0");
+
+    // Full pages of 50, each after the last page's cursor
+    let pages: Vec<_> = venue.requests_to("/v5/order/realtime").into_iter().map(|request| request.query).collect();
+    assert_eq!(pages.len(), 3);
+    assert!(pages.iter().all(|query| query.contains("limit=50")), "{:?}", pages);
+    assert!(!pages[0].contains("cursor=") && pages[1].contains("cursor=50") && pages[2].contains("cursor=100"), "{:?}", pages);
+}
+
+#[tokio::test]
+async fn repeated_open_orders_cursor_is_an_error() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+    for i in 0..120 {
+        client.place_order(&limit(Side::Buy, "1", "60000", &format!("rest-{}", i), TimeInForce::GTC)).await.unwrap();
+    }
+
+    // Half a list would read as orders gone
+    venue.stick_cursor();
+    let error = venue.rest_client().get_open_orders(&venue.symbol("BTCUSDT")).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Decode(_)), "{:?}", error);
+    assert_eq!(venue.requests_to("/v5/order/realtime").len(), 2);
+}
+
+#[tokio::test]
+async fn post_only_that_would_cross_is_cancelled() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+
+    // Accepted, then cancelled by the venue instead of taking liquidity
+    client.place_order(&limit(Side::Buy, "1", "65000.5", "po-cross", TimeInForce::PostOnly)).await.unwrap();
+    let order = venue.order("po-cross").unwrap();
+    assert_eq!(order.status, MockOrderStatus::Cancelled);
+    assert_eq!(order.reject_reason, "EC_PostOnlyWillTakeLiquidity");
+    assert!(venue.position("BTCUSDT").size.is_zero());
+
+    client.place_order(&limit(Side::Buy, "1", "65000.1", "po-rest", TimeInForce::PostOnly)).await.unwrap();
+    assert_eq!(venue.order("po-rest").unwrap().status, MockOrderStatus::New);
+}
+
+#[tokio::test]
+async fn resting_order_fills_when_the_book_trades_through() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+    client.place_order(&limit(Side::Sell, "0.5", "65001.5", "ask-1", TimeInForce::PostOnly)).await.unwrap();
+
+    venue.set_book("BTCUSDT", &[("65001.5", "3")], &[("65002", "5")]);
+
+    let order = venue.order("ask-1").unwrap();
+    assert_eq!(order.status, MockOrderStatus::Filled);
+    assert_eq!(order.avg_price(), price("65001.5"));
+    assert_eq!(venue.position("BTCUSDT").size, qty("-0.5"));
+    assert_eq!(venue.position("BTCUSDT").entry_price, price("65001.5"));
+}
+
+#[tokio::test]
+async fn amend_and_cancel_by_client_id() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+    client.place_order(&limit(Side::Buy, "1", "64990", "bid-1", TimeInForce::GTC)).await.unwrap();
+
+    client.amend_order(&BybitAmendOrderRequest {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        orderId: None,
+        orderLinkId: Some("bid-1".to_string()),
+        qty: Some(qty("2")),
+        price: Some(price("64995")),
+    }).await.unwrap();
+    let order = venue.order("bid-1").unwrap();
+    assert_eq!((order.qty, order.price), (qty("2"), Some(price("64995"))));
+
+    client.cancel_order(&BybitCancelOrderRequest {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        orderId: None,
+        orderLinkId: Some("bid-1".to_string()),
+    }).await.unwrap();
+    assert_eq!(venue.order("bid-1").unwrap().status, MockOrderStatus::Cancelled);
+
+    // Cancelling twice is a venue reject
+    let error = client.cancel_order(&BybitCancelOrderRequest {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        orderId: None,
+        orderLinkId: Some("bid-1".to_string()),
+    }).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Rejected { code: 110001, .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn batch_orders_report_each_outcome() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+
+    let item = |link_id: &str, limit_price: &str| BybitBatchPlaceItem {
+        symbol: "BTCUSDT".to_string(),
+        side: Side::Buy,
+        orderType: OrderType::Limit,
+        qty: qty("0.1"),
+        price: Some(price(limit_price)),
+        timeInForce: Some(TimeInForce::PostOnly),
+        orderLinkId: Some(link_id.to_string()),
+        reduceOnly: None,
+        positionIdx: None,
+    };
+    let response = client.place_batch_orders(&BybitBatchRequest {
+        category: "linear".to_string(),
+        request: vec![item("b-1", "64990"), item("b-2", "64980"), item("b-1", "64970")],
+    }).await.unwrap();
+
+    let failures = response.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].code, 110072);
+    assert_eq!(venue.open_orders("BTCUSDT").len(), 2);
+
+    let response = client.cancel_batch_orders(&BybitBatchRequest {
+        category: "linear".to_string(),
+        request: vec![BybitBatchCancelItem { symbol: "BTCUSDT".to_string(), orderId: None, orderLinkId: Some("b-2".to_string()) }],
+    }).await.unwrap();
+    assert!(response.failures().is_empty());
+
+    client.cancel_all_orders(&BybitCancelAllRequest {
+        category: "linear".to_string(),
+        symbol: Some("BTCUSDT".to_string()),
+        baseCoin: None,
+        settleCoin: None,
+    }).await.unwrap();
+    assert!(venue.open_orders("BTCUSDT").is_empty());
+}
+
+#[tokio::test]
+async fn common_interface_batches_up_to_the_venue_limit() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.rest_client();
+    let symbol = venue.symbol("BTCUSDT");
+
+    // 24 bids and a repeated id, linear takes 20 orders a batch
+    let orders: Vec<_> = (0..25).map(|index| QTSOrderRequest {
+        side: Side::Buy,
+        order_type: OrderType::Limit,
+        price: Some(price("64900") - price(&index.to_string())),
+        qty: qty("0.1"),
+        time_in_force: TimeInForce::PostOnly,
+        client_order_id: format!("q-{}", index % 24),
+        reduce_only: false,
+    }).collect();
+    let results = client.place_orders(&symbol, &orders).await;
+    assert_eq!(venue.batch_sizes("/v5/order/create-batch"), vec![20, 5]);
+    assert!(results[..24].iter().all(Result::is_ok));
+    assert_eq!(results[24].as_ref().unwrap_err().code(), Some(110072));
+    assert_eq!(venue.open_orders("BTCUSDT").len(), 24);
+
+    let results = client.cancel_orders(&symbol, &["q-1".to_string(), "missing".to_string()]).await;
+    assert_eq!(results[0].as_ref().unwrap().client_order_id, "q-1");
+    assert!(results[1].as_ref().unwrap_err().is_order_not_found());
+    assert_eq!(venue.open_orders("BTCUSDT").len(), 23);
+}
+
+#[tokio::test]
+async fn reduce_only_cannot_open_a_position() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+
+    let mut request = limit(Side::Sell, "1", "64000", "ro-1", TimeInForce::IOC);
+    request.reduceOnly = Some(true);
+    let error = client.place_order(&request).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Rejected { code: 110017, .. }), "{:?}", error);
+
+    // Capped at the position size
+    venue.set_position("BTCUSDT", "0.4", "64000");
+    request.orderLinkId = Some("ro-2".to_string());
+    client.place_order(&request).await.unwrap();
+    assert_eq!(venue.order("ro-2").unwrap().filled_qty, qty("0.4"));
+    assert!(venue.position("BTCUSDT").size.is_zero());
+}
+
+#[tokio::test]
+async fn venue_errors_map_onto_the_taxonomy() {
+    let venue = venue(Exchanges::Bybit).await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    // Wrong secret, the signature check fails on the venue
+    let client = RestClientBuilder::new(MOCK_API_KEY.to_string(), "wrong".to_string())
+        .environment(Environment::Custom(venue.rest_url()))
+        .build(&Exchanges::Bybit)
+        .unwrap();
+    let error = client.get_positions(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Auth { code: 10004, .. }), "{:?}", error);
+
+    // Venue clock a minute ahead of ours
+    venue.set_clock_offset_ms(60_000);
+    let error = venue.rest_client().get_positions(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Timestamp { code: 10002, .. }), "{:?}", error);
+    assert!(error.is_retryable());
+    venue.set_clock_offset_ms(0);
+
+    venue.rate_limit_next(2_000);
+    let error = venue.rest_client().get_orderbook(&symbol).await.unwrap_err();
+    match error {
+        ExchangeError::RateLimited { code: 10006, retry_after_ms: Some(retry_after_ms), .. } => assert!(retry_after_ms <= 2_000),
+        other => panic!("expected rate limit, got {:?}", other),
+    }
+
+    venue.fail_next(110007, "ab not enough for new order");
+    let error = venue.rest_client().get_positions(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Rejected { code: 110007, .. }), "{:?}", error);
+    assert!(!error.is_retryable());
+
+    venue.fail_next_http(502, "<html>Bad Gateway</html>");
+    let error = venue.rest_client().get_ticker(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Http { status: 502, .. }), "{:?}", error);
+    assert!(error.is_retryable());
+
+    // Went through, the body just isn't JSON
+    venue.fail_next_http(200, "<html>maintenance</html>");
+    let error = venue.rest_client().get_ticker(&symbol).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::Decode(_)), "{:?}", error);
+    assert!(!error.is_retryable());
+}
+
+#[tokio::test]
+async fn disconnect_cancel_all_window_is_set_and_checked() {
+    let venue = venue(Exchanges::Bybit).await;
+    let client = venue.bybit_client();
+
+    let request = BybitDcpRequest { product: Some("DERIVATIVES".to_string()), timeWindow: 10 };
+    assert_eq!(client.set_dcp(&request).await.unwrap().retCode, 0);
+    assert_eq!(venue.dcp(), Some(("DERIVATIVES".to_string(), 10)));
+    let info = client.get_account_type().await.unwrap();
+    assert_eq!((info.result.dcpStatus.as_str(), info.result.timeWindow), ("ON", 10));
+
+    // Outside what Bybit accepts, never sent
+    let error = client.set_dcp(&BybitDcpRequest { product: None, timeWindow: 2 }).await.unwrap_err();
+    assert!(matches!(error, ExchangeError::InvalidRequest(_)), "{:?}", error);
+    assert_eq!(venue.requests_to("/v5/order/disconnected-cancel-all").len(), 1);
+}
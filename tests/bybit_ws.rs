@@ -0,0 +1,240 @@
+mod common;
+mod mock_venue;
+
+use common::{price, qty};
+use mock_venue::{next_matching, venue, MockVenue, MOCK_API_KEY, MOCK_API_SECRET, WAIT};
+use rust_qts::decimal::Price;
+use rust_qts::enums::{Exchanges, OrderType, Side, TimeInForce};
+use rust_qts::exchange::bybit::bybit_ws::{
+    BybitDcpProduct, BybitPrivateTopic, BybitWebSocketClient, BybitWebSocketConfig, BybitWebSocketMessage,
+};
+use rust_qts::exchange::bybit::response::{BybitDcpRequest, BybitPlaceOrderRequest, LocalOrderbook, OrderbookSyncError};
+use std::time::Duration;
+
+fn config(venue: &MockVenue) -> BybitWebSocketConfig {
+    BybitWebSocketConfig {
+        base_url: Some(venue.ws_url()),
+        reconnect_delay_ms: 50,
+        ..Default::default()
+    }
+}
+
+fn is_snapshot(message: &BybitWebSocketMessage) -> bool {
+    matches!(message, BybitWebSocketMessage::Orderbook(book) if book.data_type == "snapshot")
+}
+
+fn is_delta(message: &BybitWebSocketMessage) -> bool {
+    matches!(message, BybitWebSocketMessage::Orderbook(book) if book.data_type == "delta")
+}
+
+/// Feed snapshots and deltas from the stream into the local book
+fn apply(book: &mut LocalOrderbook, message: BybitWebSocketMessage) -> Result<(), OrderbookSyncError> {
+    let BybitWebSocketMessage::Orderbook(update) = message else {
+        panic!("expected an orderbook message, got {:?}", message);
+    };
+    if update.data_type == "snapshot" {
+        book.apply_snapshot(&update.data)
+    } else {
+        book.apply_delta(&update.data)
+    }
+}
+
+#[tokio::test]
+async fn local_book_follows_snapshot_and_deltas() {
+    let venue = venue(Exchanges::Bybit).await;
+    let mut client = BybitWebSocketClient::new(config(&venue));
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+    client.subscribe_orderbook("BTCUSDT", 50).await.unwrap();
+
+    let ack = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Subscription(_))).await;
+    assert!(matches!(ack, BybitWebSocketMessage::Subscription(ref ack) if ack.success && ack.args == ["orderbook.50.BTCUSDT"]));
+
+    let mut book = LocalOrderbook::from_symbol(&venue.symbol("BTCUSDT")).unwrap();
+    apply(&mut book, next_matching(&mut receiver, is_snapshot).await).unwrap();
+    assert_eq!(book.best_bid().unwrap().price, price("65000"));
+
+    // Bid level removed, ask level changed
+    venue.set_book("BTCUSDT", &[("64999.9", "2")], &[("65000.5", "3"), ("65001", "2")]);
+    apply(&mut book, next_matching(&mut receiver, is_delta).await).unwrap();
+
+    assert_eq!(book.best_bid().unwrap().price, price("64999.9"));
+    assert_eq!(book.best_ask().unwrap().size, "3".parse().unwrap());
+    assert_eq!(book.depth(), (1, 2));
+}
+
+#[tokio::test]
+async fn missed_update_is_detected_and_resnapshotted() {
+    let venue = venue(Exchanges::Bybit).await;
+    let mut client = BybitWebSocketClient::new(config(&venue));
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+    client.subscribe_orderbook("BTCUSDT", 50).await.unwrap();
+
+    let mut book = LocalOrderbook::from_symbol(&venue.symbol("BTCUSDT")).unwrap();
+    apply(&mut book, next_matching(&mut receiver, is_snapshot).await).unwrap();
+
+    venue.skip_book_updates(1);
+    venue.set_book("BTCUSDT", &[("64999.9", "2")], &[("65000.5", "1")]);
+    venue.set_book("BTCUSDT", &[("64999.8", "4")], &[("65000.5", "1")]);
+
+    let error = apply(&mut book, next_matching(&mut receiver, is_delta).await).unwrap_err();
+    assert!(matches!(error, OrderbookSyncError::Gap { expected: 2, received: 3 }), "{:?}", error);
+    assert!(book.needs_snapshot);
+
+    client.resubscribe_orderbook("BTCUSDT", 50).await.unwrap();
+    apply(&mut book, next_matching(&mut receiver, is_snapshot).await).unwrap();
+    assert!(!book.needs_snapshot);
+    assert_eq!(book.best_bid().unwrap().price, price("64999.8"));
+}
+
+#[test]
+fn off_tick_levels_are_refused_not_rounded() {
+    let tick = price("0.1");
+    assert_eq!(LocalOrderbook::new("BTCUSDT".to_string(), Price::ZERO).unwrap_err(), OrderbookSyncError::InvalidTickSize { tick_size: Price::ZERO });
+
+    let mut book = LocalOrderbook::new("BTCUSDT".to_string(), tick).unwrap();
+    let off_tick = [(price("65000"), "1".parse().unwrap()), (price("64999.96"), "2".parse().unwrap())];
+    let error = book.apply_snapshot_levels(&off_tick, &[], 1, 1).unwrap_err();
+    assert_eq!(error, OrderbookSyncError::OffTick { price: price("64999.96"), tick_size: tick });
+    assert!(book.needs_snapshot);
+
+    book.apply_snapshot_levels(&off_tick[..1], &[(price("65000.5"), "1".parse().unwrap())], 1, 1).unwrap();
+    // 64999.96 would have landed on the 65000 key and overwritten its size
+    let error = book.apply_delta_levels(&[(price("64999.96"), "2".parse().unwrap())], &[], 2, 2).unwrap_err();
+    assert!(matches!(error, OrderbookSyncError::OffTick { .. }), "{:?}", error);
+    assert!(book.needs_snapshot);
+    assert_eq!(book.best_bid().unwrap().size, "1".parse().unwrap());
+    assert_eq!(book.depth(), (1, 1));
+}
+
+#[tokio::test]
+async fn dropped_connection_reconnects_and_replays_subscriptions() {
+    let venue = venue(Exchanges::Bybit).await;
+    let mut client = BybitWebSocketClient::new(config(&venue));
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+    client.subscribe_orderbook("BTCUSDT", 50).await.unwrap();
+    next_matching(&mut receiver, is_snapshot).await;
+
+    venue.drop_connections();
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Disconnected(_))).await;
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Reconnected { .. })).await;
+
+    // The replayed subscription starts over with a snapshot
+    next_matching(&mut receiver, is_snapshot).await;
+    assert_eq!(client.active_subscriptions(), vec!["orderbook.50.BTCUSDT".to_string()]);
+}
+
+#[tokio::test]
+async fn heartbeat_measures_rtt_and_detects_a_stale_connection() {
+    let venue = venue(Exchanges::Bybit).await;
+    let mut client = BybitWebSocketClient::new(BybitWebSocketConfig {
+        heartbeat_interval_ms: 50,
+        ..config(&venue)
+    });
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Heartbeat { .. })).await;
+
+    venue.mute_pongs(true);
+    let message = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Disconnected(_))).await;
+    assert!(matches!(message, BybitWebSocketMessage::Disconnected(ref reason) if reason.contains("stale")), "{:?}", message);
+
+    venue.mute_pongs(false);
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Reconnected { .. })).await;
+}
+
+#[tokio::test]
+async fn private_stream_pushes_orders_fills_and_positions() {
+    let venue = venue(Exchanges::Bybit).await;
+    let mut client = BybitWebSocketClient::new_private(config(&venue), MOCK_API_KEY.to_string(), MOCK_API_SECRET.to_string());
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+
+    let auth = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Auth(_))).await;
+    assert!(matches!(auth, BybitWebSocketMessage::Auth(ref auth) if auth.success), "{:?}", auth);
+
+    client.subscribe_private(&[BybitPrivateTopic::Order, BybitPrivateTopic::Execution, BybitPrivateTopic::Position]).await.unwrap();
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Subscription(ack) if ack.success)).await;
+
+    venue.bybit_client().place_order(&BybitPlaceOrderRequest {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: Side::Sell,
+        orderType: OrderType::Market,
+        qty: "0.5".parse().unwrap(),
+        price: None,
+        timeInForce: Some(TimeInForce::IOC),
+        orderLinkId: Some("mkt-1".to_string()),
+        reduceOnly: None,
+        positionIdx: None,
+    }).await.unwrap();
+
+    let execution = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Execution(_))).await;
+    let BybitWebSocketMessage::Execution(execution) = execution else { unreachable!() };
+    assert_eq!(execution.data[0].orderLinkId, "mkt-1");
+    assert_eq!(execution.data[0].execPrice, "65000");
+    assert!(!execution.data[0].isMaker);
+    let fill = execution.data[0].to_fill().unwrap();
+    assert_eq!((fill.qty, fill.filled_qty), (qty("0.5"), qty("0.5")));
+
+    let position = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Position(_))).await;
+    let BybitWebSocketMessage::Position(position) = position else { unreachable!() };
+    assert_eq!((position.data[0].side.as_str(), position.data[0].size.as_str()), ("Sell", "0.5"));
+
+    let order = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Order(_))).await;
+    let BybitWebSocketMessage::Order(order) = order else { unreachable!() };
+    assert_eq!(order.data[0].orderStatus, "Filled");
+}
+
+#[tokio::test]
+async fn private_stream_rejects_a_bad_signature() {
+    let venue = venue(Exchanges::Bybit).await;
+    let mut client = BybitWebSocketClient::new_private(config(&venue), MOCK_API_KEY.to_string(), "wrong".to_string());
+    let (_sender, mut receiver) = client.connect().await.unwrap();
+
+    let auth = next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Auth(_))).await;
+    assert!(matches!(auth, BybitWebSocketMessage::Auth(ref auth) if !auth.success && auth.ret_msg == "Invalid sign"), "{:?}", auth);
+}
+
+#[tokio::test]
+async fn dcp_cancels_resting_orders_once_the_stream_is_gone_for_the_window() {
+    let venue = venue(Exchanges::Bybit).await;
+    let rest_client = venue.bybit_client();
+    rest_client.set_dcp(&BybitDcpRequest { product: Some("DERIVATIVES".to_string()), timeWindow: 3 }).await.unwrap();
+
+    let mut client = BybitWebSocketClient::new_private(config(&venue), MOCK_API_KEY.to_string(), MOCK_API_SECRET.to_string());
+    let (sender, mut receiver) = client.connect().await.unwrap();
+    // Only the topic of the product DCP is set on holds it
+    client.subscribe_private(&[BybitPrivateTopic::Order, BybitPrivateTopic::Dcp(BybitDcpProduct::Spot)]).await.unwrap();
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Subscription(ack) if ack.success)).await;
+    assert_eq!(venue.dcp_connections(), 0);
+    client.subscribe_private(&[BybitPrivateTopic::Dcp(BybitDcpProduct::Derivatives)]).await.unwrap();
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Subscription(ack) if ack.success)).await;
+    assert_eq!(venue.dcp_connections(), 1);
+
+    rest_client.place_order(&BybitPlaceOrderRequest {
+        category: "linear".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        side: Side::Buy,
+        orderType: OrderType::Limit,
+        qty: "0.1".parse().unwrap(),
+        price: Some(price("64000")),
+        timeInForce: Some(TimeInForce::GTC),
+        orderLinkId: Some("rest-1".to_string()),
+        reduceOnly: None,
+        positionIdx: None,
+    }).await.unwrap();
+
+    // A reconnect inside the window replays dcp and keeps the order
+    venue.drop_connections();
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Reconnected { .. })).await;
+    next_matching(&mut receiver, |message| matches!(message, BybitWebSocketMessage::Subscription(ack) if ack.success)).await;
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+    assert_eq!(venue.open_orders("BTCUSDT").len(), 1);
+
+    // Gone for good
+    drop((client, sender, receiver));
+    tokio::time::timeout(WAIT, async {
+        while !venue.open_orders("BTCUSDT").is_empty() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.expect("DCP never cancelled the order");
+    assert_eq!(venue.dcp_connections(), 0);
+}
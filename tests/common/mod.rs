@@ -0,0 +1,12 @@
+// Shared by several test crates, each one only uses part of it
+#![allow(dead_code)]
+
+use rust_qts::decimal::{Price, Qty};
+
+pub fn price(value: &str) -> Price {
+    value.parse().unwrap()
+}
+
+pub fn qty(value: &str) -> Qty {
+    value.parse().unwrap()
+}
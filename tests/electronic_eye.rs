@@ -0,0 +1,661 @@
+mod common;
+mod mock_venue;
+
+use common::{price, qty};
+use mock_venue::{next_matching, venues, MockVenue, MOCK_API_KEY, MOCK_API_SECRET, WAIT};
+use rust_qts::data_structure::{QTSFill, QTSOrderRequest};
+use rust_qts::decimal::{Price, Qty};
+use rust_qts::enums::{Environment, Exchanges, HedgeMode, OrderType, Side, TimeInForce};
+use rust_qts::oms::new_client_order_id;
+use rust_qts::strategy::eye::ee::ElectronicEye;
+use rust_qts::strategy::eye::events::{self, EESenders, ExecutionEvent, FeedStatus, Leg, Stream};
+use rust_qts::strategy::eye::feeds::{self, FeedConfig};
+use rust_qts::strategy::eye::params::EEConfig;
+use rust_qts::strategy::eye::quoter::quote_order_id;
+use std::time::Duration;
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+
+/// The shared venue pair, already carrying a hedged 0.2 position
+async fn hedged_venues() -> (MockVenue, MockVenue) {
+    let (bybit, binance) = venues().await;
+    bybit.set_position("BTCUSDT", "0.2", "64000");
+    binance.set_position("BTCUSDT", "-0.2", "64010");
+    (bybit, binance)
+}
+
+#[tokio::test]
+async fn poll_reads_positions_on_both_legs() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+
+    ee.poll().await;
+
+    assert_eq!(bybit.requests_to("/v5/position/list").len(), 1);
+    assert_eq!(binance.requests_to("/fapi/v3/positionRisk").len(), 1);
+}
+
+#[tokio::test]
+async fn run_keeps_polling_until_stopped() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+
+    // Initial poll, then one more from the backoffice once poll_interval_seconds has passed
+    let _ = tokio::time::timeout(Duration::from_secs(5), ee.run()).await;
+
+    assert!(bybit.requests_to("/v5/position/list").len() >= 2);
+    assert!(binance.requests_to("/fapi/v3/positionRisk").len() >= 2);
+}
+
+/// Resting quotes on the mock as (side, price, size), bids first
+fn quotes(venue: &MockVenue) -> Vec<(Side, Price, Qty)> {
+    let mut quotes: Vec<_> = venue.open_orders("BTCUSDT").iter().map(|order| (order.side, order.price.unwrap(), order.qty)).collect();
+    quotes.sort_by_key(|(side, price, _)| (*side == Side::Sell, *price));
+    quotes
+}
+
+#[tokio::test]
+async fn quotes_a_ladder_around_the_hedge_mid() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+
+    // Hedge mid 65001.25, 2.5bp inside and 2bp between levels
+    ee.process_book_updates().await;
+    assert_eq!(quotes(&bybit), vec![
+        (Side::Buy, price("64971.9"), qty("0.02")),
+        (Side::Buy, price("64984.9"), qty("0.01")),
+        (Side::Sell, price("65017.6"), qty("0.01")),
+        (Side::Sell, price("65030.6"), qty("0.02")),
+    ]);
+    assert!(bybit.open_orders("BTCUSDT").iter().all(|order| order.client_order_id.starts_with("ee-")));
+
+    // One batch for the whole ladder, and nothing moved, nothing sent
+    ee.process_book_updates().await;
+    assert_eq!(bybit.batch_sizes("/v5/order/create-batch"), vec![4]);
+    assert!(bybit.requests_to("/v5/order/create").is_empty());
+    assert!(bybit.requests_to("/v5/order/cancel-batch").is_empty());
+
+    // Both markets move 20, past relist_interval, the whole ladder follows
+    binance.set_book("BTCUSDT", &[("65021", "1")], &[("65021.5", "1")]);
+    bybit.set_book("BTCUSDT", &[("65010", "1")], &[("65020.5", "1")]);
+    ee.process_book_updates().await;
+    assert_eq!(bybit.batch_sizes("/v5/order/cancel-batch"), vec![4]);
+    assert_eq!(bybit.batch_sizes("/v5/order/create-batch"), vec![4, 4]);
+    assert_eq!(quotes(&bybit)[1], (Side::Buy, price("65004.9"), qty("0.01")));
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 4);
+}
+
+#[tokio::test]
+async fn leaves_orders_it_does_not_own_alone() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+
+    let manual = QTSOrderRequest {
+        side: Side::Buy,
+        order_type: OrderType::Limit,
+        price: Some(price("60000")),
+        qty: qty("0.1"),
+        time_in_force: TimeInForce::GTC,
+        client_order_id: "manual-1".to_string(),
+        reduce_only: false,
+    };
+    bybit.rest_client().place_order(&bybit.symbol("BTCUSDT"), &manual).await.unwrap();
+
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+    assert!(bybit.order("manual-1").unwrap().status.is_open());
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 5);
+}
+
+#[tokio::test]
+async fn stops_bidding_at_max_position() {
+    let (bybit, binance) = hedged_venues().await;
+    bybit.set_position("BTCUSDT", "0.5", "64000");
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+
+    ee.process_book_updates().await;
+    assert!(quotes(&bybit).iter().all(|(side, _, _)| *side == Side::Sell));
+    assert_eq!(quotes(&bybit).len(), 2);
+}
+
+#[tokio::test]
+async fn quote_fill_is_hedged_on_the_other_venue() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    // Inner ask gets lifted, short 0.01 on the quote leg
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+
+    ee.process_fills().await;
+    let hedge = &binance.requests_to("/fapi/v1/order")[0];
+    assert!(hedge.query.contains("side=BUY") && hedge.query.contains("quantity=0.01&") && hedge.query.contains("timeInForce=IOC"), "{}", hedge.query);
+    assert_eq!(binance.position("BTCUSDT").size, qty("-0.19"));
+
+    // Hedge fill is picked up, nothing more to send
+    ee.process_fills().await;
+    assert_eq!(binance.requests_to("/fapi/v1/order").len(), 1);
+}
+
+/// Streams on a mock venue, private too when credentials are given
+fn feed(venue: &MockVenue, private: bool) -> FeedConfig {
+    FeedConfig {
+        environment: Environment::Custom(venue.rest_url()),
+        ws_url: Some(venue.ws_url()),
+        credentials: private.then(|| (MOCK_API_KEY.to_string(), MOCK_API_SECRET.to_string())),
+    }
+}
+
+async fn eventually(what: &str, mut condition: impl FnMut() -> bool) {
+    tokio::time::timeout(WAIT, async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }).await.unwrap_or_else(|_| panic!("timed out waiting for {}", what));
+}
+
+/// Run loop on streamed books and Bybit executions, returns once the first ladder is resting
+async fn running(bybit: &MockVenue, binance: &MockVenue, config: EEConfig) -> EESenders {
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    ee.start_feeds(feed(bybit, true), feed(binance, true)).await.unwrap();
+    let senders = ee.senders();
+    tokio::spawn(ee.run());
+
+    eventually("the first ladder", || bybit.open_orders("BTCUSDT").len() == 4).await;
+    senders
+}
+
+fn slow_polling_config() -> EEConfig {
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.quote_params.poll_interval_seconds = 60;
+    config
+}
+
+#[tokio::test]
+async fn streamed_quote_fill_is_hedged_without_a_poll() {
+    let (bybit, binance) = hedged_venues().await;
+    let _senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+
+    eventually("the hedge", || !binance.requests_to("/fapi/v1/order").is_empty()).await;
+    let hedge = &binance.requests_to("/fapi/v1/order")[0];
+    assert!(hedge.query.contains("side=BUY") && hedge.query.contains("quantity=0.01&"), "{}", hedge.query);
+
+    // Only the startup poll ever read the quote position, the fill came off the stream
+    assert_eq!(bybit.requests_to("/v5/position/list").len(), 1);
+
+    // And the lifted level is back
+    eventually("the relisted ask", || bybit.open_orders("BTCUSDT").len() == 4).await;
+}
+
+#[tokio::test]
+async fn streamed_books_move_the_quotes() {
+    let (bybit, binance) = hedged_venues().await;
+    let _senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    bybit.set_book("BTCUSDT", &[("65010", "1")], &[("65020.5", "1")]);
+    binance.set_book("BTCUSDT", &[("65021", "1")], &[("65021.5", "1")]);
+
+    eventually("the ladder to follow", || {
+        let quotes = quotes(&bybit);
+        quotes.len() == 4 && quotes[1] == (Side::Buy, price("65004.9"), qty("0.01"))
+    }).await;
+}
+
+#[tokio::test]
+async fn rate_limited_quote_cancel_is_sent_again() {
+    let (bybit, binance) = hedged_venues().await;
+    let _senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    // The batch cancel is refused, those quotes are still resting and must not be forgotten
+    bybit.rate_limit_next(0);
+    bybit.set_book("BTCUSDT", &[("65010", "1")], &[("65020.5", "1")]);
+    binance.set_book("BTCUSDT", &[("65021", "1")], &[("65021.5", "1")]);
+
+    eventually("the ladder to follow", || {
+        let quotes = quotes(&bybit);
+        quotes.len() == 4 && quotes[1] == (Side::Buy, price("65004.9"), qty("0.01"))
+    }).await;
+    assert!(bybit.requests_to("/v5/order/cancel-batch").len() >= 2);
+}
+
+#[tokio::test]
+async fn quotes_are_not_refreshed_inside_order_refresh_rate() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = slow_polling_config();
+    config.quote_params.order_refresh_rate_ms = 1500;
+    let _senders = running(&bybit, &binance, config).await;
+
+    bybit.set_book("BTCUSDT", &[("65010", "1")], &[("65020.5", "1")]);
+    binance.set_book("BTCUSDT", &[("65021", "1")], &[("65021.5", "1")]);
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(bybit.requests_to("/v5/order/cancel-batch").is_empty());
+
+    // Relisted once the quotes have aged past the refresh rate
+    eventually("the relist", || {
+        let quotes = quotes(&bybit);
+        quotes.len() == 4 && quotes[1] == (Side::Buy, price("65004.9"), qty("0.01"))
+    }).await;
+    assert_eq!(bybit.batch_sizes("/v5/order/cancel-batch"), vec![4]);
+}
+
+#[tokio::test]
+async fn filled_level_waits_out_fill_refresh_delay() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = slow_polling_config();
+    config.quote_params.fill_refresh_delay = 1;
+    let _senders = running(&bybit, &binance, config).await;
+
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+    eventually("the hedge", || !binance.requests_to("/fapi/v1/order").is_empty()).await;
+
+    // Hedged straight away, but the level itself stays empty for now
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(bybit.batch_sizes("/v5/order/create-batch"), vec![4]);
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 3);
+
+    eventually("the relisted ask", || bybit.open_orders("BTCUSDT").len() == 4).await;
+    assert_eq!(quotes(&bybit)[2], (Side::Sell, price("65017.6"), qty("0.01")));
+}
+
+#[tokio::test]
+async fn config_change_is_picked_up_while_running() {
+    let (bybit, binance) = hedged_venues().await;
+    let senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    let mut config = slow_polling_config();
+    config.quote_params.bid_on = false;
+    senders.config.send(config).unwrap();
+
+    eventually("the bids to be pulled", || quotes(&bybit).iter().all(|(side, _, _)| *side == Side::Sell)).await;
+    assert_eq!(quotes(&bybit).len(), 2);
+}
+
+#[tokio::test]
+async fn margin_over_threshold_quotes_reduce_only() {
+    let (bybit, binance) = hedged_venues().await;
+    bybit.set_position("BTCUSDT", "0.015", "64000");
+    bybit.set_margin(20000.0, 85000.0);
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.run_mode.risk_management_mode = true;
+    let mut ee = ElectronicEye::with_clients(config.clone(), bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.check_margin().await;
+
+    // Long 0.015, only asks and never for more than the position
+    ee.process_book_updates().await;
+    assert_eq!(quotes(&bybit), vec![
+        (Side::Sell, price("65017.6"), qty("0.01")),
+        (Side::Sell, price("65030.6"), qty("0.005")),
+    ]);
+    assert!(bybit.open_orders("BTCUSDT").iter().all(|order| order.reduce_only));
+    assert_eq!(binance.requests_to("/fapi/v3/positionRisk").len(), 2);
+
+    // Back under the threshold, both sides again, a flagged ask that still fits is kept
+    bybit.set_margin(20000.0, 1000.0);
+    ee.check_margin().await;
+    assert_eq!(quotes(&bybit).len(), 4);
+    assert!(bybit.open_orders("BTCUSDT").iter().filter(|order| order.side == Side::Buy).all(|order| !order.reduce_only));
+
+    // Switching risk management off drops the readings
+    bybit.set_margin(20000.0, 85000.0);
+    ee.check_margin().await;
+    assert_eq!(quotes(&bybit).len(), 2);
+    config.run_mode.risk_management_mode = false;
+    ee.process_config_updates(config).await;
+    assert_eq!(quotes(&bybit).len(), 4);
+}
+
+#[tokio::test]
+async fn margin_is_checked_on_schedule_only_in_risk_management_mode() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = slow_polling_config();
+    config.quote_params.margin_check_interval = 500;
+    let senders = running(&bybit, &binance, config.clone()).await;
+    tokio::time::sleep(Duration::from_millis(700)).await;
+    assert!(bybit.requests_to("/v5/account/wallet-balance").is_empty());
+
+    config.run_mode.risk_management_mode = true;
+    senders.config.send(config).unwrap();
+    eventually("two margin checks", || {
+        bybit.requests_to("/v5/account/wallet-balance").len() >= 2 && binance.requests_to("/fapi/v3/balance").len() >= 2
+    }).await;
+}
+
+#[tokio::test]
+async fn ledger_starts_from_the_venue_and_follows_fills() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    let ledger = ee.ledger();
+    assert_eq!(ledger.leg(Leg::Quote).size(), qty("0.2"));
+    assert_eq!(ledger.leg(Leg::Quote).entry_price(), Some(64000.0));
+    assert_eq!(ledger.leg(Leg::Hedge).size(), qty("-0.2"));
+    assert_eq!(ledger.net_position(), 0.0);
+    // Long 0.2 from 64000 marked at the 65000.25 mid, short 0.2 from 64010 at 65001.25
+    assert!((ledger.unrealized_pnl() - (200.05 - 198.25)).abs() < 1e-6, "{}", ledger);
+
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+    ee.process_fills().await;
+    ee.process_fills().await;
+
+    // Without streams both fills are taken at the mids, and the hedge squares it up again
+    let ledger = ee.ledger();
+    assert_eq!(ledger.leg(Leg::Quote).size(), qty("0.19"));
+    assert_eq!(ledger.leg(Leg::Hedge).size(), qty("-0.19"));
+    assert_eq!(ledger.net_position(), 0.0);
+    assert!((ledger.leg(Leg::Quote).realized_pnl() - 10.0025).abs() < 1e-6, "{}", ledger);
+    assert!((ledger.leg(Leg::Hedge).realized_pnl() + 9.9125).abs() < 1e-6, "{}", ledger);
+}
+
+fn limit(side: Side, limit_price: &str, size: &str, client_order_id: String) -> QTSOrderRequest {
+    QTSOrderRequest {
+        side,
+        order_type: OrderType::Limit,
+        price: Some(price(limit_price)),
+        qty: qty(size),
+        time_in_force: TimeInForce::GTC,
+        client_order_id,
+        reduce_only: false,
+    }
+}
+
+#[tokio::test]
+async fn recovery_adopts_quotes_and_cancels_hedges_before_quoting() {
+    let (bybit, binance) = hedged_venues().await;
+
+    // Left by a run that crashed, an inner bid still on its level, a stale ask and a resting hedge
+    let kept_bid = quote_order_id(Side::Buy, 0);
+    let stale_ask = quote_order_id(Side::Sell, 0);
+    let hedge = new_client_order_id("eh-");
+    let quote_client = bybit.rest_client();
+    quote_client.place_order(&bybit.symbol("BTCUSDT"), &limit(Side::Buy, "64984.9", "0.01", kept_bid.clone())).await.unwrap();
+    quote_client.place_order(&bybit.symbol("BTCUSDT"), &limit(Side::Sell, "66000", "0.01", stale_ask.clone())).await.unwrap();
+    binance.rest_client().place_order(&binance.symbol("BTCUSDT"), &limit(Side::Buy, "60000", "0.01", hedge.clone())).await.unwrap();
+
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+
+    // Not armed, books alone don't quote
+    ee.process_book_updates().await;
+    assert!(bybit.requests_to("/v5/order/create-batch").is_empty());
+
+    // A venue that doesn't answer leaves it unarmed
+    binance.fail_next(-1121, "Invalid symbol.");
+    assert!(!ee.recover().await);
+    ee.process_book_updates().await;
+    assert!(bybit.requests_to("/v5/order/create-batch").is_empty());
+
+    assert!(ee.recover().await);
+    assert!(!binance.order(&hedge).unwrap().status.is_open());
+    assert_eq!(ee.ledger().leg(Leg::Quote).size(), qty("0.2"));
+    assert_eq!(ee.oms().working_orders(&bybit.symbol("BTCUSDT")).len(), 2);
+
+    // The adopted bid is already where the ladder wants it, the ask is not
+    ee.process_book_updates().await;
+    assert!(bybit.order(&kept_bid).unwrap().status.is_open());
+    assert!(!bybit.order(&stale_ask).unwrap().status.is_open());
+    assert_eq!(bybit.batch_sizes("/v5/order/create-batch"), vec![3]);
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 4);
+}
+
+#[tokio::test]
+async fn shutdown_cancels_our_orders_and_returns_from_run() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut ee = ElectronicEye::with_clients(slow_polling_config(), bybit.rest_client(), binance.rest_client());
+    ee.start_feeds(feed(&bybit, true), feed(&binance, true)).await.unwrap();
+    let senders = ee.senders();
+    let run = tokio::spawn(ee.run());
+    eventually("the first ladder", || bybit.open_orders("BTCUSDT").len() == 4).await;
+
+    let manual = limit(Side::Buy, "60000", "0.1", "manual-1".to_string());
+    bybit.rest_client().place_order(&bybit.symbol("BTCUSDT"), &manual).await.unwrap();
+
+    senders.shutdown.send(()).unwrap();
+    let clean = tokio::time::timeout(WAIT, run).await.unwrap().unwrap();
+
+    assert!(clean);
+    let open: Vec<_> = bybit.open_orders("BTCUSDT").into_iter().map(|order| order.client_order_id).collect();
+    assert_eq!(open, vec!["manual-1".to_string()]);
+    // Quoting stopped, nothing went out after the cancels
+    assert_eq!(bybit.requests_to("/v5/order/create").len(), 1);
+    assert_eq!(bybit.batch_sizes("/v5/order/create-batch"), vec![4]);
+}
+
+#[tokio::test]
+async fn shutdown_flattens_the_residual_when_asked() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.hedge_params.hedge_mode = HedgeMode::NoHedge;
+    config.run_mode.flatten_on_shutdown = true;
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+    ee.process_fills().await;
+    assert!(binance.requests_to("/fapi/v1/order").is_empty());
+
+    assert!(ee.shutdown().await);
+    assert!(bybit.open_orders("BTCUSDT").is_empty());
+    let flatten = &binance.requests_to("/fapi/v1/order")[0];
+    assert!(flatten.query.contains("side=BUY") && flatten.query.contains("quantity=0.01&") && flatten.query.contains("timeInForce=IOC"), "{}", flatten.query);
+    assert_eq!(binance.position("BTCUSDT").size, qty("-0.19"));
+}
+
+#[tokio::test]
+async fn failed_cancel_is_reported_by_shutdown() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    bybit.fail_next_http(502, "Bad Gateway");
+    assert!(!ee.shutdown().await);
+    // The read back still got it
+    assert!(bybit.open_orders("BTCUSDT").is_empty());
+}
+
+#[tokio::test]
+async fn refused_cancel_is_reported_by_shutdown() {
+    let (bybit, binance) = hedged_venues().await;
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    // Only the order being gone counts as cancelled
+    bybit.fail_next(10001, "params error");
+    assert!(!ee.shutdown().await);
+    assert!(bybit.open_orders("BTCUSDT").is_empty());
+}
+
+#[tokio::test]
+async fn failed_hedge_cancel_is_retried_before_replacing() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.hedge_params.hedge_mode = HedgeMode::Passive;
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    let quote_fill = |size: &str, filled: &str| ExecutionEvent::Fill { leg: Leg::Quote, fill: QTSFill {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Bybit,
+        order_id: "1".to_string(),
+        client_order_id: inner_ask.client_order_id.clone(),
+        side: Side::Sell,
+        price: inner_ask.price.unwrap(),
+        qty: qty(size),
+        filled_qty: qty(filled),
+        fee: 0.0,
+        is_maker: true,
+        time: 0,
+    }};
+
+    // Passive bid rests on the hedge venue
+    ee.process_execution(quote_fill("0.005", "0.005")).await;
+    assert_eq!(binance.open_orders("BTCUSDT").len(), 1);
+
+    // The residual grows, the cancel of the resting bid fails and it stays the working hedge
+    binance.fail_next_http(502, "Bad Gateway");
+    ee.process_execution(quote_fill("0.005", "0.01")).await;
+    assert_eq!(binance.open_orders("BTCUSDT").len(), 1);
+
+    // Cancelled for real, then replaced for the whole residual
+    ee.hedge().await;
+    assert!(binance.open_orders("BTCUSDT").is_empty());
+    ee.hedge().await;
+    let hedges = binance.open_orders("BTCUSDT");
+    assert_eq!(hedges.len(), 1);
+    assert_eq!(hedges[0].qty, qty("0.01"));
+}
+
+#[tokio::test]
+async fn quote_filled_before_its_cancel_still_shuts_down_clean() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.hedge_params.hedge_mode = HedgeMode::NoHedge;
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+
+    // The cancel comes back as an unknown order
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+
+    assert!(ee.shutdown().await);
+    assert!(bybit.open_orders("BTCUSDT").is_empty());
+}
+
+#[tokio::test]
+async fn dcp_is_armed_on_the_quote_leg_stream() {
+    let (bybit, binance) = hedged_venues().await;
+    let mut config = slow_polling_config();
+    config.quote_params.dcp_window = 3;
+    let mut ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    ee.start_feeds(feed(&bybit, true), feed(&binance, true)).await.unwrap();
+
+    let request = &bybit.requests_to("/v5/order/disconnected-cancel-all")[0];
+    assert!(request.body.contains(r#""product":"DERIVATIVES""#) && request.body.contains(r#""timeWindow":3"#), "{}", request.body);
+    assert_eq!(bybit.dcp(), Some(("DERIVATIVES".to_string(), 3)));
+    assert_eq!(bybit.dcp_connections(), 1);
+}
+
+#[tokio::test]
+async fn dropped_book_stream_pulls_the_quotes_until_it_is_back() {
+    let (bybit, binance) = hedged_venues().await;
+    let _senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    // Bybit streams reconnect after a second, the replayed snapshot brings the quotes back
+    bybit.drop_connections();
+    eventually("the quotes to be pulled", || bybit.open_orders("BTCUSDT").is_empty()).await;
+    eventually("the ladder to be back", || bybit.open_orders("BTCUSDT").len() == 4).await;
+}
+
+#[tokio::test]
+async fn book_gap_is_reported_like_a_dropped_stream() {
+    for exchange in [Exchanges::Bybit, Exchanges::Binance] {
+        let venue = MockVenue::start(exchange.clone()).await;
+        venue.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "1")]);
+        let (senders, mut receivers) = events::channels();
+        feeds::spawn_book_feed(Leg::Hedge, venue.symbol("BTCUSDT"), &feed(&venue, false), false, senders.market_data, senders.feed_state).await.unwrap();
+
+        // Binance only syncs once a diff comes in
+        venue.set_book("BTCUSDT", &[("65000", "2")], &[("65000.5", "1")]);
+        next_matching(&mut receivers.market_data, |update| update.top_of_book.0.is_some()).await;
+
+        venue.skip_book_updates(1);
+        venue.set_book("BTCUSDT", &[("64999.9", "2")], &[("65000.5", "1")]);
+        venue.set_book("BTCUSDT", &[("64999.8", "4")], &[("65000.5", "1")]);
+
+        let down = next_matching(&mut receivers.feed_state, |_| true).await;
+        assert_eq!((down.leg, down.stream, down.status), (Leg::Hedge, Stream::Book, FeedStatus::Down), "{:?}", exchange);
+        let back = next_matching(&mut receivers.feed_state, |_| true).await;
+        assert_eq!(back.status, FeedStatus::Resubscribed, "{:?}", exchange);
+
+        // Binance needs another diff to take its snapshot
+        venue.set_book("BTCUSDT", &[("64999.8", "5")], &[("65000.5", "1")]);
+        let update = next_matching(&mut receivers.market_data, |update| update.top_of_book.0 == Some(price("64999.8"))).await;
+        assert_eq!(update.top_of_book.1, Some(price("65000.5")));
+    }
+}
+
+#[tokio::test]
+async fn book_is_polled_once_its_stream_gives_up() {
+    let (bybit, binance) = hedged_venues().await;
+    let _senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    // The Binance stream doesn't reconnect, REST takes over
+    binance.drop_connections();
+    eventually("the quotes to be pulled", || bybit.open_orders("BTCUSDT").is_empty()).await;
+    eventually("the ladder off the polled book", || bybit.open_orders("BTCUSDT").len() == 4).await;
+
+    bybit.set_book("BTCUSDT", &[("65010", "1")], &[("65020.5", "1")]);
+    binance.set_book("BTCUSDT", &[("65021", "1")], &[("65021.5", "1")]);
+    eventually("the ladder to follow", || {
+        let quotes = quotes(&bybit);
+        quotes.len() == 4 && quotes[1] == (Side::Buy, price("65004.9"), qty("0.01"))
+    }).await;
+}
+
+#[tokio::test]
+async fn quote_fill_missed_while_the_private_stream_was_down_is_hedged() {
+    let (bybit, binance) = hedged_venues().await;
+    let _senders = running(&bybit, &binance, slow_polling_config()).await;
+
+    // Filled while nothing is there to stream it, the stream reconnects a second later
+    bybit.drop_private_connections();
+    let inner_ask = bybit.open_orders("BTCUSDT").into_iter()
+        .filter(|order| order.side == Side::Sell)
+        .min_by_key(|order| order.price)
+        .unwrap();
+    bybit.fill_order(&inner_ask.client_order_id, "0.01");
+
+    eventually("the hedge", || !binance.requests_to("/fapi/v1/order").is_empty()).await;
+    let hedge = &binance.requests_to("/fapi/v1/order")[0];
+    assert!(hedge.query.contains("side=BUY") && hedge.query.contains("quantity=0.01&"), "{}", hedge.query);
+    eventually("the relisted ask", || bybit.open_orders("BTCUSDT").len() == 4).await;
+}
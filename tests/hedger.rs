@@ -0,0 +1,181 @@
+mod common;
+
+use common::{price, qty};
+use rust_qts::data_structure::{QTSOrderRequest, Symbol};
+use rust_qts::decimal::Price;
+use rust_qts::enums::{Exchanges, HedgeMode, InstrumentType, Side, TimeInForce};
+use rust_qts::strategy::eye::hedger::{HedgeAction, Hedger};
+use rust_qts::strategy::eye::params::EEConfig;
+use rust_qts::strategy::strategy_structs::HedgeParams;
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+// 65000 / 65001
+const TOP: (Option<Price>, Option<Price>) = (Some(Price::from_raw(6_500_000_000_000)), Some(Price::from_raw(6_500_100_000_000)));
+
+fn params(hedge_mode: HedgeMode) -> HedgeParams {
+    let mut params = EEConfig::from_yaml_str(CONFIG).unwrap().hedge_params;
+    params.hedge_mode = hedge_mode;
+    params.hedge_delay = 0.5;
+    params.max_slippage = 0.001;
+    params.hedge_offset = 0.0002;
+    params
+}
+
+fn symbol() -> Symbol {
+    Symbol {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Binance,
+        instrument_type: InstrumentType::Perp,
+        min_tick: price("0.1"),
+        qty_decimal: 3,
+    }
+}
+
+fn placed(action: Option<HedgeAction>) -> QTSOrderRequest {
+    match action {
+        Some(HedgeAction::Place(request)) => request,
+        other => panic!("expected an order, got {:?}", other),
+    }
+}
+
+#[test]
+fn mode_zero_tracks_the_residual_without_trading() {
+    let mut hedger = Hedger::new(params(HedgeMode::NoHedge), symbol());
+    hedger.on_quote_fill(Side::Buy, qty("0.1"), price("65000"), 0);
+
+    assert_eq!(hedger.unhedged_usd(), -6_500.0);
+    assert!(hedger.next_action(10_000, TOP).is_none());
+}
+
+#[test]
+fn fills_inside_the_delay_go_out_as_one_ioc() {
+    let mut hedger = Hedger::new(params(HedgeMode::Active), symbol());
+    hedger.on_quote_fill(Side::Buy, qty("0.1"), price("65000"), 1_000);
+    assert!(hedger.next_action(1_200, TOP).is_none());
+
+    hedger.on_quote_fill(Side::Buy, qty("0.05"), price("65002"), 1_300);
+    let request = placed(hedger.next_action(1_500, TOP));
+
+    // 9750.1 USD at the 65000 bid, limit 10bp through it
+    assert_eq!(request.side, Side::Sell);
+    assert_eq!(request.qty, qty("0.15"));
+    assert_eq!(request.price, Some(price("64935")));
+    assert_eq!(request.time_in_force, TimeInForce::IOC);
+    assert!(request.client_order_id.starts_with("eh-"));
+}
+
+#[test]
+fn hedge_ratio_scales_the_notional() {
+    let mut params = params(HedgeMode::Active);
+    params.hedge_ratio = 0.5;
+    params.hedge_delay = 0.0;
+    let mut hedger = Hedger::new(params, symbol());
+
+    hedger.on_quote_fill(Side::Sell, qty("0.2"), price("65000"), 0);
+    assert_eq!(hedger.unhedged_usd(), 6_500.0);
+
+    let request = placed(hedger.next_action(0, TOP));
+    assert_eq!((request.side, request.qty), (Side::Buy, qty("0.1")));
+    assert_eq!(request.price, Some(price("65066")));
+}
+
+#[test]
+fn ioc_remainder_is_retried_once_the_position_shows_it() {
+    let mut hedger = Hedger::new(params(HedgeMode::Active), symbol());
+    hedger.on_quote_fill(Side::Buy, qty("0.1"), price("65000"), 0);
+    let request = placed(hedger.next_action(500, TOP));
+
+    // Nothing new while the IOC is still out
+    assert!(hedger.next_action(600, TOP).is_none());
+
+    hedger.on_hedge_fill(Side::Sell, qty("0.06"), price("65000"));
+    hedger.on_hedge_synced();
+    assert_eq!(hedger.unhedged_usd(), -2_600.0);
+
+    let retry = placed(hedger.next_action(700, TOP));
+    assert_eq!(retry.qty, qty("0.04"));
+    assert_ne!(retry.client_order_id, request.client_order_id);
+
+    hedger.on_hedge_fill(Side::Sell, qty("0.04"), price("65000"));
+    hedger.on_hedge_synced();
+    assert_eq!(hedger.unhedged_usd(), 0.0);
+    assert!(hedger.next_action(800, TOP).is_none());
+}
+
+#[test]
+fn less_than_half_a_lot_waits_for_more_fills() {
+    let mut hedger = Hedger::new(params(HedgeMode::Active), symbol());
+    hedger.on_quote_fill(Side::Buy, qty("0.0004"), price("65000"), 0);
+    assert!(hedger.next_action(1_000, TOP).is_none());
+
+    hedger.on_quote_fill(Side::Buy, qty("0.0007"), price("65000"), 2_000);
+    let request = placed(hedger.next_action(2_500, TOP));
+    assert_eq!(request.qty, qty("0.001"));
+}
+
+#[test]
+fn passive_hedge_rests_and_is_replaced_when_the_residual_changes() {
+    let mut hedger = Hedger::new(params(HedgeMode::Passive), symbol());
+    hedger.on_quote_fill(Side::Buy, qty("0.1"), price("65001"), 0);
+    let request = placed(hedger.next_action(500, TOP));
+
+    // Sells 2bp behind the 65001 ask
+    assert_eq!(request.side, Side::Sell);
+    assert_eq!(request.qty, qty("0.1"));
+    assert_eq!(request.price, Some(price("65014.1")));
+    assert_eq!(request.time_in_force, TimeInForce::PostOnly);
+
+    // Partial fill leaves it resting
+    hedger.on_hedge_fill(Side::Sell, qty("0.03"), price("65014.1"));
+    hedger.on_hedge_synced();
+    assert!(hedger.next_action(600, TOP).is_none());
+    assert_eq!(hedger.working_order(), Some(request.client_order_id.as_str()));
+
+    // More quote fills, cancel then re-place for the new total
+    hedger.on_quote_fill(Side::Buy, qty("0.05"), price("65001"), 700);
+    match hedger.next_action(800, TOP) {
+        Some(HedgeAction::Cancel(client_order_id)) => assert_eq!(client_order_id, request.client_order_id),
+        other => panic!("expected a cancel, got {:?}", other),
+    }
+    assert!(hedger.next_action(900, TOP).is_none());
+
+    hedger.on_order_done(&request.client_order_id);
+    let replacement = placed(hedger.next_action(1_000, TOP));
+    assert_eq!(replacement.qty, qty("0.12"));
+}
+
+#[test]
+fn refused_cancel_keeps_the_order_working_and_is_sent_again() {
+    let mut hedger = Hedger::new(params(HedgeMode::Passive), symbol());
+    hedger.on_quote_fill(Side::Buy, qty("0.1"), price("65001"), 0);
+    let request = placed(hedger.next_action(500, TOP));
+
+    hedger.on_quote_fill(Side::Buy, qty("0.05"), price("65001"), 700);
+    assert!(matches!(hedger.next_action(800, TOP), Some(HedgeAction::Cancel(_))));
+
+    // Still resting on the venue, no replacement until it is gone
+    hedger.on_cancel_failed(&request.client_order_id);
+    assert_eq!(hedger.working_order(), Some(request.client_order_id.as_str()));
+    match hedger.next_action(900, TOP) {
+        Some(HedgeAction::Cancel(client_order_id)) => assert_eq!(client_order_id, request.client_order_id),
+        other => panic!("expected the cancel again, got {:?}", other),
+    }
+}
+
+#[test]
+fn flatten_sends_the_whole_residual_whatever_the_mode() {
+    let mut hedger = Hedger::new(params(HedgeMode::NoHedge), symbol());
+    assert!(hedger.flatten(TOP).is_none());
+
+    hedger.on_quote_fill(Side::Sell, qty("0.1"), price("65000"), 0);
+    let request = placed(hedger.flatten(TOP));
+
+    // 6500 USD at the 65001 ask, limit 10bp through it
+    assert_eq!(request.side, Side::Buy);
+    assert_eq!(request.qty, qty("0.1"));
+    assert_eq!(request.price, Some(price("65066")));
+    assert_eq!(request.time_in_force, TimeInForce::IOC);
+
+    // No ask to lift
+    assert!(hedger.flatten((TOP.0, None)).is_none());
+}
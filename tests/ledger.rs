@@ -0,0 +1,121 @@
+mod common;
+
+use common::{price, qty};
+use rust_qts::data_structure::QTSPosition;
+use rust_qts::enums::{Exchanges, Side};
+use rust_qts::strategy::eye::events::Leg;
+use rust_qts::strategy::eye::ledger::{Ledger, PositionKeeper};
+use rust_qts::strategy::eye::params::EEConfig;
+use rust_qts::strategy::strategy_structs::HedgeParams;
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+
+fn ratios(hedge_ratio: f64, price_ratio: f64) -> HedgeParams {
+    let mut params = EEConfig::from_yaml_str(CONFIG).unwrap().hedge_params;
+    params.hedge_ratio = hedge_ratio;
+    params.price_ratio = price_ratio;
+    params
+}
+
+fn close_to(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() < 1e-6
+}
+
+fn venue_position(size: &str, entry_price: &str) -> QTSPosition {
+    QTSPosition {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Binance,
+        size: qty(size),
+        entry_price: price(entry_price),
+        mark_price: price(entry_price),
+        unrealized_pnl: 0.0,
+        time: 0,
+    }
+}
+
+#[test]
+fn adding_averages_the_entry_and_reducing_realizes() {
+    let mut keeper = PositionKeeper::new();
+    keeper.on_fill(Side::Buy, qty("0.1"), price("65000"), 1.3);
+    keeper.on_fill(Side::Buy, qty("0.3"), price("65100"), 3.9);
+    assert_eq!(keeper.size(), qty("0.4"));
+    assert!(close_to(keeper.entry_price().unwrap(), 65075.0));
+
+    // Half out 25 above the entry
+    keeper.on_fill(Side::Sell, qty("0.2"), price("65100"), -0.5);
+    assert!(close_to(keeper.realized_pnl(), 5.0));
+    assert!(close_to(keeper.entry_price().unwrap(), 65075.0));
+    assert!(close_to(keeper.fees(), 4.7));
+
+    // Through flat, the rest starts a short at the fill price
+    keeper.on_fill(Side::Sell, qty("0.3"), price("65000"), 0.0);
+    assert_eq!(keeper.size(), qty("-0.1"));
+    assert!(close_to(keeper.realized_pnl(), 5.0 - 15.0));
+    assert!(close_to(keeper.entry_price().unwrap(), 65000.0));
+
+    keeper.on_fill(Side::Buy, qty("0.1"), price("64900"), 0.0);
+    assert_eq!(keeper.entry_price(), None);
+    assert!(close_to(keeper.realized_pnl(), 0.0));
+}
+
+#[test]
+fn net_pnl_takes_off_fees_and_funding() {
+    let mut keeper = PositionKeeper::new();
+    assert_eq!(keeper.unrealized_pnl(), 0.0);
+
+    keeper.on_fill(Side::Sell, qty("0.5"), price("65000"), 6.5);
+    keeper.set_mark(price("64900"));
+    keeper.on_funding(2.0);
+    keeper.on_funding(-0.5);
+
+    assert!(close_to(keeper.unrealized_pnl(), 50.0));
+    assert!(close_to(keeper.funding(), 1.5));
+    assert!(close_to(keeper.net_pnl(), 50.0 - 6.5 - 1.5));
+}
+
+#[test]
+fn legs_net_off_against_each_other() {
+    let mut ledger = Ledger::new(&ratios(1.0, 1.0));
+    ledger.leg_mut(Leg::Quote).on_fill(Side::Sell, qty("0.01"), price("65020"), -0.13);
+    ledger.leg_mut(Leg::Hedge).on_fill(Side::Buy, qty("0.004"), price("65000"), 0.13);
+    assert!(close_to(ledger.net_position(), -0.006));
+
+    ledger.leg_mut(Leg::Hedge).on_fill(Side::Buy, qty("0.006"), price("65000"), 0.195);
+    ledger.set_mark(Leg::Quote, price("65010"));
+    ledger.set_mark(Leg::Hedge, price("65010"));
+    assert_eq!(ledger.net_position(), 0.0);
+    // Sold 20 over where it was bought back, whichever way the market goes
+    assert!(close_to(ledger.unrealized_pnl(), 0.2));
+    assert!(close_to(ledger.net_pnl(), 0.2 + 0.13 - 0.325));
+}
+
+#[test]
+fn net_position_scales_the_quote_leg_by_the_ratios() {
+    // Half the notional hedged, on an instrument a tenth of the quote's price
+    let mut ledger = Ledger::new(&ratios(0.5, 10.0));
+    ledger.leg_mut(Leg::Quote).on_fill(Side::Buy, qty("0.2"), price("65000"), 0.0);
+    ledger.leg_mut(Leg::Hedge).on_fill(Side::Sell, qty("0.6"), price("6500"), 0.0);
+    assert!(close_to(ledger.net_position(), 0.4));
+
+    ledger.leg_mut(Leg::Hedge).on_fill(Side::Sell, qty("0.4"), price("6500"), 0.0);
+    assert!(close_to(ledger.net_position(), 0.0));
+
+    // Read again after a config edit
+    ledger.set_params(&ratios(1.0, 10.0));
+    assert!(close_to(ledger.net_position(), 1.0));
+}
+
+#[test]
+fn check_takes_over_the_venue_position() {
+    let mut ledger = Ledger::new(&ratios(1.0, 1.0));
+    ledger.leg_mut(Leg::Hedge).on_fill(Side::Sell, qty("0.2"), price("65000"), 0.0);
+    assert_eq!(ledger.check(Leg::Hedge, &[venue_position("-0.2", "65000")]), None);
+
+    // A fill we never saw
+    assert_eq!(ledger.check(Leg::Hedge, &[venue_position("-0.25", "64990")]), Some(qty("-0.05")));
+    assert_eq!(ledger.leg(Leg::Hedge).size(), qty("-0.25"));
+    assert_eq!(ledger.leg(Leg::Hedge).entry_price(), Some(64990.0));
+
+    assert_eq!(ledger.check(Leg::Hedge, &[]), Some(qty("0.25")));
+    assert_eq!(ledger.leg(Leg::Hedge).entry_price(), None);
+}
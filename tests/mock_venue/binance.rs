@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+
+use rust_qts::decimal::{Price, Qty};
+use rust_qts::enums::{OrderType, Side};
+
+use super::http::{HttpRequest, HttpResponse};
+use super::{
+    sign, Injected, MockOrder, MockOrderStatus, MockPosition, MockTimeInForce, MockWebSocket, NewOrder,
+    OrderLookup, OrderReject, VenueEvent, VenueState,
+};
+
+/*
+    Binance USD-M futures wire format
+    https://binance-docs.github.io/apidocs/futures/en/
+*/
+
+
+/*
+    REST
+*/
+
+fn error(status: u16, code: i64, msg: &str) -> HttpResponse {
+    HttpResponse::json(status, json!({ "code": code, "msg": msg }))
+}
+
+fn reject_code(reject: OrderReject) -> (i64, &'static str) {
+    match reject {
+        OrderReject::UnknownSymbol => (-1121, "Invalid symbol."),
+        OrderReject::DuplicateClientId => (-4116, "ClientOrderId is duplicated."),
+        OrderReject::ReduceOnlyIncrease => (-2022, "ReduceOnly Order is rejected."),
+        OrderReject::InvalidQty => (-4003, "Quantity less than or equal to zero."),
+        OrderReject::InvalidPrice => (-4001, "Price less than 0."),
+        OrderReject::NotFound => (-2011, "Unknown order sent."),
+    }
+}
+
+pub(super) fn render_injected(_state: &VenueState, injected: Injected) -> HttpResponse {
+    match injected {
+        Injected::Code { code, msg } => error(if code == -1003 { 429 } else { 400 }, code, &msg),
+        Injected::Http { status, body } => HttpResponse { status, headers: Vec::new(), body },
+        Injected::RateLimit { retry_after_ms } => {
+            error(429, -1003, "Too many requests; current limit is 2400 requests per minute.")
+                .with_header("Retry-After", retry_after_ms.div_ceil(1000).to_string())
+        }
+    }
+}
+
+/// Drop the signature parameter, keeping the rest byte for byte
+fn strip_signature(raw: &str) -> (String, Option<String>) {
+    let mut signature = None;
+    let kept: Vec<&str> = raw.split('&').filter(|pair| {
+        match pair.strip_prefix("signature=") {
+            Some(value) => {
+                signature = Some(value.to_string());
+                false
+            }
+            None => !pair.is_empty(),
+        }
+    }).collect();
+    (kept.join("&"), signature)
+}
+
+/// Check X-MBX-APIKEY and the signature over the query string concatenated with the body
+fn verify(state: &VenueState, request: &HttpRequest, params: &HashMap<String, String>) -> Result<(), HttpResponse> {
+    if request.header("x-mbx-apikey") != Some(state.api_key.as_str()) {
+        return Err(error(401, -2015, "Invalid API-key, IP, or permissions for action."));
+    }
+
+    let timestamp: i64 = params.get("timestamp").and_then(|value| value.parse().ok()).unwrap_or(0);
+    let recv_window: i64 = params.get("recvWindow").and_then(|value| value.parse().ok()).unwrap_or(5000);
+    let now = state.now_ms();
+    if timestamp > now + 1000 || now - timestamp > recv_window {
+        return Err(error(400, -1021, "Timestamp for this request is outside of the recvWindow."));
+    }
+
+    let (query, query_signature) = strip_signature(&request.query);
+    let (body, body_signature) = strip_signature(&request.body);
+    let signature = query_signature.or(body_signature);
+    if signature.as_deref() != Some(sign(&state.api_secret, &format!("{}{}", query, body)).as_str()) {
+        return Err(error(400, -1022, "Signature for this request is not valid."));
+    }
+    Ok(())
+}
+
+pub(super) fn route(state: &mut VenueState, request: &HttpRequest) -> HttpResponse {
+    let params = request.params();
+    let public = matches!(request.path.as_str(), "/fapi/v1/depth" | "/fapi/v1/ticker/24hr");
+    if !public && let Err(response) = verify(state, request, &params) {
+        return response;
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/fapi/v1/depth") => depth(state, &params),
+        ("GET", "/fapi/v1/ticker/24hr") => ticker(state, &params),
+        ("GET", "/fapi/v3/positionRisk") => position_risk(state, &params),
+        ("GET", "/fapi/v1/openOrders") => open_orders(state, &params),
+        ("GET", "/fapi/v3/balance") => balance(state),
+        ("POST", "/fapi/v1/order") => new_order(state, &params),
+        ("PUT", "/fapi/v1/order") => modify_order(state, &params),
+        ("DELETE", "/fapi/v1/order") => cancel_order(state, &params),
+        ("DELETE", "/fapi/v1/allOpenOrders") => cancel_all(state, &params),
+        _ => error(404, -5000, "Path not found"),
+    }
+}
+
+fn symbol_param(params: &HashMap<String, String>) -> &str {
+    params.get("symbol").map(String::as_str).unwrap_or_default()
+}
+
+fn depth(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let Some(book) = state.books.get(symbol_param(params)) else {
+        return error(400, -1121, "Invalid symbol.");
+    };
+    let limit = params.get("limit").and_then(|limit| limit.parse().ok()).unwrap_or(500);
+    let now = state.now_ms();
+    HttpResponse::json(200, json!({
+        "lastUpdateId": book.update_id,
+        "E": now,
+        "T": now,
+        "bids": book.bid_levels(limit),
+        "asks": book.ask_levels(limit),
+    }))
+}
+
+fn ticker_json(state: &VenueState, symbol: &str) -> Value {
+    let book = &state.books[symbol];
+    let last_price = book.last_price.unwrap_or(book.mark_price()).to_string();
+    let now = state.now_ms();
+    json!({
+        "symbol": symbol,
+        "priceChange": "0",
+        "priceChangePercent": "0",
+        "weightedAvgPrice": last_price,
+        "lastPrice": last_price,
+        "lastQty": "0",
+        "openPrice": last_price,
+        "highPrice": last_price,
+        "lowPrice": last_price,
+        "volume": "0",
+        "quoteVolume": "0",
+        "openTime": now - 86_400_000,
+        "closeTime": now,
+        "firstId": 0,
+        "lastId": 0,
+        "count": 0,
+    })
+}
+
+fn ticker(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    // One object for a symbol, an array without
+    match params.get("symbol") {
+        Some(symbol) if state.books.contains_key(symbol) => HttpResponse::json(200, ticker_json(state, symbol)),
+        Some(_) => error(400, -1121, "Invalid symbol."),
+        None => {
+            let mut symbols: Vec<&String> = state.books.keys().collect();
+            symbols.sort();
+            HttpResponse::json(200, Value::Array(symbols.into_iter().map(|symbol| ticker_json(state, symbol)).collect()))
+        }
+    }
+}
+
+/// Share of the account margin is by notional, (initial, maintenance)
+fn position_json(symbol: &str, position: &MockPosition, mark_price: Price, margin: (f64, f64), updated_ms: i64) -> Value {
+    let pnl = (mark_price.to_f64() - position.entry_price.to_f64()) * position.size.to_f64();
+    json!({
+        "symbol": symbol,
+        "positionSide": "BOTH",
+        "positionAmt": position.size.to_string(),
+        "entryPrice": position.entry_price.to_string(),
+        "breakEvenPrice": position.entry_price.to_string(),
+        "markPrice": mark_price.to_string(),
+        "unRealizedProfit": pnl.to_string(),
+        "liquidationPrice": "0",
+        "isolatedMargin": "0",
+        "notional": (mark_price.to_f64() * position.size.to_f64()).to_string(),
+        "marginAsset": "USDT",
+        "isolatedWallet": "0",
+        "initialMargin": margin.0.to_string(),
+        "maintMargin": margin.1.to_string(),
+        "positionInitialMargin": "0",
+        "openOrderInitialMargin": "0",
+        "adl": 0,
+        "bidNotional": "0",
+        "askNotional": "0",
+        "updateTime": updated_ms,
+    })
+}
+
+fn position_risk(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    // v3 only returns symbols with an open position
+    let mut symbols: Vec<&String> = state.positions.iter()
+        .filter(|(symbol, position)| !position.size.is_zero() && params.get("symbol").is_none_or(|wanted| wanted == *symbol))
+        .map(|(symbol, _)| symbol)
+        .collect();
+    symbols.sort();
+
+    let notional = |symbol: &str| (state.mark_price(symbol).to_f64() * state.positions[symbol].size.to_f64()).abs();
+    let total: f64 = symbols.iter().map(|symbol| notional(symbol)).sum();
+    let (initial, maintenance) = state.margin;
+
+    let list: Vec<Value> = symbols.into_iter()
+        .map(|symbol| {
+            let share = if total > 0.0 { notional(symbol) / total } else { 0.0 };
+            position_json(symbol, &state.positions[symbol], state.mark_price(symbol), (initial * share, maintenance * share), state.now_ms())
+        })
+        .collect();
+    HttpResponse::json(200, Value::Array(list))
+}
+
+fn order_status(order: &MockOrder) -> &'static str {
+    match order.status {
+        MockOrderStatus::New => "NEW",
+        MockOrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+        MockOrderStatus::Filled => "FILLED",
+        MockOrderStatus::Cancelled if order.cancelled_by_user => "CANCELED",
+        MockOrderStatus::Cancelled => "EXPIRED",
+    }
+}
+
+/// Superset of the open order item and the order entry response
+fn order_json(order: &MockOrder) -> Value {
+    let order_type = if order.order_type == OrderType::Market { "MARKET" } else { "LIMIT" };
+    json!({
+        "avgPrice": order.avg_price().to_string(),
+        "clientOrderId": order.client_order_id,
+        "cumQty": order.filled_qty.to_string(),
+        "cumQuote": order.cum_value().to_string(),
+        "executedQty": order.filled_qty.to_string(),
+        "orderId": order.order_id,
+        "origQty": order.qty.to_string(),
+        "origType": order_type,
+        "price": order.price.unwrap_or(Price::ZERO).to_string(),
+        "reduceOnly": order.reduce_only,
+        "side": if order.side == Side::Buy { "BUY" } else { "SELL" },
+        "positionSide": "BOTH",
+        "status": order_status(order),
+        "stopPrice": "0",
+        "closePosition": false,
+        "symbol": order.symbol,
+        "time": order.created_ms,
+        "timeInForce": match order.time_in_force {
+            MockTimeInForce::Gtc => "GTC",
+            MockTimeInForce::Ioc => "IOC",
+            MockTimeInForce::Fok => "FOK",
+            MockTimeInForce::PostOnly => "GTX",
+        },
+        "type": order_type,
+        "updateTime": order.updated_ms,
+        "workingType": "CONTRACT_PRICE",
+        "priceProtect": false,
+        "priceMatch": "NONE",
+        "selfTradePreventionMode": "NONE",
+        "goodTillDate": 0,
+    })
+}
+
+fn open_orders(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let list: Vec<Value> = state.orders.iter()
+        .filter(|order| order.status.is_open() && params.get("symbol").is_none_or(|symbol| &order.symbol == symbol))
+        .map(order_json)
+        .collect();
+    HttpResponse::json(200, Value::Array(list))
+}
+
+fn balance(state: &VenueState) -> HttpResponse {
+    let list: Vec<Value> = state.balances.iter().map(|(asset, amount)| json!({
+        "accountAlias": "mock",
+        "asset": asset,
+        "balance": amount.to_string(),
+        "crossWalletBalance": amount.to_string(),
+        "crossUnPnl": "0",
+        "availableBalance": amount.to_string(),
+        "maxWithdrawAmount": amount.to_string(),
+        "marginAvailable": true,
+        "updateTime": state.now_ms(),
+    })).collect();
+    HttpResponse::json(200, Value::Array(list))
+}
+
+fn parse_new_order(params: &HashMap<String, String>) -> Result<NewOrder, HttpResponse> {
+    let illegal = |name: &str| error(400, -1102, &format!("Mandatory parameter '{}' was not sent, was empty/null, or malformed.", name));
+
+    let side = match params.get("side").map(String::as_str) {
+        Some("BUY") => Side::Buy,
+        Some("SELL") => Side::Sell,
+        _ => return Err(illegal("side")),
+    };
+    let order_type = match params.get("type").map(String::as_str) {
+        Some("MARKET") => OrderType::Market,
+        Some("LIMIT") => OrderType::Limit,
+        _ => return Err(illegal("type")),
+    };
+    let time_in_force = match params.get("timeInForce").map(String::as_str) {
+        None | Some("GTC") => MockTimeInForce::Gtc,
+        Some("IOC") => MockTimeInForce::Ioc,
+        Some("FOK") => MockTimeInForce::Fok,
+        Some("GTX") => MockTimeInForce::PostOnly,
+        Some(_) => return Err(illegal("timeInForce")),
+    };
+    let qty: Qty = params.get("quantity").and_then(|qty| qty.parse().ok()).ok_or_else(|| illegal("quantity"))?;
+    let price: Option<Price> = match params.get("price") {
+        Some(price) => Some(price.parse().map_err(|_| illegal("price"))?),
+        None if order_type == OrderType::Limit => return Err(illegal("price")),
+        None => None,
+    };
+
+    Ok(NewOrder {
+        symbol: symbol_param(params).to_string(),
+        side,
+        order_type,
+        time_in_force,
+        price,
+        qty,
+        client_order_id: params.get("newClientOrderId").cloned(),
+        reduce_only: params.get("reduceOnly").is_some_and(|value| value == "true"),
+    })
+}
+
+fn lookup(params: &HashMap<String, String>) -> OrderLookup {
+    OrderLookup {
+        order_id: params.get("orderId").and_then(|id| id.parse().ok()),
+        client_order_id: params.get("origClientOrderId").cloned(),
+    }
+}
+
+fn respond(result: Result<MockOrder, OrderReject>) -> HttpResponse {
+    match result {
+        Ok(order) => HttpResponse::json(200, order_json(&order)),
+        Err(reject) => {
+            let (code, msg) = reject_code(reject);
+            error(400, code, msg)
+        }
+    }
+}
+
+fn new_order(state: &mut VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let request = match parse_new_order(params) {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let order = match state.submit_order(request) {
+        Ok(order) => order,
+        Err(reject) => return respond(Err(reject)),
+    };
+
+    // Post-only and FOK orders the venue could not place are errors here, not expired orders
+    match order.reject_reason.as_str() {
+        "EC_PostOnlyWillTakeLiquidity" => error(400, -5022, "Due to the order could not be executed as maker, the Post Only order will be rejected. The order will not be recorded in the order history"),
+        "EC_FOKNotFilled" => error(400, -5021, "Due to the order could not be filled immediately, the FOK order has been rejected. The order will not be recorded in the order history"),
+        _ => respond(Ok(order)),
+    }
+}
+
+fn modify_order(state: &mut VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let qty = params.get("quantity").and_then(|qty| qty.parse().ok());
+    let price = params.get("price").and_then(|price| price.parse().ok());
+    respond(state.amend_order(symbol_param(params), &lookup(params), qty, price))
+}
+
+fn cancel_order(state: &mut VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    respond(state.cancel_order(symbol_param(params), &lookup(params)))
+}
+
+fn cancel_all(state: &mut VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    state.cancel_all(Some(symbol_param(params)));
+    HttpResponse::json(200, json!({ "code": 200, "msg": "The operation of cancel all open order is done." }))
+}
+
+
+/*
+    WebSocket, /ws with live SUBSCRIBE requests
+*/
+
+pub(super) async fn handle_ws(mut ws: MockWebSocket, _path: String, state: Arc<Mutex<VenueState>>) {
+    let mut events = state.lock().unwrap().events.subscribe();
+    let mut symbols: HashSet<String> = HashSet::new();      // lowercase, subscribed to <symbol>@depth
+    let mut trade_symbols: HashSet<String> = HashSet::new();    // lowercase, subscribed to <symbol>@aggTrade
+
+    loop {
+        let outgoing: Vec<String> = tokio::select! {
+            message = ws.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                };
+                let request: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+                let streams: Vec<String> = request.get("params").and_then(Value::as_array)
+                    .map(|params| params.iter().filter_map(|param| param.as_str().map(str::to_string)).collect())
+                    .unwrap_or_default();
+                let of_kind = |kind: &str| streams.iter()
+                    .filter_map(|stream| stream.split('@').nth(1).filter(|stream_kind| *stream_kind == kind).and(stream.split('@').next()))
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                let (depth_symbols, agg_trade_symbols) = (of_kind("depth"), of_kind("aggTrade"));
+
+                match request.get("method").and_then(Value::as_str) {
+                    Some("SUBSCRIBE") => {
+                        symbols.extend(depth_symbols);
+                        trade_symbols.extend(agg_trade_symbols);
+                    }
+                    Some("UNSUBSCRIBE") => {
+                        for symbol in depth_symbols {
+                            symbols.remove(&symbol);
+                        }
+                        for symbol in agg_trade_symbols {
+                            trade_symbols.remove(&symbol);
+                        }
+                    }
+                    _ => {}
+                }
+                vec![json!({ "result": null, "id": request.get("id").cloned().unwrap_or(Value::Null) }).to_string()]
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                match event {
+                    VenueEvent::Book { symbol, bids, asks, prev_update_id, update_id, .. } if symbols.contains(&symbol.to_lowercase()) => {
+                        let now = state.lock().unwrap().now_ms();
+                        vec![json!({
+                            "e": "depthUpdate",
+                            "E": now,
+                            "T": now,
+                            "s": symbol,
+                            "U": prev_update_id + 1,
+                            "u": update_id,
+                            "pu": prev_update_id,
+                            "b": bids,
+                            "a": asks,
+                        }).to_string()]
+                    }
+                    VenueEvent::Trade { symbol, taker_side, price, qty, trade_id, time } if trade_symbols.contains(&symbol.to_lowercase()) => {
+                        vec![json!({
+                            "e": "aggTrade",
+                            "E": time,
+                            "s": symbol,
+                            "a": trade_id,
+                            "p": price.to_string(),
+                            "q": qty.to_string(),
+                            "f": trade_id,
+                            "l": trade_id,
+                            "T": time,
+                            "m": taker_side == Side::Sell,
+                        }).to_string()]
+                    }
+                    VenueEvent::Disconnect => {
+                        let _ = ws.close(None).await;
+                        return;
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        };
+
+        for text in outgoing {
+            if ws.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
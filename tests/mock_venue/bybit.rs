@@ -0,0 +1,765 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+
+use rust_qts::decimal::{Price, Qty};
+use rust_qts::enums::{OrderType, Side};
+
+use super::http::{parse_params, HttpRequest, HttpResponse};
+use super::{
+    fill_strings, sign, Injected, MockFill, MockOrder, MockOrderStatus, MockPosition, MockTimeInForce,
+    MockWebSocket, NewOrder, OrderLookup, OrderReject, VenueEvent, VenueState, spawn_dcp_trigger,
+};
+
+/*
+    Bybit v5 wire format
+    https://bybit-exchange.github.io/docs/v5/intro
+*/
+
+const CONN_ID: &str = "mock-conn";
+
+// String fields of BybitOpenOrderItem the mock has no real value for
+const ORDER_FIELDS: &[&str] = &[
+    "blockTradeId", "isLeverage", "createType", "cancelType", "leavesValue", "orderIv", "marketUnit",
+    "triggerPrice", "takeProfit", "stopLoss", "tpslMode", "ocoTriggerBy", "tpLimitPrice", "slLimitPrice",
+    "tpTriggerBy", "slTriggerBy", "triggerBy", "lastPriceOnCreated", "basePrice", "placeType", "smpType",
+    "smpOrderId", "stopOrderType",
+];
+
+const POSITION_FIELDS: &[&str] = &[
+    "riskLimitValue", "positionStatus", "leverage", "positionBalance", "liqPrice", "bustPrice", "positionMM",
+    "positionMMByMp", "positionIM", "positionIMByMp", "tpslMode", "takeProfit", "stopLoss", "trailingStop",
+    "curRealisedPnl", "cumRealisedPnl", "mmrSysUpdateTime", "leverageSysUpdatedTime", "sessionAvgPrice",
+    "createdTime",
+];
+
+const TICKER_FIELDS: &[&str] = &[
+    "prevPrice24h", "price24hPcnt", "highPrice24h", "lowPrice24h", "prevPrice1h", "openInterest",
+    "openInterestValue", "turnover24h", "fundingRate", "nextFundingTime", "predictedDeliveryPrice", "basisRate",
+    "basis", "deliveryFeeRate", "deliveryTime", "preOpenPrice", "preQty", "curPreListingPhase",
+];
+
+const WALLET_FIELDS: &[&str] = &[
+    "accountIMRate", "accountIMRateByMp", "totalInitialMargin", "totalInitialMarginByMp", "accountMMRate",
+    "accountMMRateByMp", "totalPerpUPL", "accountLTV", "totalMaintenanceMargin", "totalMaintenanceMarginByMp",
+];
+
+const COIN_FIELDS: &[&str] = &[
+    "availableToBorrow", "bonus", "accruedInterest", "totalOrderIM", "totalPositionMM", "spotHedgingQty",
+    "unrealisedPnl", "borrowAmount", "totalPositionIM", "cumRealisedPnl", "locked",
+];
+
+
+/*
+    REST
+*/
+
+fn envelope(state: &VenueState, code: i64, msg: &str, result: Value, ext_info: Value) -> HttpResponse {
+    HttpResponse::json(200, json!({
+        "retCode": code,
+        "retMsg": msg,
+        "result": result,
+        "retExtInfo": ext_info,
+        "time": state.now_ms(),
+    }))
+}
+
+fn ok(state: &VenueState, result: Value) -> HttpResponse {
+    envelope(state, 0, "OK", result, json!({}))
+}
+
+fn error(state: &VenueState, code: i64, msg: &str) -> HttpResponse {
+    envelope(state, code, msg, json!({}), json!({}))
+}
+
+fn reject_code(reject: OrderReject) -> (i64, &'static str) {
+    match reject {
+        OrderReject::UnknownSymbol => (10001, "params error: symbol invalid"),
+        OrderReject::DuplicateClientId => (110072, "OrderLinkedID is duplicate"),
+        OrderReject::ReduceOnlyIncrease => (110017, "Reduce-only rule not satisfied"),
+        OrderReject::InvalidQty => (10001, "params error: qty invalid"),
+        OrderReject::InvalidPrice => (10001, "params error: price invalid"),
+        OrderReject::NotFound => (110001, "order not exists or too late to cancel"),
+    }
+}
+
+pub(super) fn render_injected(state: &VenueState, injected: Injected) -> HttpResponse {
+    match injected {
+        Injected::Code { code, msg } => error(state, code, &msg),
+        Injected::Http { status, body } => HttpResponse { status, headers: Vec::new(), body },
+        Injected::RateLimit { retry_after_ms } => {
+            let reset = chrono::Utc::now().timestamp_millis() + retry_after_ms as i64;
+            error(state, 10006, "Too many visits. Exceeded the API Rate Limit.")
+                .with_header("X-Bapi-Limit-Reset-Timestamp", reset.to_string())
+        }
+    }
+}
+
+/// Check the X-BAPI headers the way the venue does, GET signs the query and POST the body
+fn verify(state: &VenueState, request: &HttpRequest) -> Result<(), HttpResponse> {
+    if request.header("x-bapi-api-key") != Some(state.api_key.as_str()) {
+        return Err(error(state, 10003, "API key is invalid."));
+    }
+    let timestamp: i64 = request.header("x-bapi-timestamp").and_then(|value| value.parse().ok()).unwrap_or(0);
+    let recv_window: i64 = request.header("x-bapi-recv-window").and_then(|value| value.parse().ok()).unwrap_or(5000);
+
+    let now = state.now_ms();
+    if timestamp > now + 1000 || now - timestamp > recv_window {
+        return Err(error(state, 10002, &format!(
+            "invalid request, please check your server timestamp or recv_window param. req_timestamp[{}],server_timestamp[{}],recv_window[{}]",
+            timestamp, now, recv_window
+        )));
+    }
+
+    let payload = if request.method == "GET" { &request.query } else { &request.body };
+    let origin = format!("{}{}{}{}", timestamp, state.api_key, recv_window, payload);
+    if request.header("x-bapi-sign") != Some(sign(&state.api_secret, &origin).as_str()) {
+        return Err(error(state, 10004, &format!("error sign! origin_string[{}]", origin)));
+    }
+    Ok(())
+}
+
+pub(super) fn route(state: &mut VenueState, request: &HttpRequest) -> HttpResponse {
+    let private = ["/v5/account/", "/v5/position/", "/v5/order/"].iter().any(|prefix| request.path.starts_with(prefix));
+    if private && let Err(response) = verify(state, request) {
+        return response;
+    }
+
+    let params = parse_params(&request.query);
+    let body: Value = serde_json::from_str(&request.body).unwrap_or(Value::Null);
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/v5/market/orderbook") => orderbook(state, &params),
+        ("GET", "/v5/market/tickers") => tickers(state, &params),
+        ("GET", "/v5/account/info") => account_info(state),
+        ("GET", "/v5/account/wallet-balance") => wallet_balance(state, &params),
+        ("GET", "/v5/position/list") => position_list(state, &params),
+        ("GET", "/v5/order/realtime") => open_orders(state, &params),
+        ("POST", "/v5/order/create") => create_order(state, &body),
+        ("POST", "/v5/order/amend") => amend_order(state, &body),
+        ("POST", "/v5/order/cancel") => cancel_order(state, &body),
+        ("POST", "/v5/order/cancel-all") => cancel_all(state, &body),
+        ("POST", "/v5/order/create-batch") => batch(state, &body, create_order),
+        ("POST", "/v5/order/amend-batch") => batch(state, &body, amend_order),
+        ("POST", "/v5/order/cancel-batch") => batch(state, &body, cancel_order),
+        ("POST", "/v5/order/disconnected-cancel-all") => set_dcp(state, &body),
+        _ => HttpResponse::json(404, json!({})),
+    }
+}
+
+fn orderbook(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let symbol = params.get("symbol").map(String::as_str).unwrap_or_default();
+    let Some(book) = state.books.get(symbol) else {
+        return error(state, 10001, "params error: symbol invalid");
+    };
+    let depth = params.get("limit").and_then(|limit| limit.parse().ok()).unwrap_or(25);
+    let now = state.now_ms();
+    ok(state, json!({
+        "s": symbol,
+        "b": book.bid_levels(depth),
+        "a": book.ask_levels(depth),
+        "ts": now,
+        "u": book.update_id,
+        "seq": book.seq,
+        "cts": now,
+    }))
+}
+
+fn tickers(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let mut symbols: Vec<&String> = state.books.keys()
+        .filter(|symbol| params.get("symbol").is_none_or(|wanted| wanted == *symbol))
+        .collect();
+    symbols.sort();
+
+    let list: Vec<Value> = symbols.into_iter().map(|symbol| {
+        let book = &state.books[symbol];
+        let bid = book.bid_levels(1).first().copied();
+        let ask = book.ask_levels(1).first().copied();
+        let mut item = json!({
+            "symbol": symbol,
+            "lastPrice": book.last_price.unwrap_or(book.mark_price()).to_string(),
+            "indexPrice": book.mark_price().to_string(),
+            "markPrice": book.mark_price().to_string(),
+            "volume24h": "0",
+            "bid1Price": bid.map(|(price, _)| price.to_string()).unwrap_or_default(),
+            "bid1Size": bid.map(|(_, qty)| qty.to_string()).unwrap_or_default(),
+            "ask1Price": ask.map(|(price, _)| price.to_string()).unwrap_or_default(),
+            "ask1Size": ask.map(|(_, qty)| qty.to_string()).unwrap_or_default(),
+        });
+        fill_strings(&mut item, TICKER_FIELDS);
+        item
+    }).collect();
+
+    ok(state, json!({ "category": params.get("category").cloned().unwrap_or_default(), "list": list }))
+}
+
+fn account_info(state: &VenueState) -> HttpResponse {
+    ok(state, json!({
+        "marginMode": "REGULAR_MARGIN",
+        "updatedTime": state.now_ms().to_string(),
+        "unifiedMarginStatus": 4,
+        "dcpStatus": if state.dcp.is_some() { "ON" } else { "OFF" },
+        "timeWindow": state.dcp.as_ref().map_or(10, |(_, window)| *window),
+        "smpGroup": 0,
+        "isMasterTrader": false,
+        "spotHedgingStatus": "OFF",
+    }))
+}
+
+fn wallet_balance(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let coins: Vec<Value> = state.balances.iter()
+        .filter(|(coin, _)| params.get("coin").is_none_or(|wanted| wanted.split(',').any(|wanted| wanted == coin)))
+        .map(|(coin, amount)| {
+            let mut item = json!({
+                "coin": coin,
+                "walletBalance": amount.to_string(),
+                "equity": amount.to_string(),
+                "usdValue": amount.to_string(),
+                "availableToWithdraw": amount.to_string(),
+                "collateralSwitch": true,
+                "marginCollateral": true,
+            });
+            fill_strings(&mut item, COIN_FIELDS);
+            item
+        }).collect();
+
+    let total: Qty = state.balances.iter().map(|(_, amount)| *amount).sum();
+    let (initial, maintenance) = state.margin;
+    let mut account = json!({
+        "accountType": params.get("accountType").cloned().unwrap_or_else(|| "UNIFIED".to_string()),
+        "totalInitialMargin": initial.to_string(),
+        "totalMaintenanceMargin": maintenance.to_string(),
+        "accountIMRate": (initial / total.to_f64()).to_string(),
+        "accountMMRate": (maintenance / total.to_f64()).to_string(),
+        "totalEquity": total.to_string(),
+        "totalMarginBalance": total.to_string(),
+        "totalAvailableBalance": total.to_string(),
+        "totalWalletBalance": total.to_string(),
+        "coin": coins,
+    });
+    fill_strings(&mut account, WALLET_FIELDS);
+    ok(state, json!({ "list": [account] }))
+}
+
+fn position_json(symbol: &str, position: &MockPosition, mark_price: Price, updated_ms: i64) -> Value {
+    let side = if position.size.is_positive() { "Buy" } else if position.size.is_negative() { "Sell" } else { "" };
+    let size = position.size.abs();
+    let pnl = (mark_price.to_f64() - position.entry_price.to_f64()) * position.size.to_f64();
+    let mut item = json!({
+        "category": "linear",
+        "symbol": symbol,
+        "side": side,
+        "size": size.to_string(),
+        "positionIdx": 0,
+        "riskId": 1,
+        "avgPrice": position.entry_price.to_string(),
+        "entryPrice": position.entry_price.to_string(),
+        "markPrice": mark_price.to_string(),
+        "positionValue": position.entry_price.notional(size).to_string(),
+        "unrealisedPnl": pnl.to_string(),
+        "leverage": "10",
+        "positionStatus": "Normal",
+        "tradeMode": 0,
+        "autoAddMargin": 0,
+        "adlRankIndicator": 0,
+        "seq": position.seq,
+        "isReduceOnly": false,
+        "updatedTime": updated_ms.to_string(),
+    });
+    fill_strings(&mut item, POSITION_FIELDS);
+    item
+}
+
+fn position_list(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    // A symbol query always gets its (possibly flat) position, a settleCoin query only open ones
+    let list: Vec<Value> = match params.get("symbol") {
+        Some(symbol) => vec![position_json(symbol, &state.position(symbol), state.mark_price(symbol), state.now_ms())],
+        None => {
+            let mut symbols: Vec<&String> = state.positions.iter()
+                .filter(|(_, position)| !position.size.is_zero())
+                .map(|(symbol, _)| symbol)
+                .collect();
+            symbols.sort();
+            symbols.into_iter().map(|symbol| position_json(symbol, &state.positions[symbol], state.mark_price(symbol), state.now_ms())).collect()
+        }
+    };
+    ok(state, json!({
+        "list": list,
+        "nextPageCursor": "",
+        "category": params.get("category").cloned().unwrap_or_default(),
+    }))
+}
+
+fn order_status(order: &MockOrder) -> &'static str {
+    match order.status {
+        MockOrderStatus::New => "New",
+        MockOrderStatus::PartiallyFilled => "PartiallyFilled",
+        MockOrderStatus::Filled => "Filled",
+        MockOrderStatus::Cancelled if !order.filled_qty.is_zero() => "PartiallyFilledCanceled",
+        MockOrderStatus::Cancelled => "Cancelled",
+    }
+}
+
+fn side_str(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "Buy",
+        Side::Sell => "Sell",
+    }
+}
+
+/// Superset of the REST open order item and the WebSocket order message
+fn order_json(order: &MockOrder) -> Value {
+    let mut item = json!({
+        "category": "linear",
+        "orderId": order.order_id.to_string(),
+        "orderLinkId": order.client_order_id,
+        "symbol": order.symbol,
+        "price": order.price.unwrap_or(Price::ZERO).to_string(),
+        "qty": order.qty.to_string(),
+        "side": side_str(order.side),
+        "positionIdx": 0,
+        "orderStatus": order_status(order),
+        "cancelType": if order.cancelled_by_user { "CancelByUser" } else { "UNKNOWN" },
+        "rejectReason": order.reject_reason,
+        "avgPrice": order.avg_price().to_string(),
+        "leavesQty": order.leaves_qty().to_string(),
+        "cumExecQty": order.filled_qty.to_string(),
+        "cumExecValue": order.cum_value().to_string(),
+        "cumExecFee": "0",
+        "timeInForce": match order.time_in_force {
+            MockTimeInForce::Gtc => "GTC",
+            MockTimeInForce::Ioc => "IOC",
+            MockTimeInForce::Fok => "FOK",
+            MockTimeInForce::PostOnly => "PostOnly",
+        },
+        "orderType": if order.order_type == OrderType::Market { "Market" } else { "Limit" },
+        "triggerDirection": 0,
+        "reduceOnly": order.reduce_only,
+        "closeOnTrigger": false,
+        "smpGroup": 0,
+        "createdTime": order.created_ms.to_string(),
+        "updatedTime": order.updated_ms.to_string(),
+    });
+    fill_strings(&mut item, ORDER_FIELDS);
+    item
+}
+
+fn open_orders(state: &VenueState, params: &HashMap<String, String>) -> HttpResponse {
+    let lookup = OrderLookup {
+        order_id: params.get("orderId").and_then(|id| id.parse().ok()),
+        client_order_id: params.get("orderLinkId").cloned(),
+    };
+    let filtered = lookup.order_id.is_some() || lookup.client_order_id.is_some();
+
+    // Pages of 20 unless asked for up to 50, the cursor is the offset of the next page
+    let limit = match params.get("limit").map(|limit| limit.parse::<usize>()) {
+        None => 20,
+        Some(Ok(limit)) if (1..=50).contains(&limit) => limit,
+        Some(_) => return error(state, 10001, "params error: limit invalid"),
+    };
+    let offset = params.get("cursor").and_then(|cursor| cursor.parse::<usize>().ok()).unwrap_or(0);
+
+    // Newest first, like the venue
+    let open: Vec<Value> = state.orders.iter().rev()
+        .filter(|order| order.status.is_open())
+        .filter(|order| params.get("symbol").is_none_or(|symbol| &order.symbol == symbol))
+        .filter(|order| !filtered
+            || lookup.order_id == Some(order.order_id)
+            || lookup.client_order_id.as_deref() == Some(order.client_order_id.as_str()))
+        .map(order_json)
+        .collect();
+    let list: Vec<Value> = open.iter().skip(offset).take(limit).cloned().collect();
+    let next = if offset + limit >= open.len() {
+        String::new()
+    } else if state.stuck_cursor && offset > 0 {
+        offset.to_string()
+    } else {
+        (offset + limit).to_string()
+    };
+
+    ok(state, json!({
+        "category": params.get("category").cloned().unwrap_or_default(),
+        "nextPageCursor": next,
+        "list": list,
+    }))
+}
+
+fn parse_new_order(body: &Value) -> Result<NewOrder, (i64, String)> {
+    let text = |field: &str| body.get(field).and_then(Value::as_str);
+    let invalid = |field: &str| (10001, format!("params error: {} invalid", field));
+
+    let side = match text("side") {
+        Some("Buy") => Side::Buy,
+        Some("Sell") => Side::Sell,
+        _ => return Err(invalid("side")),
+    };
+    let order_type = match text("orderType") {
+        Some("Market") => OrderType::Market,
+        Some("Limit") => OrderType::Limit,
+        _ => return Err(invalid("orderType")),
+    };
+    let time_in_force = match text("timeInForce") {
+        None | Some("GTC") => MockTimeInForce::Gtc,
+        Some("IOC") => MockTimeInForce::Ioc,
+        Some("FOK") => MockTimeInForce::Fok,
+        Some("PostOnly") => MockTimeInForce::PostOnly,
+        Some(_) => return Err(invalid("timeInForce")),
+    };
+    let qty: Qty = text("qty").and_then(|qty| qty.parse().ok()).ok_or_else(|| invalid("qty"))?;
+    let price: Option<Price> = match text("price") {
+        Some(price) => Some(price.parse().map_err(|_| invalid("price"))?),
+        None => None,
+    };
+
+    Ok(NewOrder {
+        symbol: text("symbol").unwrap_or_default().to_string(),
+        side,
+        order_type,
+        time_in_force,
+        price,
+        qty,
+        client_order_id: text("orderLinkId").map(str::to_string),
+        reduce_only: body.get("reduceOnly").and_then(Value::as_bool).unwrap_or(false),
+    })
+}
+
+fn lookup(body: &Value) -> OrderLookup {
+    OrderLookup {
+        order_id: body.get("orderId").and_then(Value::as_str).and_then(|id| id.parse().ok()),
+        client_order_id: body.get("orderLinkId").and_then(Value::as_str).map(str::to_string),
+    }
+}
+
+fn symbol_of(body: &Value) -> &str {
+    body.get("symbol").and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Outcome of one order request, shared by the single and batch endpoints
+fn order_ack(result: Result<MockOrder, OrderReject>) -> Result<Value, (i64, String)> {
+    match result {
+        Ok(order) => Ok(json!({ "orderId": order.order_id.to_string(), "orderLinkId": order.client_order_id })),
+        Err(reject) => {
+            let (code, msg) = reject_code(reject);
+            Err((code, msg.to_string()))
+        }
+    }
+}
+
+fn respond(state: &VenueState, result: Result<Value, (i64, String)>) -> HttpResponse {
+    match result {
+        Ok(value) => ok(state, value),
+        Err((code, msg)) => error(state, code, &msg),
+    }
+}
+
+fn create_order(state: &mut VenueState, body: &Value) -> HttpResponse {
+    let result = parse_new_order(body).and_then(|request| order_ack(state.submit_order(request)));
+    respond(state, result)
+}
+
+fn amend_order(state: &mut VenueState, body: &Value) -> HttpResponse {
+    let qty = body.get("qty").and_then(Value::as_str).and_then(|qty| qty.parse().ok());
+    let price = body.get("price").and_then(Value::as_str).and_then(|price| price.parse().ok());
+    let result = order_ack(state.amend_order(symbol_of(body), &lookup(body), qty, price));
+    respond(state, result)
+}
+
+fn cancel_order(state: &mut VenueState, body: &Value) -> HttpResponse {
+    let result = order_ack(state.cancel_order(symbol_of(body), &lookup(body)));
+    respond(state, result)
+}
+
+fn cancel_all(state: &mut VenueState, body: &Value) -> HttpResponse {
+    let cancelled = state.cancel_all(body.get("symbol").and_then(Value::as_str));
+    let list: Vec<Value> = cancelled.iter()
+        .map(|order| json!({ "orderId": order.order_id.to_string(), "orderLinkId": order.client_order_id }))
+        .collect();
+    ok(state, json!({ "list": list, "success": "1" }))
+}
+
+fn set_dcp(state: &mut VenueState, body: &Value) -> HttpResponse {
+    let product = body.get("product").and_then(Value::as_str).unwrap_or("OPTIONS").to_string();
+    let window = body.get("timeWindow").and_then(Value::as_u64).unwrap_or(0);
+    if !(3..=300).contains(&window) || !["OPTIONS", "DERIVATIVES", "SPOT"].contains(&product.as_str()) {
+        return error(state, 10001, "params error");
+    }
+    state.dcp = Some((product, window as u32));
+    ok(state, json!({}))
+}
+
+/// Run each item through the single order handler, statuses go in retExtInfo in request order
+fn batch(state: &mut VenueState, body: &Value, handler: fn(&mut VenueState, &Value) -> HttpResponse) -> HttpResponse {
+    let category = body.get("category").and_then(Value::as_str).unwrap_or_default().to_string();
+    let items = body.get("request").and_then(Value::as_array).cloned().unwrap_or_default();
+
+    let mut list = Vec::new();
+    let mut statuses = Vec::new();
+    for item in &items {
+        let response: Value = serde_json::from_str(&handler(state, item).body).expect("mock writes JSON");
+        let result = &response["result"];
+        list.push(json!({
+            "category": category,
+            "symbol": symbol_of(item),
+            "orderId": result.get("orderId").cloned().unwrap_or(json!("")),
+            "orderLinkId": result.get("orderLinkId").cloned()
+                .or_else(|| item.get("orderLinkId").cloned())
+                .unwrap_or(json!("")),
+            "createAt": state.now_ms().to_string(),
+        }));
+        statuses.push(json!({ "code": response["retCode"], "msg": response["retMsg"] }));
+    }
+
+    envelope(state, 0, "OK", json!({ "list": list }), json!({ "list": statuses }))
+}
+
+
+/*
+    WebSocket, /v5/public/<category> and /v5/private
+*/
+
+fn execution_json(fill: &MockFill) -> Value {
+    json!({
+        "category": "linear",
+        "symbol": fill.order.symbol,
+        "execId": format!("mock-exec-{}", fill.exec_id),
+        "orderId": fill.order.order_id.to_string(),
+        "orderLinkId": fill.order.client_order_id,
+        "side": side_str(fill.order.side),
+        "orderPrice": fill.order.price.unwrap_or(Price::ZERO).to_string(),
+        "orderQty": fill.order.qty.to_string(),
+        "leavesQty": fill.order.leaves_qty().to_string(),
+        "execPrice": fill.price.to_string(),
+        "execQty": fill.qty.to_string(),
+        "execValue": fill.price.notional(fill.qty).to_string(),
+        "execFee": "0",
+        "feeRate": "0",
+        "execType": "Trade",
+        "isMaker": fill.is_maker,
+        "execTime": fill.time.to_string(),
+        "seq": fill.exec_id as i64,
+    })
+}
+
+fn book_message(topic: &str, data_type: &str, symbol: &str, bids: &[(Price, Qty)], asks: &[(Price, Qty)], (update_id, seq): (u64, u64), now: i64) -> String {
+    json!({
+        "topic": topic,
+        "type": data_type,
+        "ts": now,
+        "data": { "s": symbol, "b": bids, "a": asks, "u": update_id, "seq": seq },
+        "cts": now,
+    }).to_string()
+}
+
+fn private_message(topic: &str, data: Value, now: i64) -> String {
+    json!({
+        "id": format!("mock-{}", now),
+        "topic": topic,
+        "creationTime": now,
+        "data": [data],
+    }).to_string()
+}
+
+/// Topics the private stream serves, "order" for all categories or "order.linear" for one
+fn is_private_topic(topic: &str) -> bool {
+    match topic.split_once('.') {
+        None => ["order", "execution", "position", "wallet"].contains(&topic),
+        Some(("dcp", product)) => ["future", "spot", "option"].contains(&product),
+        Some((name, category)) => {
+            ["order", "execution", "position"].contains(&name) && ["spot", "linear", "inverse", "option"].contains(&category)
+        }
+    }
+}
+
+/// An orderbook subscription on this connection
+struct BookTopic {
+    symbol: String,
+    last_update_id: u64,
+}
+
+pub(super) async fn handle_ws(ws: MockWebSocket, path: String, state: Arc<Mutex<VenueState>>) {
+    let mut topics: HashSet<String> = HashSet::new();
+    serve_connection(ws, path, &state, &mut topics).await;
+    // However the connection ended, it no longer holds dcp
+    let mut guard = state.lock().unwrap();
+    if guard.dcp_topic().is_some_and(|topic| topics.contains(topic)) && let Some(trigger) = guard.dcp_released() {
+        spawn_dcp_trigger(&state, trigger);
+    }
+}
+
+async fn serve_connection(mut ws: MockWebSocket, path: String, shared: &Arc<Mutex<VenueState>>, topics: &mut HashSet<String>) {
+    let state = shared;
+    let private = path.ends_with("/private");
+    let mut events = state.lock().unwrap().events.subscribe();
+    let mut authed = false;
+    let mut books: HashMap<String, BookTopic> = HashMap::new();
+
+    loop {
+        let outgoing: Vec<String> = tokio::select! {
+            message = ws.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                };
+                let request: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+                let args: Vec<String> = request.get("args").and_then(Value::as_array)
+                    .map(|args| args.iter().filter_map(|arg| arg.as_str().map(str::to_string)).collect())
+                    .unwrap_or_default();
+                let mut state = shared.lock().unwrap();
+                let now = state.now_ms();
+
+                match request.get("op").and_then(Value::as_str) {
+                    Some("ping") if state.mute_pongs => Vec::new(),
+                    Some("ping") => vec![json!({
+                        "success": true,
+                        "ret_msg": "pong",
+                        "conn_id": CONN_ID,
+                        "req_id": request.get("req_id").cloned().unwrap_or(Value::Null),
+                        "op": "ping",
+                    }).to_string()],
+                    Some("auth") => {
+                        // args: api key, expires, hex HMAC of GET/realtime{expires}
+                        let ret_msg = match args.as_slice() {
+                            [key, _, _] if key != &state.api_key => "Invalid apikey",
+                            [_, expires, _] if expires.parse::<i64>().unwrap_or(0) < now => "Params Error: expires is expired",
+                            [_, expires, signature] if signature == &sign(&state.api_secret, &format!("GET/realtime{}", expires)) => "",
+                            _ => "Invalid sign",
+                        };
+                        authed = private && ret_msg.is_empty();
+                        vec![json!({ "success": authed, "ret_msg": ret_msg, "op": "auth", "conn_id": CONN_ID }).to_string()]
+                    }
+                    Some("subscribe") if private && !authed => vec![json!({
+                        "success": false, "ret_msg": "Request not authorized", "op": "subscribe", "conn_id": CONN_ID,
+                    }).to_string()],
+                    Some("subscribe") if private => {
+                        // Every topic must exist or the whole request fails, a dcp topic only holds DCP
+                        // when it is the one of the product DCP is on
+                        if let Some(unknown) = args.iter().find(|topic| !is_private_topic(topic)) {
+                            vec![json!({
+                                "success": false,
+                                "ret_msg": format!("error:handler not found,topic:{}", unknown),
+                                "conn_id": CONN_ID,
+                                "op": "subscribe",
+                            }).to_string()]
+                        } else {
+                            if let Some(dcp_topic) = state.dcp_topic() && args.iter().any(|topic| topic == dcp_topic) && !topics.contains(dcp_topic) {
+                                state.dcp_subscribed();
+                            }
+                            topics.extend(args);
+                            vec![json!({ "success": true, "ret_msg": "", "op": "subscribe", "conn_id": CONN_ID }).to_string()]
+                        }
+                    }
+                    Some("subscribe") => {
+                        // orderbook.{depth}.{symbol} or publicTrade.{symbol}, every topic must exist or the
+                        // whole request fails
+                        let parsed: Vec<Option<(String, Option<usize>, String)>> = args.iter().map(|topic| {
+                            match topic.split('.').collect::<Vec<_>>().as_slice() {
+                                ["orderbook", depth, symbol] if state.books.contains_key(*symbol) => {
+                                    depth.parse().ok().map(|depth| (topic.clone(), Some(depth), symbol.to_string()))
+                                }
+                                ["publicTrade", symbol] if state.books.contains_key(*symbol) => Some((topic.clone(), None, symbol.to_string())),
+                                _ => None,
+                            }
+                        }).collect();
+
+                        if let Some(index) = parsed.iter().position(Option::is_none) {
+                            vec![json!({
+                                "success": false,
+                                "ret_msg": format!("error:handler not found,topic:{}", args[index]),
+                                "conn_id": CONN_ID,
+                                "op": "subscribe",
+                                "args": args,
+                            }).to_string()]
+                        } else {
+                            let mut replies = vec![json!({
+                                "success": true, "ret_msg": "", "conn_id": CONN_ID, "op": "subscribe", "args": args,
+                            }).to_string()];
+                            // A new subscription always starts with a snapshot
+                            for (topic, depth, symbol) in parsed.into_iter().flatten() {
+                                let Some(depth) = depth else {
+                                    topics.insert(topic);
+                                    continue;
+                                };
+                                let book = &state.books[&symbol];
+                                replies.push(book_message(&topic, "snapshot", &symbol, &book.bid_levels(depth), &book.ask_levels(depth), (book.update_id, book.seq), now));
+                                books.insert(topic, BookTopic { symbol, last_update_id: book.update_id });
+                            }
+                            replies
+                        }
+                    }
+                    Some("unsubscribe") => {
+                        if let Some(dcp_topic) = state.dcp_topic() && args.iter().any(|topic| topic == dcp_topic) && topics.contains(dcp_topic)
+                            && let Some(trigger) = state.dcp_released() {
+                            spawn_dcp_trigger(shared, trigger);
+                        }
+                        for topic in &args {
+                            books.remove(topic);
+                            topics.remove(topic);
+                        }
+                        vec![json!({ "success": true, "ret_msg": "", "conn_id": CONN_ID, "op": "unsubscribe", "args": args }).to_string()]
+                    }
+                    _ => vec![json!({ "success": false, "ret_msg": format!("unknown request: {}", text), "conn_id": CONN_ID, "op": "" }).to_string()],
+                }
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                let now = shared.lock().unwrap().now_ms();
+
+                match event {
+                    VenueEvent::Book { symbol, bids, asks, update_id, seq, .. } => books.iter_mut()
+                        .filter(|(_, book)| book.symbol == symbol && book.last_update_id < update_id)
+                        .map(|(topic, book)| {
+                            book.last_update_id = update_id;
+                            book_message(topic, "delta", &symbol, &bids, &asks, (update_id, seq), now)
+                        })
+                        .collect(),
+                    VenueEvent::Trade { symbol, taker_side, price, qty, trade_id, time } if topics.contains(&format!("publicTrade.{}", symbol)) => {
+                        vec![json!({
+                            "topic": format!("publicTrade.{}", symbol),
+                            "type": "snapshot",
+                            "ts": now,
+                            "data": [{
+                                "T": time,
+                                "s": symbol,
+                                "S": side_str(taker_side),
+                                "v": qty.to_string(),
+                                "p": price.to_string(),
+                                "L": "ZeroPlusTick",
+                                "i": trade_id.to_string(),
+                                "BT": false,
+                            }],
+                        }).to_string()]
+                    }
+                    VenueEvent::Order(order) if topics.contains("order") => vec![private_message("order", order_json(&order), now)],
+                    VenueEvent::Execution(fill) if topics.contains("execution") => vec![private_message("execution", execution_json(&fill), now)],
+                    VenueEvent::Position { symbol, position, mark_price } if topics.contains("position") => {
+                        vec![private_message("position", position_json(&symbol, &position, mark_price, now), now)]
+                    }
+                    VenueEvent::Disconnect => {
+                        let _ = ws.close(None).await;
+                        return;
+                    }
+                    VenueEvent::DisconnectPrivate if private => {
+                        let _ = ws.close(None).await;
+                        return;
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        };
+
+        for text in outgoing {
+            if ws.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
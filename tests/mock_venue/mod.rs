@@ -0,0 +1,952 @@
+// Shared by several test crates, each one only uses part of it
+#![allow(dead_code)]
+
+/*
+    In-process mock venue for integration tests
+
+    Speaks the Bybit v5 or Binance USD-M futures REST and WebSocket formats modelled in the
+    response.rs files, on two localhost ports (REST and WebSocket). Signed requests are checked
+    with the same HMAC scheme the venue uses, so a bad key, bad signature or a timestamp outside
+    recvWindow comes back with the venue's own error code.
+
+    Orders match against a scripted book set with `set_book`: takers sweep the scripted levels,
+    post-only orders that would cross are cancelled, and resting orders fill in full at their own
+    price once a later `set_book` trades through them. Resting orders are not shown in the book.
+    Public trades are only the ones scripted with `print_trade`, our own fills don't print.
+
+        let venue = MockVenue::start(Exchanges::Bybit).await;
+        venue.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "2")]);
+        let client = venue.rest_client();
+*/
+
+mod binance;
+mod bybit;
+mod http;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::WebSocketStream;
+
+use rust_qts::data_structure::Symbol;
+use rust_qts::decimal::{Price, Qty};
+use rust_qts::enums::{Environment, Exchanges, InstrumentType, OrderType, Side};
+use rust_qts::exchange::base_rest::{ExchangeRest, RestClientBuilder};
+use rust_qts::exchange::binance::binance_rest::BinanceRestClient;
+use rust_qts::exchange::bybit::bybit_rest::BybitRestClient;
+
+pub use http::HttpRequest;
+use http::HttpResponse;
+
+pub const MOCK_API_KEY: &str = "mock-api-key";
+pub const MOCK_API_SECRET: &str = "mock-api-secret";
+
+/// Tick and lot of every scripted instrument
+pub const MOCK_TICK: &str = "0.1";
+pub const MOCK_QTY_DECIMAL: u32 = 3;
+
+/// How long the helpers wait for an expected message
+pub const WAIT: Duration = Duration::from_secs(5);
+
+
+/*
+    Orders, fills and positions
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockOrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+}
+
+impl MockOrderStatus {
+    pub fn is_open(self) -> bool {
+        matches!(self, MockOrderStatus::New | MockOrderStatus::PartiallyFilled)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockTimeInForce {
+    Gtc,
+    Ioc,
+    Fok,
+    PostOnly,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockOrder {
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub time_in_force: MockTimeInForce,
+    pub price: Option<Price>,
+    pub qty: Qty,
+    pub filled_qty: Qty,
+    pub cum_value_raw: i128,        // sum of price.raw() * qty.raw() over the fills
+    pub status: MockOrderStatus,
+    pub reduce_only: bool,
+    pub reject_reason: String,      // Bybit spelling, EC_NoError unless the venue cancelled it
+    pub cancelled_by_user: bool,
+    pub created_ms: i64,
+    pub updated_ms: i64,
+}
+
+impl MockOrder {
+    pub fn leaves_qty(&self) -> Qty {
+        if self.status.is_open() { self.qty - self.filled_qty } else { Qty::ZERO }
+    }
+
+    pub fn avg_price(&self) -> Price {
+        if self.filled_qty.is_zero() {
+            return Price::ZERO;
+        }
+        Price::from_raw((self.cum_value_raw / self.filled_qty.raw() as i128) as i64)
+    }
+
+    pub fn cum_value(&self) -> f64 {
+        self.cum_value_raw as f64 / 1e16
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockFill {
+    pub exec_id: u64,
+    pub order: MockOrder,           // order state right after this fill
+    pub price: Price,
+    pub qty: Qty,
+    pub is_maker: bool,
+    pub time: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockPosition {
+    pub size: Qty,                  // signed, negative when short
+    pub entry_price: Price,
+    pub seq: i64,
+}
+
+/// Venue neutral order request, each venue module parses its own wire format into this
+#[derive(Debug, Clone)]
+struct NewOrder {
+    symbol: String,
+    side: Side,
+    order_type: OrderType,
+    time_in_force: MockTimeInForce,
+    price: Option<Price>,
+    qty: Qty,
+    client_order_id: Option<String>,
+    reduce_only: bool,
+}
+
+/// Why the venue refused an order request outright
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OrderReject {
+    UnknownSymbol,
+    DuplicateClientId,
+    ReduceOnlyIncrease,
+    InvalidQty,
+    InvalidPrice,
+    NotFound,
+}
+
+/// Find an order by venue id or client id
+#[derive(Debug, Clone, Default)]
+struct OrderLookup {
+    order_id: Option<u64>,
+    client_order_id: Option<String>,
+}
+
+
+/*
+    Book, events and failure injection
+*/
+
+#[derive(Debug, Clone, Default)]
+struct Book {
+    bids: BTreeMap<Price, Qty>,
+    asks: BTreeMap<Price, Qty>,
+    update_id: u64,
+    seq: u64,
+    last_price: Option<Price>,
+}
+
+impl Book {
+    fn best_bid(&self) -> Option<Price> {
+        self.bids.keys().next_back().copied()
+    }
+
+    fn best_ask(&self) -> Option<Price> {
+        self.asks.keys().next().copied()
+    }
+
+    fn mark_price(&self) -> Price {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Price::from_raw((bid.raw() + ask.raw()) / 2),
+            (Some(price), None) | (None, Some(price)) => price,
+            (None, None) => self.last_price.unwrap_or(Price::ZERO),
+        }
+    }
+
+    /// Bids best first
+    fn bid_levels(&self, depth: usize) -> Vec<(Price, Qty)> {
+        self.bids.iter().rev().take(depth).map(|(price, qty)| (*price, *qty)).collect()
+    }
+
+    /// Asks best first
+    fn ask_levels(&self, depth: usize) -> Vec<(Price, Qty)> {
+        self.asks.iter().take(depth).map(|(price, qty)| (*price, *qty)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum VenueEvent {
+    Book { symbol: String, bids: Vec<(Price, Qty)>, asks: Vec<(Price, Qty)>, prev_update_id: u64, update_id: u64, seq: u64 },
+    Order(MockOrder),
+    Execution(MockFill),
+    Position { symbol: String, position: MockPosition, mark_price: Price },
+    Trade { symbol: String, taker_side: Side, price: Price, qty: Qty, trade_id: u64, time: i64 },
+    Disconnect,
+    DisconnectPrivate,
+}
+
+#[derive(Debug, Clone)]
+enum Injected {
+    /// Venue error code, in the venue's own envelope
+    Code { code: i64, msg: String },
+    /// Raw status and body, e.g. a gateway error page
+    Http { status: u16, body: String },
+    /// The venue's rate limit reply with its back off hint
+    RateLimit { retry_after_ms: u64 },
+}
+
+struct VenueState {
+    exchange: Exchanges,
+    api_key: String,
+    api_secret: String,
+    books: HashMap<String, Book>,
+    orders: Vec<MockOrder>,
+    positions: HashMap<String, MockPosition>,
+    balances: Vec<(String, Qty)>,
+    margin: (f64, f64),                     // account initial and maintenance margin, in USDT
+    requests: Vec<HttpRequest>,
+    injected: VecDeque<Injected>,
+    clock_offset_ms: i64,
+    skip_book_updates: u32,
+    mute_pongs: bool,
+    stuck_cursor: bool,                     // open order pages hand back the cursor they were asked for
+    dcp: Option<(String, u32)>,             // disconnect-cancel-all product and window in seconds
+    dcp_connections: u32,                   // private connections subscribed to the dcp topic of the product
+    dcp_epoch: u64,                         // bumped on every dcp subscription, a pending trigger only fires if unchanged
+    next_order_id: u64,
+    next_exec_id: u64,
+    events: broadcast::Sender<VenueEvent>,
+}
+
+impl VenueState {
+    fn new(exchange: Exchanges) -> Self {
+        let (events, _) = broadcast::channel(4096);
+        VenueState {
+            exchange,
+            api_key: MOCK_API_KEY.to_string(),
+            api_secret: MOCK_API_SECRET.to_string(),
+            books: HashMap::new(),
+            orders: Vec::new(),
+            positions: HashMap::new(),
+            balances: vec![("USDT".to_string(), "100000".parse().unwrap())],
+            margin: (0.0, 0.0),
+            requests: Vec::new(),
+            injected: VecDeque::new(),
+            clock_offset_ms: 0,
+            skip_book_updates: 0,
+            mute_pongs: false,
+            stuck_cursor: false,
+            dcp: None,
+            dcp_connections: 0,
+            dcp_epoch: 0,
+            next_order_id: 1,
+            next_exec_id: 1,
+            events,
+        }
+    }
+
+    /// Venue clock, shifted by `clock_offset_ms` to simulate drift
+    fn now_ms(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis() + self.clock_offset_ms
+    }
+
+    fn publish(&self, event: VenueEvent) {
+        // No receivers just means no WebSocket is connected
+        let _ = self.events.send(event);
+    }
+
+    fn mark_price(&self, symbol: &str) -> Price {
+        self.books.get(symbol).map(|book| book.mark_price()).unwrap_or(Price::ZERO)
+    }
+
+    fn position(&self, symbol: &str) -> MockPosition {
+        self.positions.get(symbol).copied().unwrap_or_default()
+    }
+
+    fn set_book(&mut self, symbol: &str, bids: BTreeMap<Price, Qty>, asks: BTreeMap<Price, Qty>) {
+        let book = self.books.entry(symbol.to_string()).or_default();
+        let bid_changes = diff_levels(&book.bids, &bids);
+        let ask_changes = diff_levels(&book.asks, &asks);
+        book.bids = bids;
+        book.asks = asks;
+
+        self.publish_book(symbol, bid_changes, ask_changes);
+        self.cross_resting_orders(symbol);
+    }
+
+    /// Move the book ids forward and broadcast the changed levels
+    fn publish_book(&mut self, symbol: &str, bids: Vec<(Price, Qty)>, asks: Vec<(Price, Qty)>) {
+        if bids.is_empty() && asks.is_empty() {
+            return;
+        }
+        let book = self.books.get_mut(symbol).expect("book exists");
+        let prev_update_id = book.update_id;
+        book.update_id += 1;
+        book.seq += 1;
+        let (update_id, seq) = (book.update_id, book.seq);
+
+        // A skipped update leaves a gap in the ids the stream sends
+        if self.skip_book_updates > 0 {
+            self.skip_book_updates -= 1;
+            return;
+        }
+        self.publish(VenueEvent::Book { symbol: symbol.to_string(), bids, asks, prev_update_id, update_id, seq });
+    }
+
+    fn find_open(&self, symbol: &str, lookup: &OrderLookup) -> Option<usize> {
+        self.orders.iter().position(|order| {
+            order.symbol == symbol
+                && order.status.is_open()
+                && (lookup.order_id == Some(order.order_id)
+                    || lookup.client_order_id.as_deref() == Some(order.client_order_id.as_str()))
+        })
+    }
+
+    fn submit_order(&mut self, request: NewOrder) -> Result<MockOrder, OrderReject> {
+        if !self.books.contains_key(&request.symbol) {
+            return Err(OrderReject::UnknownSymbol);
+        }
+        if !request.qty.is_positive() {
+            return Err(OrderReject::InvalidQty);
+        }
+        if request.order_type == OrderType::Limit && !request.price.is_some_and(|price| price.is_positive()) {
+            return Err(OrderReject::InvalidPrice);
+        }
+        if let Some(client_order_id) = &request.client_order_id
+            && self.orders.iter().any(|order| &order.client_order_id == client_order_id) {
+            return Err(OrderReject::DuplicateClientId);
+        }
+
+        // Reduce-only must shrink the position, and is capped at its size
+        let mut qty = request.qty;
+        if request.reduce_only {
+            let position = self.position(&request.symbol).size;
+            let reduces = match request.side {
+                Side::Buy => position.is_negative(),
+                Side::Sell => position.is_positive(),
+            };
+            if !reduces {
+                return Err(OrderReject::ReduceOnlyIncrease);
+            }
+            qty = qty.min(position.abs());
+        }
+
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        let now = self.now_ms();
+        self.orders.push(MockOrder {
+            order_id,
+            client_order_id: request.client_order_id.unwrap_or_else(|| format!("mock-{}", order_id)),
+            symbol: request.symbol,
+            side: request.side,
+            order_type: request.order_type,
+            // Market orders never rest
+            time_in_force: if request.order_type == OrderType::Market { MockTimeInForce::Ioc } else { request.time_in_force },
+            price: request.price,
+            qty,
+            filled_qty: Qty::ZERO,
+            cum_value_raw: 0,
+            status: MockOrderStatus::New,
+            reduce_only: request.reduce_only,
+            reject_reason: "EC_NoError".to_string(),
+            cancelled_by_user: false,
+            created_ms: now,
+            updated_ms: now,
+        });
+
+        let index = self.orders.len() - 1;
+        self.match_order(index);
+        Ok(self.orders[index].clone())
+    }
+
+    fn amend_order(&mut self, symbol: &str, lookup: &OrderLookup, qty: Option<Qty>, price: Option<Price>) -> Result<MockOrder, OrderReject> {
+        let index = self.find_open(symbol, lookup).ok_or(OrderReject::NotFound)?;
+        if let Some(qty) = qty
+            && qty <= self.orders[index].filled_qty {
+            return Err(OrderReject::InvalidQty);
+        }
+        if price.is_some_and(|price| !price.is_positive()) {
+            return Err(OrderReject::InvalidPrice);
+        }
+
+        let now = self.now_ms();
+        let order = &mut self.orders[index];
+        if let Some(qty) = qty {
+            order.qty = qty;
+        }
+        if price.is_some() {
+            order.price = price;
+        }
+        order.updated_ms = now;
+
+        // A new price can cross the book, same rules as a new order
+        self.match_order(index);
+        Ok(self.orders[index].clone())
+    }
+
+    fn cancel_order(&mut self, symbol: &str, lookup: &OrderLookup) -> Result<MockOrder, OrderReject> {
+        let index = self.find_open(symbol, lookup).ok_or(OrderReject::NotFound)?;
+        self.cancel_at(index, true);
+        Ok(self.orders[index].clone())
+    }
+
+    fn cancel_all(&mut self, symbol: Option<&str>) -> Vec<MockOrder> {
+        let indexes: Vec<usize> = (0..self.orders.len())
+            .filter(|&index| self.orders[index].status.is_open() && symbol.is_none_or(|symbol| self.orders[index].symbol == symbol))
+            .collect();
+        indexes.into_iter().map(|index| {
+            self.cancel_at(index, true);
+            self.orders[index].clone()
+        }).collect()
+    }
+
+    /// Private topic that holds DCP for the product it is set on
+    fn dcp_topic(&self) -> Option<&'static str> {
+        match self.dcp.as_ref()?.0.as_str() {
+            "DERIVATIVES" => Some("dcp.future"),
+            "SPOT" => Some("dcp.spot"),
+            _ => Some("dcp.option"),
+        }
+    }
+
+    fn dcp_subscribed(&mut self) {
+        self.dcp_connections += 1;
+        self.dcp_epoch += 1;
+    }
+
+    /// A dcp connection went away
+    ///
+    /// Returns the window and epoch to arm the trigger with when it was the last one and DCP is on
+    fn dcp_released(&mut self) -> Option<(u32, u64)> {
+        self.dcp_connections = self.dcp_connections.saturating_sub(1);
+        let (_, window) = self.dcp.clone()?;
+        (self.dcp_connections == 0).then_some((window, self.dcp_epoch))
+    }
+
+    fn cancel_at(&mut self, index: usize, by_user: bool) {
+        let now = self.now_ms();
+        let order = &mut self.orders[index];
+        order.status = MockOrderStatus::Cancelled;
+        order.cancelled_by_user = by_user;
+        order.updated_ms = now;
+        let order = order.clone();
+        self.publish(VenueEvent::Order(order));
+    }
+
+    /// Run an order against the scripted book: post-only and FOK checks, sweep, then rest or expire
+    fn match_order(&mut self, index: usize) {
+        let order = self.orders[index].clone();
+        let book = &self.books[&order.symbol];
+        let remaining = order.qty - order.filled_qty;
+
+        // Opposite side levels this order is allowed to take, best first
+        let crosses = |level: Price| match (order.side, order.price) {
+            (_, None) => true,
+            (Side::Buy, Some(limit)) => level <= limit,
+            (Side::Sell, Some(limit)) => level >= limit,
+        };
+        let takeable: Vec<(Price, Qty)> = match order.side {
+            Side::Buy => book.ask_levels(usize::MAX),
+            Side::Sell => book.bid_levels(usize::MAX),
+        }.into_iter().take_while(|(price, _)| crosses(*price)).collect();
+
+        if order.time_in_force == MockTimeInForce::PostOnly && !takeable.is_empty() {
+            self.orders[index].reject_reason = "EC_PostOnlyWillTakeLiquidity".to_string();
+            self.cancel_at(index, false);
+            return;
+        }
+        if order.time_in_force == MockTimeInForce::Fok && takeable.iter().map(|(_, qty)| *qty).sum::<Qty>() < remaining {
+            self.orders[index].reject_reason = "EC_FOKNotFilled".to_string();
+            self.cancel_at(index, false);
+            return;
+        }
+
+        // Sweep the scripted levels
+        let mut left = remaining;
+        let mut fills = Vec::new();
+        for (price, available) in takeable {
+            if left.is_zero() {
+                break;
+            }
+            let qty = left.min(available);
+            fills.push((price, qty, available - qty));
+            left -= qty;
+        }
+
+        if !fills.is_empty() {
+            let changes: Vec<(Price, Qty)> = fills.iter().map(|(price, _, rest)| (*price, *rest)).collect();
+            let book = self.books.get_mut(&order.symbol).expect("book exists");
+            let side = if order.side == Side::Buy { &mut book.asks } else { &mut book.bids };
+            for (price, rest) in &changes {
+                if rest.is_zero() {
+                    side.remove(price);
+                } else {
+                    side.insert(*price, *rest);
+                }
+            }
+            let (bids, asks) = if order.side == Side::Buy { (Vec::new(), changes) } else { (changes, Vec::new()) };
+            self.publish_book(&order.symbol, bids, asks);
+
+            for (price, qty, _) in fills {
+                self.apply_fill(index, price, qty, false);
+            }
+        }
+
+        let order = &self.orders[index];
+        if order.status.is_open() && matches!(order.time_in_force, MockTimeInForce::Ioc | MockTimeInForce::Fok) {
+            self.orders[index].reject_reason = "EC_NoImmediateQtyToFill".to_string();
+            self.cancel_at(index, false);
+        } else {
+            let order = order.clone();
+            self.publish(VenueEvent::Order(order));
+        }
+    }
+
+    /// Fill resting orders in full once the scripted book trades through their price
+    fn cross_resting_orders(&mut self, symbol: &str) {
+        let book = &self.books[symbol];
+        let (best_bid, best_ask) = (book.best_bid(), book.best_ask());
+        let crossed: Vec<usize> = (0..self.orders.len()).filter(|&index| {
+            let order = &self.orders[index];
+            order.symbol == symbol && order.status.is_open() && match (order.side, order.price) {
+                (Side::Buy, Some(price)) => best_ask.is_some_and(|ask| ask <= price),
+                (Side::Sell, Some(price)) => best_bid.is_some_and(|bid| bid >= price),
+                _ => false,
+            }
+        }).collect();
+
+        for index in crossed {
+            let order = &self.orders[index];
+            let (price, qty) = (order.price.expect("resting orders have a price"), order.qty - order.filled_qty);
+            self.apply_fill(index, price, qty, true);
+            let order = self.orders[index].clone();
+            self.publish(VenueEvent::Order(order));
+        }
+    }
+
+    /// Book a fill against an order and the position, and push the execution and position updates
+    fn apply_fill(&mut self, index: usize, price: Price, qty: Qty, is_maker: bool) {
+        let now = self.now_ms();
+        let order = &mut self.orders[index];
+        order.filled_qty += qty;
+        order.cum_value_raw += price.raw() as i128 * qty.raw() as i128;
+        order.status = if order.filled_qty == order.qty { MockOrderStatus::Filled } else { MockOrderStatus::PartiallyFilled };
+        order.updated_ms = now;
+        let order = order.clone();
+
+        // Position: weighted entry when adding, unchanged when reducing, fill price when flipping
+        let signed = if order.side == Side::Buy { qty } else { -qty };
+        let position = self.positions.entry(order.symbol.clone()).or_default();
+        let size = position.size + signed;
+        if position.size.is_zero() || position.size.is_negative() == signed.is_negative() {
+            let value = position.entry_price.raw() as i128 * position.size.abs().raw() as i128 + price.raw() as i128 * qty.raw() as i128;
+            position.entry_price = Price::from_raw((value / size.abs().raw() as i128) as i64);
+        } else if size.is_zero() {
+            position.entry_price = Price::ZERO;
+        } else if size.is_negative() != position.size.is_negative() {
+            position.entry_price = price;
+        }
+        position.size = size;
+        position.seq += 1;
+        let position = *position;
+
+        if let Some(book) = self.books.get_mut(&order.symbol) {
+            book.last_price = Some(price);
+        }
+
+        let exec_id = self.next_exec_id;
+        self.next_exec_id += 1;
+        let mark_price = self.mark_price(&order.symbol);
+        let symbol = order.symbol.clone();
+        self.publish(VenueEvent::Execution(MockFill { exec_id, order, price, qty, is_maker, time: now }));
+        self.publish(VenueEvent::Position { symbol, position, mark_price });
+    }
+}
+
+/// Levels that differ between two books, a size of zero removes the level
+fn diff_levels(old: &BTreeMap<Price, Qty>, new: &BTreeMap<Price, Qty>) -> Vec<(Price, Qty)> {
+    let mut changes: Vec<(Price, Qty)> = old.keys()
+        .filter(|price| !new.contains_key(price))
+        .map(|price| (*price, Qty::ZERO))
+        .collect();
+    changes.extend(new.iter()
+        .filter(|(price, qty)| old.get(price) != Some(qty))
+        .map(|(price, qty)| (*price, *qty)));
+    changes.sort();
+    changes
+}
+
+fn parse_levels(levels: &[(&str, &str)]) -> BTreeMap<Price, Qty> {
+    levels.iter().map(|(price, qty)| (price.parse().unwrap(), qty.parse().unwrap())).collect()
+}
+
+/// Hex HMAC-SHA256, the signature both venues use
+fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Default every listed field that is missing to an empty string
+fn fill_strings(value: &mut Value, fields: &[&str]) {
+    if let Value::Object(map) = value {
+        for field in fields {
+            map.entry(field.to_string()).or_insert_with(|| Value::String(String::new()));
+        }
+    }
+}
+
+
+/*
+    Servers
+*/
+
+fn handle_http(state: &Mutex<VenueState>, request: HttpRequest) -> HttpResponse {
+    let mut state = state.lock().unwrap();
+    state.requests.push(request.clone());
+
+    if let Some(injected) = state.injected.pop_front() {
+        return match state.exchange {
+            Exchanges::Binance => binance::render_injected(&state, injected),
+            _ => bybit::render_injected(&state, injected),
+        };
+    }
+    match state.exchange {
+        Exchanges::Binance => binance::route(&mut state, &request),
+        _ => bybit::route(&mut state, &request),
+    }
+}
+
+async fn serve_ws(listener: TcpListener, state: Arc<Mutex<VenueState>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut path = String::new();
+            // The handshake callback signature is fixed by tungstenite
+            #[allow(clippy::result_large_err)]
+            let callback = |request: &Request, response: Response| {
+                path = request.uri().path().to_string();
+                Ok(response)
+            };
+            let Ok(ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await else {
+                return;
+            };
+            let exchange = state.lock().unwrap().exchange.clone();
+            match exchange {
+                Exchanges::Binance => binance::handle_ws(ws, path, state).await,
+                _ => bybit::handle_ws(ws, path, state).await,
+            }
+        });
+    }
+}
+
+type MockWebSocket = WebSocketStream<TcpStream>;
+
+/// Cancel every open order once the window has passed without a dcp connection coming back
+fn spawn_dcp_trigger(state: &Arc<Mutex<VenueState>>, (window, epoch): (u32, u64)) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(window as u64)).await;
+        let mut state = state.lock().unwrap();
+        if state.dcp_connections > 0 || state.dcp_epoch != epoch {
+            return;
+        }
+        let indexes: Vec<usize> = (0..state.orders.len()).filter(|&index| state.orders[index].status.is_open()).collect();
+        for index in indexes {
+            state.cancel_at(index, false);
+        }
+    });
+}
+
+
+/*
+    Test facing handle
+*/
+
+pub struct MockVenue {
+    exchange: Exchanges,
+    rest_addr: SocketAddr,
+    ws_addr: SocketAddr,
+    state: Arc<Mutex<VenueState>>,
+}
+
+impl MockVenue {
+    /// Start a venue on ephemeral localhost ports, accepting MOCK_API_KEY / MOCK_API_SECRET
+    pub async fn start(exchange: Exchanges) -> MockVenue {
+        let state = Arc::new(Mutex::new(VenueState::new(exchange.clone())));
+
+        let rest_listener = TcpListener::bind("127.0.0.1:0").await.expect("bind REST port");
+        let ws_listener = TcpListener::bind("127.0.0.1:0").await.expect("bind WebSocket port");
+        let rest_addr = rest_listener.local_addr().unwrap();
+        let ws_addr = ws_listener.local_addr().unwrap();
+
+        let http_state = state.clone();
+        tokio::spawn(http::serve(rest_listener, move |request| handle_http(&http_state, request)));
+        tokio::spawn(serve_ws(ws_listener, state.clone()));
+
+        MockVenue { exchange, rest_addr, ws_addr, state }
+    }
+
+    pub fn exchange(&self) -> Exchanges {
+        self.exchange.clone()
+    }
+
+    /// Base URL for Environment::Custom
+    pub fn rest_url(&self) -> String {
+        format!("http://{}", self.rest_addr)
+    }
+
+    /// Bybit: base for `BybitWebSocketConfig.base_url`, Binance: the full stream URL
+    pub fn ws_url(&self) -> String {
+        match self.exchange {
+            Exchanges::Binance => format!("ws://{}/ws", self.ws_addr),
+            _ => format!("ws://{}/v5", self.ws_addr),
+        }
+    }
+
+    pub fn builder(&self) -> RestClientBuilder {
+        RestClientBuilder::new(MOCK_API_KEY.to_string(), MOCK_API_SECRET.to_string())
+            .environment(Environment::Custom(self.rest_url()))
+            .timeout(WAIT)
+    }
+
+    pub fn rest_client(&self) -> Box<dyn ExchangeRest> {
+        self.builder().build(&self.exchange).expect("build client")
+    }
+
+    pub fn bybit_client(&self) -> BybitRestClient {
+        self.builder().build_bybit().expect("build client")
+    }
+
+    pub fn binance_client(&self) -> BinanceRestClient {
+        self.builder().build_binance().expect("build client")
+    }
+
+    /// Instrument on this venue with the mock tick and lot
+    pub fn symbol(&self, symbol: &str) -> Symbol {
+        Symbol {
+            symbol: symbol.to_string(),
+            exchange: self.exchange.clone(),
+            instrument_type: InstrumentType::Perp,
+            min_tick: MOCK_TICK.parse().unwrap(),
+            qty_decimal: MOCK_QTY_DECIMAL,
+        }
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut VenueState) -> R) -> R {
+        f(&mut self.state.lock().unwrap())
+    }
+
+    /// Replace the scripted book, streams get the changed levels as one update
+    pub fn set_book(&self, symbol: &str, bids: &[(&str, &str)], asks: &[(&str, &str)]) {
+        let (bids, asks) = (parse_levels(bids), parse_levels(asks));
+        self.with_state(|state| state.set_book(symbol, bids, asks));
+    }
+
+    /// Print a public trade on the trade streams, the book and our orders are left as they are
+    pub fn print_trade(&self, symbol: &str, taker_side: Side, price: &str, qty: &str) {
+        let (price, qty) = (price.parse().unwrap(), qty.parse().unwrap());
+        self.with_state(|state| {
+            let (trade_id, time) = (state.next_exec_id, state.now_ms());
+            state.next_exec_id += 1;
+            state.publish(VenueEvent::Trade { symbol: symbol.to_string(), taker_side, price, qty, trade_id, time });
+        });
+    }
+
+    /// Signed size, negative for short
+    pub fn set_position(&self, symbol: &str, size: &str, entry_price: &str) {
+        let (size, entry_price) = (size.parse().unwrap(), entry_price.parse().unwrap());
+        self.with_state(|state| {
+            let position = state.positions.entry(symbol.to_string()).or_default();
+            position.size = size;
+            position.entry_price = entry_price;
+        });
+    }
+
+    pub fn set_balance(&self, coin: &str, amount: &str) {
+        let amount = amount.parse().unwrap();
+        self.with_state(|state| {
+            state.balances.retain(|(existing, _)| existing != coin);
+            state.balances.push((coin.to_string(), amount));
+        });
+    }
+
+    /// Margin the account's positions use, reported as rates on Bybit and per position on Binance
+    pub fn set_margin(&self, initial: f64, maintenance: f64) {
+        self.with_state(|state| state.margin = (initial, maintenance));
+    }
+
+    /// Answer the next request with this venue error code
+    pub fn fail_next(&self, code: i64, msg: &str) {
+        self.with_state(|state| state.injected.push_back(Injected::Code { code, msg: msg.to_string() }));
+    }
+
+    /// Answer the next request with a raw status and body
+    pub fn fail_next_http(&self, status: u16, body: &str) {
+        self.with_state(|state| state.injected.push_back(Injected::Http { status, body: body.to_string() }));
+    }
+
+    /// Answer the next request with the venue's rate limit reply
+    pub fn rate_limit_next(&self, retry_after_ms: u64) {
+        self.with_state(|state| state.injected.push_back(Injected::RateLimit { retry_after_ms }));
+    }
+
+    /// Shift the venue clock, signed requests older than recvWindow get rejected
+    pub fn set_clock_offset_ms(&self, offset_ms: i64) {
+        self.with_state(|state| state.clock_offset_ms = offset_ms);
+    }
+
+    /// Swallow the next `count` book updates so streams see a sequence gap
+    pub fn skip_book_updates(&self, count: u32) {
+        self.with_state(|state| state.skip_book_updates += count);
+    }
+
+    /// Stop answering application level pings
+    pub fn mute_pongs(&self, muted: bool) {
+        self.with_state(|state| state.mute_pongs = muted);
+    }
+
+    /// Page open orders with a cursor that never moves past the second page
+    pub fn stick_cursor(&self) {
+        self.with_state(|state| state.stuck_cursor = true);
+    }
+
+    /// Close every open WebSocket connection, new connections are still accepted
+    pub fn drop_connections(&self) {
+        self.with_state(|state| state.publish(VenueEvent::Disconnect));
+    }
+
+    /// Disconnect-cancel-all product and window, once set
+    pub fn dcp(&self) -> Option<(String, u32)> {
+        self.with_state(|state| state.dcp.clone())
+    }
+
+    /// Private connections currently subscribed to the dcp topic of the product DCP is on
+    pub fn dcp_connections(&self) -> u32 {
+        self.with_state(|state| state.dcp_connections)
+    }
+
+    /// Close only the private WebSocket connections, market data keeps streaming
+    pub fn drop_private_connections(&self) {
+        self.with_state(|state| state.publish(VenueEvent::DisconnectPrivate));
+    }
+
+    /// Fill a resting order as maker at its own price
+    pub fn fill_order(&self, client_order_id: &str, qty: &str) {
+        let qty: Qty = qty.parse().unwrap();
+        self.with_state(|state| {
+            let index = state.orders.iter()
+                .position(|order| order.client_order_id == client_order_id && order.status.is_open())
+                .unwrap_or_else(|| panic!("no open order {}", client_order_id));
+            let order = &state.orders[index];
+            let (price, qty) = (order.price.expect("resting orders have a price"), qty.min(order.qty - order.filled_qty));
+            state.apply_fill(index, price, qty, true);
+            let order = state.orders[index].clone();
+            state.publish(VenueEvent::Order(order));
+        });
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        self.with_state(|state| state.orders.clone())
+    }
+
+    pub fn open_orders(&self, symbol: &str) -> Vec<MockOrder> {
+        self.with_state(|state| state.orders.iter().filter(|order| order.symbol == symbol && order.status.is_open()).cloned().collect())
+    }
+
+    pub fn order(&self, client_order_id: &str) -> Option<MockOrder> {
+        self.with_state(|state| state.orders.iter().find(|order| order.client_order_id == client_order_id).cloned())
+    }
+
+    pub fn position(&self, symbol: &str) -> MockPosition {
+        self.with_state(|state| state.position(symbol))
+    }
+
+    /// Best bid and ask of the scripted book
+    pub fn top_of_book(&self, symbol: &str) -> (Option<Price>, Option<Price>) {
+        self.with_state(|state| state.books.get(symbol).map(|book| (book.best_bid(), book.best_ask())).unwrap_or((None, None)))
+    }
+
+    /// Every REST request received so far, in order
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.with_state(|state| state.requests.clone())
+    }
+
+    pub fn requests_to(&self, path: &str) -> Vec<HttpRequest> {
+        self.requests().into_iter().filter(|request| request.path == path).collect()
+    }
+
+    /// Orders in each batch request sent to `path`, in the order the requests came in
+    pub fn batch_sizes(&self, path: &str) -> Vec<usize> {
+        self.requests_to(path).iter().map(|request| {
+            let body: Value = serde_json::from_str(&request.body).unwrap_or(Value::Null);
+            body.get("request").and_then(Value::as_array).map_or(0, Vec::len)
+        }).collect()
+    }
+}
+
+
+/// Venue with two BTCUSDT levels a side, 65000 / 65000.5 at the touch
+pub async fn venue(exchange: Exchanges) -> MockVenue {
+    let venue = MockVenue::start(exchange).await;
+    venue.set_book("BTCUSDT", &[("65000", "1"), ("64999.9", "2")], &[("65000.5", "1"), ("65001", "2")]);
+    venue
+}
+
+/// Quote leg on a Bybit mock, hedge leg on a Binance mock quoting a dollar higher
+pub async fn venues() -> (MockVenue, MockVenue) {
+    let bybit = MockVenue::start(Exchanges::Bybit).await;
+    bybit.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "1")]);
+    let binance = MockVenue::start(Exchanges::Binance).await;
+    binance.set_book("BTCUSDT", &[("65001", "1")], &[("65001.5", "1")]);
+    (bybit, binance)
+}
+
+
+/// Wait for the next message matching `predicate`, skipping the rest
+pub async fn next_matching<T: std::fmt::Debug>(receiver: &mut mpsc::UnboundedReceiver<T>, mut predicate: impl FnMut(&T) -> bool) -> T {
+    tokio::time::timeout(WAIT, async {
+        loop {
+            let message = receiver.recv().await.expect("channel closed");
+            if predicate(&message) {
+                return message;
+            }
+        }
+    }).await.expect("timed out waiting for message")
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use common::{price, qty};
+use rust_qts::data_structure::{QTSFill, QTSOpenOrder, QTSOrderAck, QTSOrderRequest, Symbol};
+use rust_qts::enums::{Exchanges, InstrumentType, OrderType, Side, TimeInForce};
+use rust_qts::exchange::error::ExchangeError;
+use rust_qts::oms::{new_client_order_id, OrderManager, OrderState, MAX_CLIENT_ORDER_ID_LEN, RECONCILE_GRACE_MS};
+use rust_qts::strategy::eye::quoter::{quote_level, quote_order_id};
+
+fn symbol() -> Symbol {
+    Symbol {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Bybit,
+        instrument_type: InstrumentType::Perp,
+        min_tick: price("0.1"),
+        qty_decimal: 3,
+    }
+}
+
+fn request(client_order_id: &str) -> QTSOrderRequest {
+    QTSOrderRequest {
+        side: Side::Buy,
+        order_type: OrderType::Limit,
+        price: Some(price("65000")),
+        qty: qty("0.010"),
+        time_in_force: TimeInForce::PostOnly,
+        client_order_id: client_order_id.to_string(),
+        reduce_only: false,
+    }
+}
+
+fn ack(client_order_id: &str) -> QTSOrderAck {
+    QTSOrderAck { exchange: Exchanges::Bybit, order_id: format!("venue-{}", client_order_id), client_order_id: client_order_id.to_string() }
+}
+
+fn fill(client_order_id: &str, size: &str, filled: &str) -> QTSFill {
+    QTSFill {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Bybit,
+        order_id: format!("venue-{}", client_order_id),
+        client_order_id: client_order_id.to_string(),
+        side: Side::Buy,
+        price: price("65000"),
+        qty: qty(size),
+        filled_qty: qty(filled),
+        fee: 0.0,
+        is_maker: true,
+        time: 0,
+    }
+}
+
+fn open(client_order_id: &str, filled: &str) -> QTSOpenOrder {
+    QTSOpenOrder {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Bybit,
+        order_id: format!("venue-{}", client_order_id),
+        client_order_id: client_order_id.to_string(),
+        side: Side::Buy,
+        price: price("65000"),
+        qty: qty("0.010"),
+        filled_qty: qty(filled),
+        status: "New".to_string(),
+        reduce_only: false,
+        time: 0,
+    }
+}
+
+fn state(oms: &OrderManager, client_order_id: &str) -> OrderState {
+    oms.order(client_order_id).unwrap().state
+}
+
+#[test]
+fn client_order_ids_fit_both_venues() {
+    let id = new_client_order_id("eh-");
+    assert!(id.starts_with("eh-"));
+    assert!(id.len() <= MAX_CLIENT_ORDER_ID_LEN);
+    assert_ne!(id, new_client_order_id("eh-"));
+
+    let id = quote_order_id(Side::Sell, 12);
+    assert!(id.len() <= MAX_CLIENT_ORDER_ID_LEN);
+    assert_eq!(quote_level(&id), Some((Side::Sell, 12)));
+}
+
+#[test]
+fn order_goes_from_pending_new_to_filled() {
+    let mut oms = OrderManager::new();
+    oms.track(&symbol(), &request("ee-b0-1"), 0);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::PendingNew);
+
+    oms.on_place_result("ee-b0-1", &Ok(ack("ee-b0-1")), 1);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::New);
+    assert_eq!(oms.order("ee-b0-1").unwrap().order_id.as_deref(), Some("venue-ee-b0-1"));
+
+    oms.on_fill(&fill("ee-b0-1", "0.004", "0.004"), 2);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::PartiallyFilled);
+    assert_eq!(oms.working_orders(&symbol()).len(), 1);
+
+    oms.on_fill(&fill("ee-b0-1", "0.006", "0.010"), 3);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::Filled);
+    assert!(oms.working_orders(&symbol()).is_empty());
+}
+
+#[test]
+fn stream_ahead_of_the_ack_wins() {
+    let mut oms = OrderManager::new();
+    oms.track(&symbol(), &request("ee-b0-1"), 0);
+
+    // Fill streamed before the REST ack came back
+    oms.on_fill(&fill("ee-b0-1", "0.004", "0.004"), 1);
+    oms.on_place_result("ee-b0-1", &Ok(ack("ee-b0-1")), 2);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::PartiallyFilled);
+
+    // Closed on the stream, then the ack of our cancel
+    assert_eq!(oms.on_closed("ee-b0-1", "Cancelled", qty("0.004"), 3), Some(OrderState::PartiallyFilled));
+    oms.on_cancel_result("ee-b0-1", &Ok(ack("ee-b0-1")), 4);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::Cancelled);
+    assert_eq!(oms.order("ee-b0-1").unwrap().filled_qty, qty("0.004"));
+}
+
+#[test]
+fn fill_after_the_cancel_ack_still_counts() {
+    let mut oms = OrderManager::new();
+    oms.track(&symbol(), &request("ee-b0-1"), 0);
+    oms.on_place_result("ee-b0-1", &Ok(ack("ee-b0-1")), 1);
+
+    oms.on_cancel_sent("ee-b0-1", 2);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::PendingCancel);
+    assert_eq!(oms.working_orders(&symbol()).len(), 1);
+
+    // The venue filled it while the cancel was in flight and refused the cancel
+    let refused = Err(ExchangeError::Rejected { exchange: Exchanges::Bybit, code: 110001, msg: "order not exists".to_string() });
+    oms.on_cancel_result("ee-b0-1", &refused, 3);
+    oms.on_fill(&fill("ee-b0-1", "0.010", "0.010"), 4);
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::Filled);
+    assert_eq!(oms.on_closed("ee-b0-1", "Filled", qty("0.010"), 5), Some(OrderState::Filled));
+}
+
+#[test]
+fn rejects_close_the_order_but_transport_errors_do_not() {
+    let mut oms = OrderManager::new();
+    oms.track(&symbol(), &request("ee-b0-1"), 0);
+    oms.track(&symbol(), &request("ee-a0-1"), 0);
+
+    let rejected = Err(ExchangeError::Rejected { exchange: Exchanges::Bybit, code: 170218, msg: "post only".to_string() });
+    oms.on_place_result("ee-b0-1", &rejected, 1);
+    oms.on_place_result("ee-a0-1", &Err(ExchangeError::Transport("timed out".to_string())), 1);
+
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::Rejected);
+    // May have reached the venue
+    assert_eq!(state(&oms, "ee-a0-1"), OrderState::PendingNew);
+    let working: Vec<_> = oms.working_orders(&symbol()).into_iter().map(|order| order.client_order_id.clone()).collect();
+    assert_eq!(working, vec!["ee-a0-1".to_string()]);
+}
+
+#[test]
+fn only_unknown_order_closes_on_a_failed_cancel() {
+    let mut oms = OrderManager::new();
+    let failures = [
+        ExchangeError::RateLimited { exchange: Exchanges::Bybit, code: 10006, msg: "too many visits".to_string(), retry_after_ms: None },
+        ExchangeError::Timestamp { exchange: Exchanges::Bybit, code: 10002, msg: "outside recv_window".to_string() },
+        ExchangeError::Auth { exchange: Exchanges::Bybit, code: 10003, msg: "invalid api key".to_string() },
+        ExchangeError::Decode("unexpected body".to_string()),
+        ExchangeError::Rejected { exchange: Exchanges::Bybit, code: 10001, msg: "params error".to_string() },
+        // Binance's unknown order code means nothing on Bybit
+        ExchangeError::Rejected { exchange: Exchanges::Bybit, code: -2011, msg: "unknown order".to_string() },
+    ];
+    for (i, failure) in failures.into_iter().enumerate() {
+        let id = format!("ee-b{}-1", i);
+        oms.track(&symbol(), &request(&id), 0);
+        oms.on_place_result(&id, &Ok(ack(&id)), 1);
+        oms.on_cancel_sent(&id, 2);
+        oms.on_cancel_result(&id, &Err(failure), 3);
+        assert_eq!(state(&oms, &id), OrderState::PendingCancel, "{}", id);
+    }
+
+    oms.track(&symbol(), &request("ee-a0-1"), 0);
+    oms.on_cancel_sent("ee-a0-1", 1);
+    let gone = Err(ExchangeError::Rejected { exchange: Exchanges::Bybit, code: 110001, msg: "order not exists".to_string() });
+    oms.on_cancel_result("ee-a0-1", &gone, 2);
+    assert_eq!(state(&oms, "ee-a0-1"), OrderState::Cancelled);
+}
+
+#[test]
+fn reconcile_reopens_orders_still_listed_but_keeps_cancels() {
+    let mut oms = OrderManager::new();
+    for id in ["ee-b0-closed", "ee-b1-cancelling"] {
+        oms.track(&symbol(), &request(id), 0);
+        oms.on_place_result(id, &Ok(ack(id)), 0);
+    }
+
+    // Closed on a stale stream update, and a cancel the venue refused for a rate limit
+    oms.on_closed("ee-b0-closed", "Cancelled", qty("0"), 1);
+    oms.on_cancel_sent("ee-b1-cancelling", 1);
+    let limited = Err(ExchangeError::RateLimited { exchange: Exchanges::Bybit, code: 10006, msg: "too many visits".to_string(), retry_after_ms: None });
+    oms.on_cancel_result("ee-b1-cancelling", &limited, 2);
+    assert_eq!(state(&oms, "ee-b0-closed"), OrderState::Cancelled);
+    assert_eq!(state(&oms, "ee-b1-cancelling"), OrderState::PendingCancel);
+
+    let open_orders = vec![open("ee-b0-closed", "0"), open("ee-b1-cancelling", "0.004")];
+    assert!(oms.reconcile(&symbol(), &open_orders, "ee-", 3).is_empty());
+    assert_eq!(state(&oms, "ee-b0-closed"), OrderState::New);
+    // Still to be cancelled, with the fill the venue reports
+    assert_eq!(state(&oms, "ee-b1-cancelling"), OrderState::PendingCancel);
+    assert_eq!(oms.order("ee-b1-cancelling").unwrap().filled_qty, qty("0.004"));
+    assert_eq!(oms.working_orders(&symbol()).len(), 2);
+}
+
+#[test]
+fn fills_counted_by_a_reconcile_are_not_counted_again() {
+    let mut oms = OrderManager::new();
+    oms.track(&symbol(), &request("ee-b0-1"), 0);
+    oms.on_place_result("ee-b0-1", &Ok(ack("ee-b0-1")), 0);
+
+    // The venue lists two fills before the stream delivers them
+    oms.reconcile(&symbol(), &[open("ee-b0-1", "0.006")], "ee-", 1);
+    oms.on_fill(&fill("ee-b0-1", "0.004", "0.004"), 2);
+    oms.on_fill(&fill("ee-b0-1", "0.002", "0.006"), 3);
+    assert_eq!(oms.order("ee-b0-1").unwrap().filled_qty, qty("0.006"));
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::PartiallyFilled);
+
+    // Delivered twice
+    oms.on_fill(&fill("ee-b0-1", "0.004", "0.010"), 4);
+    oms.on_fill(&fill("ee-b0-1", "0.004", "0.010"), 5);
+    assert_eq!(oms.order("ee-b0-1").unwrap().filled_qty, qty("0.010"));
+    assert_eq!(state(&oms, "ee-b0-1"), OrderState::Filled);
+}
+
+#[test]
+fn reconcile_closes_missing_orders_and_returns_orphans() {
+    let mut oms = OrderManager::new();
+    oms.track(&symbol(), &request("ee-b0-listed"), 0);
+    oms.track(&symbol(), &request("ee-b1-gone"), 0);
+    oms.track(&symbol(), &request("ee-b2-just-sent"), RECONCILE_GRACE_MS);
+    oms.on_place_result("ee-b0-listed", &Ok(ack("ee-b0-listed")), 0);
+    oms.on_place_result("ee-b1-gone", &Ok(ack("ee-b1-gone")), 0);
+
+    let open_orders = vec![open("ee-b0-listed", "0.002"), open("ee-a0-old-run", "0"), open("manual", "0")];
+    let orphans = oms.reconcile(&symbol(), &open_orders, "ee-", RECONCILE_GRACE_MS + 1);
+
+    assert_eq!(orphans.len(), 1);
+    assert_eq!(orphans[0].client_order_id, "ee-a0-old-run");
+    assert_eq!(state(&oms, "ee-b0-listed"), OrderState::PartiallyFilled);
+    assert_eq!(state(&oms, "ee-b1-gone"), OrderState::Cancelled);
+    assert_eq!(state(&oms, "ee-b2-just-sent"), OrderState::PendingNew);
+
+    // Closed orders are kept a while for late updates, then forgotten
+    oms.prune(RECONCILE_GRACE_MS + 2);
+    assert!(oms.order("ee-b1-gone").is_some());
+    oms.prune(RECONCILE_GRACE_MS + 120_000);
+    assert!(oms.order("ee-b1-gone").is_none());
+    assert!(oms.order("ee-b0-listed").is_some());
+}
@@ -0,0 +1,218 @@
+mod common;
+mod mock_venue;
+
+use common::{price, qty};
+use mock_venue::{next_matching, MockVenue};
+use rust_qts::data_structure::QTSOrderRequest;
+use rust_qts::decimal::Qty;
+use rust_qts::enums::{Environment, Exchanges, OrderType, Side, TimeInForce};
+use rust_qts::exchange::base_rest::ExchangeRest;
+use rust_qts::exchange::error::ExchangeError;
+use rust_qts::strategy::eye::ee::ElectronicEye;
+use rust_qts::strategy::eye::events::{self, ExecutionEvent, Leg};
+use rust_qts::strategy::eye::feeds::{self, FeedConfig};
+use rust_qts::strategy::eye::paper::PaperExchange;
+use rust_qts::strategy::eye::params::EEConfig;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+
+fn order(client_order_id: &str, side: Side, order_type: OrderType, limit: Option<&str>, time_in_force: TimeInForce) -> QTSOrderRequest {
+    QTSOrderRequest {
+        side,
+        order_type,
+        price: limit.map(price),
+        qty: qty("0.1"),
+        time_in_force,
+        client_order_id: client_order_id.to_string(),
+        reduce_only: false,
+    }
+}
+
+/// Paper venue on the quote leg with the Bybit mock behind it for market data
+async fn paper() -> (MockVenue, PaperExchange, mpsc::UnboundedReceiver<ExecutionEvent>) {
+    let venue = MockVenue::start(Exchanges::Bybit).await;
+    venue.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "1")]);
+    let (tx, rx) = mpsc::unbounded_channel();
+    let paper = PaperExchange::new(venue.rest_client(), venue.symbol("BTCUSDT"), Leg::Quote, tx);
+    paper.on_top_of_book((Some(price("65000")), Some(price("65000.5"))));
+    (venue, paper, rx)
+}
+
+fn next_fill(rx: &mut mpsc::UnboundedReceiver<ExecutionEvent>) -> rust_qts::data_structure::QTSFill {
+    match rx.try_recv() {
+        Ok(ExecutionEvent::Fill { leg: Leg::Quote, fill }) => fill,
+        other => panic!("expected a fill, got {:?}", other),
+    }
+}
+
+fn next_closed(rx: &mut mpsc::UnboundedReceiver<ExecutionEvent>) -> (String, String, Qty) {
+    match rx.try_recv() {
+        Ok(ExecutionEvent::OrderClosed { client_order_id, status, filled_qty, .. }) => (client_order_id, status, filled_qty),
+        other => panic!("expected a closed order, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn resting_bid_fills_as_maker_when_the_ask_comes_down() {
+    let (venue, paper, mut rx) = paper().await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    paper.place_order(&symbol, &order("bid-1", Side::Buy, OrderType::Limit, Some("64990"), TimeInForce::PostOnly)).await.unwrap();
+    assert_eq!(paper.get_open_orders(&symbol).await.unwrap().len(), 1);
+    assert!(rx.try_recv().is_err());
+
+    // Touching the bid isn't enough, the ask has to reach it
+    paper.on_top_of_book((Some(price("64990")), Some(price("64995"))));
+    assert!(rx.try_recv().is_err());
+    paper.on_top_of_book((Some(price("64985")), Some(price("64990"))));
+
+    let fill = next_fill(&mut rx);
+    assert_eq!((fill.client_order_id.as_str(), fill.side, fill.price, fill.qty), ("bid-1", Side::Buy, price("64990"), qty("0.1")));
+    assert!(fill.is_maker);
+    assert!((fill.fee - 6499.0 * 0.0002).abs() < 1e-9);
+    assert_eq!(next_closed(&mut rx), ("bid-1".to_string(), "Filled".to_string(), qty("0.1")));
+
+    let position = &paper.get_positions(&symbol).await.unwrap()[0];
+    assert_eq!((position.size, position.entry_price), (qty("0.1"), price("64990")));
+    assert!(paper.get_open_orders(&symbol).await.unwrap().is_empty());
+
+    // Nothing reached the real venue
+    assert!(venue.requests_to("/v5/order/create").is_empty());
+}
+
+#[tokio::test]
+async fn resting_orders_fill_on_trades_at_or_through_their_price() {
+    let (venue, paper, mut rx) = paper().await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    paper.place_order(&symbol, &order("bid-1", Side::Buy, OrderType::Limit, Some("64990"), TimeInForce::PostOnly)).await.unwrap();
+    paper.place_order(&symbol, &order("ask-1", Side::Sell, OrderType::Limit, Some("65010"), TimeInForce::PostOnly)).await.unwrap();
+
+    // Prints between the two leave both resting
+    paper.on_trades(&[price("64995"), price("65005")]);
+    assert!(rx.try_recv().is_err());
+
+    // A print at the bid reaches it, the book never did
+    paper.on_trades(&[price("64990")]);
+    let fill = next_fill(&mut rx);
+    assert_eq!((fill.client_order_id.as_str(), fill.price, fill.qty, fill.is_maker), ("bid-1", price("64990"), qty("0.1"), true));
+    assert_eq!(next_closed(&mut rx), ("bid-1".to_string(), "Filled".to_string(), qty("0.1")));
+
+    // Any print through the ask in a batch
+    paper.on_trades(&[price("65003"), price("65012")]);
+    let fill = next_fill(&mut rx);
+    assert_eq!((fill.client_order_id.as_str(), fill.price), ("ask-1", price("65010")));
+    next_closed(&mut rx);
+    assert!(paper.get_open_orders(&symbol).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn book_feeds_stream_public_trades_for_the_paper_venues() {
+    for exchange in [Exchanges::Bybit, Exchanges::Binance] {
+        let venue = MockVenue::start(exchange.clone()).await;
+        venue.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "1")]);
+        let config = FeedConfig { environment: Environment::Custom(venue.rest_url()), ws_url: Some(venue.ws_url()), credentials: None };
+        let (senders, mut receivers) = events::channels();
+        feeds::spawn_book_feed(Leg::Quote, venue.symbol("BTCUSDT"), &config, true, senders.market_data, senders.feed_state).await.unwrap();
+
+        // Binance only syncs once a diff comes in
+        venue.set_book("BTCUSDT", &[("65000", "2")], &[("65000.5", "1")]);
+        next_matching(&mut receivers.market_data, |update| update.top_of_book.0.is_some()).await;
+
+        venue.print_trade("BTCUSDT", Side::Sell, "65000", "0.2");
+        let update = next_matching(&mut receivers.market_data, |update| !update.trades.is_empty()).await;
+        assert_eq!(update.trades, vec![price("65000")], "{:?}", exchange);
+        assert_eq!(update.top_of_book, (Some(price("65000")), Some(price("65000.5"))));
+    }
+}
+
+#[tokio::test]
+async fn marketable_orders_take_the_touch() {
+    let (venue, paper, mut rx) = paper().await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    paper.place_order(&symbol, &order("buy-1", Side::Buy, OrderType::Market, None, TimeInForce::IOC)).await.unwrap();
+    let fill = next_fill(&mut rx);
+    assert_eq!((fill.price, fill.is_maker), (price("65000.5"), false));
+    assert!((fill.fee - 6500.05 * 0.00055).abs() < 1e-9);
+    assert_eq!(next_closed(&mut rx).1, "Filled");
+
+    // Closing 10 lower realizes the loss on top of both fees
+    paper.on_top_of_book((Some(price("64990.5")), Some(price("64991"))));
+    paper.place_order(&symbol, &order("sell-1", Side::Sell, OrderType::Limit, Some("64990"), TimeInForce::IOC)).await.unwrap();
+    assert_eq!(next_fill(&mut rx).price, price("64990.5"));
+    next_closed(&mut rx);
+
+    let (realized, fees) = paper.pnl();
+    assert!((realized - -1.0).abs() < 1e-9);
+    assert!((fees - (6500.05 + 6499.05) * 0.00055).abs() < 1e-9);
+    assert!(paper.get_positions(&symbol).await.unwrap()[0].size.is_zero());
+}
+
+#[tokio::test]
+async fn ioc_away_from_the_touch_is_closed_unfilled() {
+    let (venue, paper, mut rx) = paper().await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    paper.place_order(&symbol, &order("ioc-1", Side::Buy, OrderType::Limit, Some("64900"), TimeInForce::IOC)).await.unwrap();
+    assert_eq!(next_closed(&mut rx), ("ioc-1".to_string(), "Cancelled".to_string(), Qty::ZERO));
+    assert!(paper.get_open_orders(&symbol).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn crossing_post_only_is_rejected() {
+    let (venue, paper, mut rx) = paper().await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    let result = paper.place_order(&symbol, &order("po-1", Side::Sell, OrderType::Limit, Some("65000"), TimeInForce::PostOnly)).await;
+    assert!(matches!(result, Err(ExchangeError::Rejected { .. })));
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn cancels_are_reported_like_the_stream() {
+    let (venue, paper, mut rx) = paper().await;
+    let symbol = venue.symbol("BTCUSDT");
+
+    paper.place_order(&symbol, &order("ask-1", Side::Sell, OrderType::Limit, Some("65010"), TimeInForce::GTC)).await.unwrap();
+    paper.place_order(&symbol, &order("ask-2", Side::Sell, OrderType::Limit, Some("65020"), TimeInForce::GTC)).await.unwrap();
+
+    paper.cancel_order(&symbol, "ask-1").await.unwrap();
+    assert_eq!(next_closed(&mut rx), ("ask-1".to_string(), "Cancelled".to_string(), Qty::ZERO));
+    // Gone is reported like the venue does it
+    assert!(paper.cancel_order(&symbol, "ask-1").await.unwrap_err().is_order_not_found());
+
+    paper.cancel_all_orders(&symbol).await.unwrap();
+    assert_eq!(next_closed(&mut rx).0, "ask-2");
+    assert!(venue.requests_to("/v5/order/cancel").is_empty());
+}
+
+#[tokio::test]
+async fn debug_mode_runs_on_real_data_without_touching_the_venues() {
+    let bybit = MockVenue::start(Exchanges::Bybit).await;
+    bybit.set_book("BTCUSDT", &[("65000", "1")], &[("65000.5", "1")]);
+    let binance = MockVenue::start(Exchanges::Binance).await;
+    binance.set_book("BTCUSDT", &[("65001", "1")], &[("65001.5", "1")]);
+
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.run_mode.debug = true;
+    let ee = ElectronicEye::with_clients(config, bybit.rest_client(), binance.rest_client());
+    let run = tokio::spawn(ee.run());
+
+    // Quote leg trades down through the bids, the paper fills get hedged on paper
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    bybit.set_book("BTCUSDT", &[("64900", "1")], &[("64900.5", "1")]);
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    run.abort();
+
+    assert!(!bybit.requests_to("/v5/market/orderbook").is_empty());
+    assert!(!binance.requests_to("/fapi/v1/depth").is_empty());
+    for path in ["/v5/order/create", "/v5/order/create-batch", "/v5/order/cancel", "/v5/order/cancel-batch", "/v5/order/cancel-all", "/v5/position/list"] {
+        assert!(bybit.requests_to(path).is_empty(), "{} reached the venue", path);
+    }
+    for path in ["/fapi/v1/order", "/fapi/v1/allOpenOrders", "/fapi/v3/positionRisk"] {
+        assert!(binance.requests_to(path).is_empty(), "{} reached the venue", path);
+    }
+}
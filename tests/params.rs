@@ -0,0 +1,93 @@
+use rust_qts::enums::HedgeMode;
+use rust_qts::strategy::eye::params::{ConfigError, EEConfig};
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+
+fn problems(config: &EEConfig) -> Vec<String> {
+    match config.validate() {
+        Ok(()) => Vec::new(),
+        Err(ConfigError::Invalid(problems)) => problems.iter().map(|problem| problem.to_string()).collect(),
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn fixture_is_valid() {
+    let config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    assert_eq!(config.hedge_params.hedge_mode, HedgeMode::Active);
+    assert!(problems(&config).is_empty());
+}
+
+#[test]
+fn every_problem_is_reported_with_its_path() {
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.quote_params.clip_size = -0.01;
+    config.quote_params.bid_on = false;
+    config.quote_params.ask_on = false;
+    config.quote_instrument.min_tick = "0".parse().unwrap();
+    config.hedge_instrument = config.quote_instrument.clone();
+    config.hedge_params.hedge_ratio = f64::NAN;
+
+    assert_eq!(problems(&config), vec![
+        "quote_instrument.min_tick: must be greater than 0",
+        "hedge_instrument.min_tick: must be greater than 0",
+        "hedge_instrument: must not be the same instrument as quote_instrument",
+        "hedge_params.hedge_ratio: must be a number",
+        "quote_params.ask_on: bid_on and ask_on are both off, nothing would be quoted",
+        "quote_params.clip_size: must be greater than 0",
+    ]);
+}
+
+#[test]
+fn sizes_and_intervals_have_to_fit_together() {
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.quote_params.clip_size = 0.0004;
+    config.quote_params.relist_interval = 0.0003;
+    config.quote_params.margin_ratio_threshold = 1.5;
+    config.quote_params.quote_levels = 0;
+
+    assert_eq!(problems(&config), vec![
+        "quote_params.clip_size: rounds to nothing at qty_decimal 3",
+        "quote_params.quote_levels: must be at least 1",
+        "quote_params.margin_ratio_threshold: must be at most 1, the account is liquidated at 1",
+    ]);
+
+    // A single level has nothing to overlap with
+    config.quote_params.clip_size = 0.01;
+    config.quote_params.quote_levels = 1;
+    config.quote_params.margin_ratio_threshold = 0.8;
+    assert!(problems(&config).is_empty());
+}
+
+#[test]
+fn loading_rejects_what_validation_finds() {
+    let yaml = CONFIG.replace("clip_size: 0.01", "clip_size: -0.01").replace("bid_on: true", "bid_on: false").replace("ask_on: true", "ask_on: false");
+    let error = EEConfig::from_yaml_str(&yaml).unwrap_err();
+    assert!(matches!(&error, ConfigError::Invalid(problems) if problems.len() == 2));
+    assert!(error.to_string().contains("quote_params.clip_size: must be greater than 0"));
+}
+
+#[test]
+fn unknown_hedge_mode_fails_to_parse() {
+    let error = EEConfig::from_yaml_str(&CONFIG.replace("hedge_mode: 1", "hedge_mode: 7")).unwrap_err();
+    assert!(matches!(error, ConfigError::Parse(_)));
+    let message = error.to_string();
+    assert!(message.contains("hedge_params: hedge_mode must be 0, 1 or 2, got 7"), "{}", message);
+
+    let config = EEConfig::from_yaml_str(&CONFIG.replace("hedge_mode: 1", "hedge_mode: 2")).unwrap();
+    assert_eq!(config.hedge_params.hedge_mode, HedgeMode::Passive);
+}
+
+#[test]
+fn dcp_window_has_to_be_one_bybit_takes() {
+    let mut config = EEConfig::from_yaml_str(CONFIG).unwrap();
+    config.quote_params.dcp_window = 2;
+    assert_eq!(problems(&config), vec!["quote_params.dcp_window: must be 0 or between 3 and 300 seconds"]);
+
+    config.quote_params.dcp_window = 10;
+    assert!(problems(&config).is_empty());
+
+    // Hedge leg is the Bybit one now
+    std::mem::swap(&mut config.quote_instrument, &mut config.hedge_instrument);
+    assert_eq!(problems(&config), vec!["quote_params.dcp_window: disconnect-cancel-all is only on Bybit, must be 0"]);
+}
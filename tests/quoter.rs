@@ -0,0 +1,147 @@
+mod common;
+
+use common::{price, qty};
+use rust_qts::data_structure::{QTSOpenOrder, Symbol};
+use rust_qts::decimal::{Price, Qty};
+use rust_qts::enums::{Exchanges, InstrumentType, Side};
+use rust_qts::strategy::eye::params::EEConfig;
+use rust_qts::strategy::eye::quoter::{build_ladder, diff_orders, reduce_only_ladder, DesiredOrder};
+use rust_qts::strategy::strategy_structs::QuoteParams;
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+
+fn params() -> QuoteParams {
+    EEConfig::from_yaml_str(CONFIG).unwrap().quote_params
+}
+
+fn symbol() -> Symbol {
+    Symbol {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Bybit,
+        instrument_type: InstrumentType::Perp,
+        min_tick: price("0.1"),
+        qty_decimal: 3,
+    }
+}
+
+fn levels(orders: &[DesiredOrder], side: Side) -> Vec<(Price, Qty)> {
+    orders.iter().filter(|order| order.side == side).map(|order| (order.price, order.qty)).collect()
+}
+
+fn resting(order: &DesiredOrder, id: &str) -> QTSOpenOrder {
+    QTSOpenOrder {
+        symbol: "BTCUSDT".to_string(),
+        exchange: Exchanges::Bybit,
+        order_id: id.to_string(),
+        client_order_id: format!("ee-{}", id),
+        side: order.side,
+        price: order.price,
+        qty: order.qty,
+        filled_qty: Qty::ZERO,
+        status: "New".to_string(),
+        reduce_only: false,
+        time: 0,
+    }
+}
+
+#[test]
+fn ladder_steps_out_from_the_min_spread() {
+    let ladder = build_ladder(&params(), &symbol(), price("65000"), Qty::ZERO, (None, None));
+
+    // 2.5bp either side, then 2bp per level, growing by clip_step_size
+    assert_eq!(levels(&ladder, Side::Buy), vec![(price("64983.7"), qty("0.01")), (price("64970.7"), qty("0.02"))]);
+    assert_eq!(levels(&ladder, Side::Sell), vec![(price("65016.3"), qty("0.01")), (price("65029.3"), qty("0.02"))]);
+}
+
+#[test]
+fn offsets_skew_the_inner_quotes() {
+    let mut params = params();
+    params.bid_offset = 0.001;
+    params.ask_offset = -0.0002;
+    params.quote_levels = 1;
+
+    // 12bp apart already, so min_spread does not widen them
+    let ladder = build_ladder(&params, &symbol(), price("65000"), Qty::ZERO, (None, None));
+    assert_eq!(levels(&ladder, Side::Buy), vec![(price("64935"), qty("0.01"))]);
+    assert_eq!(levels(&ladder, Side::Sell), vec![(price("64987"), qty("0.01"))]);
+
+    // Crossed at 65065 / 64909, widened back out to min_spread around 64987
+    params.bid_offset = -0.001;
+    params.ask_offset = -0.0014;
+    let ladder = build_ladder(&params, &symbol(), price("65000"), Qty::ZERO, (None, None));
+    assert_eq!(levels(&ladder, Side::Buy), vec![(price("64970.7"), qty("0.01"))]);
+    assert_eq!(levels(&ladder, Side::Sell), vec![(price("65003.3"), qty("0.01"))]);
+}
+
+#[test]
+fn quotes_never_cross_the_quote_book() {
+    let ladder = build_ladder(&params(), &symbol(), price("65000"), Qty::ZERO, (Some(price("65020")), Some(price("64980"))));
+
+    // Both inner and outer bids would be at or through 64980, they collapse onto one tick
+    assert_eq!(levels(&ladder, Side::Buy), vec![(price("64979.9"), qty("0.01")), (price("64970.7"), qty("0.02"))]);
+    assert_eq!(levels(&ladder, Side::Sell), vec![(price("65020.1"), qty("0.01")), (price("65029.3"), qty("0.02"))]);
+}
+
+#[test]
+fn sides_switch_off_by_flag_and_at_max_position() {
+    let mut params = params();
+    params.ask_on = false;
+    let ladder = build_ladder(&params, &symbol(), price("65000"), Qty::ZERO, (None, None));
+    assert!(levels(&ladder, Side::Sell).is_empty());
+    assert_eq!(levels(&ladder, Side::Buy).len(), 2);
+
+    params.ask_on = true;
+    let ladder = build_ladder(&params, &symbol(), price("65000"), qty("0.5"), (None, None));
+    assert!(levels(&ladder, Side::Buy).is_empty());
+    assert_eq!(levels(&ladder, Side::Sell).len(), 2);
+
+    let ladder = build_ladder(&params, &symbol(), price("65000"), qty("-0.5"), (None, None));
+    assert!(levels(&ladder, Side::Sell).is_empty());
+    assert_eq!(levels(&ladder, Side::Buy).len(), 2);
+}
+
+#[test]
+fn reduce_only_quotes_the_position_back_to_flat() {
+    let ladder = build_ladder(&params(), &symbol(), price("65000"), Qty::ZERO, (None, None));
+    assert!(reduce_only_ladder(ladder.clone(), Qty::ZERO).is_empty());
+
+    // Long 0.015, inner ask takes 0.01 and the next level only what is left
+    let reduced = reduce_only_ladder(ladder.clone(), qty("0.015"));
+    assert!(levels(&reduced, Side::Buy).is_empty());
+    assert_eq!(levels(&reduced, Side::Sell), vec![(price("65016.3"), qty("0.01")), (price("65029.3"), qty("0.005"))]);
+
+    let reduced = reduce_only_ladder(ladder, qty("-0.5"));
+    assert!(levels(&reduced, Side::Sell).is_empty());
+    assert_eq!(levels(&reduced, Side::Buy), vec![(price("64983.7"), qty("0.01")), (price("64970.7"), qty("0.02"))]);
+}
+
+#[test]
+fn diff_keeps_orders_within_tolerance_and_relists_the_rest() {
+    let ladder = build_ladder(&params(), &symbol(), price("65000"), Qty::ZERO, (None, None));
+    let current: Vec<QTSOpenOrder> = ladder.iter().enumerate().map(|(index, order)| resting(order, &index.to_string())).collect();
+
+    // Nothing to do when the book has not moved
+    assert!(diff_orders(&ladder, &current, price("6.5")).is_empty());
+
+    // Fair moves 5, inside tolerance, still nothing to do
+    let moved = build_ladder(&params(), &symbol(), price("65005"), Qty::ZERO, (None, None));
+    assert!(diff_orders(&moved, &current, price("6.5")).is_empty());
+
+    // Fair moves 10, every level is relisted
+    let moved = build_ladder(&params(), &symbol(), price("65010"), Qty::ZERO, (None, None));
+    let diff = diff_orders(&moved, &current, price("6.5"));
+    assert_eq!(diff.cancels.len(), 4);
+    assert_eq!(diff.places, moved);
+}
+
+#[test]
+fn diff_relists_a_partially_filled_level() {
+    let ladder = build_ladder(&params(), &symbol(), price("65000"), Qty::ZERO, (None, None));
+    let mut current: Vec<QTSOpenOrder> = ladder.iter().enumerate().map(|(index, order)| resting(order, &index.to_string())).collect();
+    current[0].filled_qty = qty("0.004");
+
+    let diff = diff_orders(&ladder, &current, price("6.5"));
+    assert_eq!(diff.cancels.len(), 1);
+    assert_eq!(diff.cancels[0].order_id, "0");
+    assert_eq!(diff.places, vec![ladder[0].clone()]);
+}
@@ -0,0 +1,103 @@
+mod mock_venue;
+
+use mock_venue::{venues, WAIT};
+use rust_qts::enums::{HedgeMode, Side};
+use rust_qts::strategy::eye::ee::ElectronicEye;
+use rust_qts::strategy::eye::params::EEConfig;
+use rust_qts::strategy::eye::reload::{config_diff, ConfigChange};
+use std::time::Duration;
+
+const CONFIG: &str = include_str!("fixtures/ee_config.yaml");
+
+fn config() -> EEConfig {
+    EEConfig::from_yaml_str(CONFIG).unwrap()
+}
+
+#[test]
+fn diff_lists_each_changed_field_by_path() {
+    let old = config();
+    assert!(config_diff(&old, &old).is_empty());
+
+    let mut new = config();
+    new.quote_params.min_spread = 0.0006;
+    new.quote_params.bid_on = false;
+    new.hedge_params.hedge_mode = HedgeMode::Passive;
+
+    let changes = config_diff(&old, &new);
+    let lines: Vec<String> = changes.iter().map(ConfigChange::to_string).collect();
+    assert_eq!(lines, vec![
+        "hedge_params.hedge_mode: 1 -> 2",
+        "quote_params.bid_on: true -> false",
+        "quote_params.min_spread: 0.0005 -> 0.0006",
+    ]);
+    assert!(changes.iter().all(|change| !change.needs_restart()));
+}
+
+#[test]
+fn instruments_strategy_and_paper_mode_need_a_restart() {
+    let old = config();
+    let mut new = config();
+    new.quote_instrument.symbol = "ETHUSDT".to_string();
+    new.hedge_instrument.min_tick = "0.01".parse().unwrap();
+    new.strategy.strategy_name = "renamed".to_string();
+    new.run_mode.debug = true;
+    new.run_mode.risk_management_mode = true;
+
+    let restart: Vec<String> = config_diff(&old, &new).into_iter()
+        .filter(ConfigChange::needs_restart)
+        .map(|change| change.path)
+        .collect();
+    assert_eq!(restart, vec!["hedge_instrument.min_tick", "quote_instrument.symbol", "run_mode.debug", "strategy.strategy_name"]);
+}
+
+#[tokio::test]
+async fn restart_changes_reject_the_whole_update() {
+    let (bybit, binance) = venues().await;
+    let mut ee = ElectronicEye::with_clients(config(), bybit.rest_client(), binance.rest_client());
+    assert!(ee.recover().await);
+    ee.process_book_updates().await;
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 4);
+
+    let mut new = config();
+    new.quote_params.bid_on = false;
+    new.hedge_instrument.symbol = "ETHUSDT".to_string();
+    assert!(!ee.process_config_updates(new).await);
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 4);
+
+    let mut new = config();
+    new.quote_params.bid_on = false;
+    assert!(ee.process_config_updates(new).await);
+    assert!(bybit.open_orders("BTCUSDT").iter().all(|order| order.side == Side::Sell));
+}
+
+async fn eventually(what: &str, mut condition: impl FnMut() -> bool) {
+    tokio::time::timeout(WAIT, async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }).await.unwrap_or_else(|_| panic!("timed out waiting for {}", what));
+}
+
+#[tokio::test]
+async fn edits_to_the_watched_file_are_applied() {
+    let (bybit, binance) = venues().await;
+    let path = std::env::temp_dir().join(format!("ee_config_{}.yaml", std::process::id()));
+    std::fs::write(&path, CONFIG).unwrap();
+    let path = path.to_str().unwrap().to_string();
+
+    let ee = ElectronicEye::with_clients(EEConfig::from_yaml_file(&path).unwrap(), bybit.rest_client(), binance.rest_client());
+    ee.watch_config(&path);
+    tokio::spawn(ee.run());
+    eventually("the first ladder", || bybit.open_orders("BTCUSDT").len() == 4).await;
+
+    // Half written file is skipped, the running config stays
+    std::fs::write(&path, "quote_params: [").unwrap();
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 4);
+
+    std::fs::write(&path, CONFIG.replace("ask_on: true", "ask_on: false")).unwrap();
+    eventually("the asks to be pulled", || bybit.open_orders("BTCUSDT").iter().all(|order| order.side == Side::Buy)).await;
+    assert_eq!(bybit.open_orders("BTCUSDT").len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
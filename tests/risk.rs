@@ -0,0 +1,31 @@
+use rust_qts::strategy::eye::events::Leg;
+use rust_qts::strategy::eye::risk::MarginMonitor;
+
+#[test]
+fn either_leg_over_the_threshold_means_reduce_only() {
+    let mut monitor = MarginMonitor::new(0.8);
+    assert!(!monitor.on_margin(Leg::Quote, 0.5));
+    assert!(!monitor.reduce_only());
+
+    assert!(monitor.on_margin(Leg::Hedge, 0.8));
+    assert!(monitor.reduce_only());
+    assert!(!monitor.on_margin(Leg::Quote, 0.9));
+
+    // Both legs have to be back under it
+    assert!(!monitor.on_margin(Leg::Hedge, 0.1));
+    assert!(monitor.on_margin(Leg::Quote, 0.79));
+    assert!(!monitor.reduce_only());
+    assert_eq!(monitor.margin_ratio(Leg::Quote), Some(0.79));
+}
+
+#[test]
+fn new_threshold_applies_to_the_last_readings() {
+    let mut monitor = MarginMonitor::new(0.8);
+    monitor.on_margin(Leg::Quote, 0.6);
+    assert!(monitor.set_threshold(0.5));
+    assert!(monitor.reduce_only());
+
+    assert!(monitor.clear());
+    assert!(!monitor.reduce_only());
+    assert_eq!(monitor.margin_ratio(Leg::Quote), None);
+}